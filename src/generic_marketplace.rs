@@ -1,3 +1,4 @@
+use crate::outpost_factory::openhub::OpenHub;
 use scrypto::prelude::*;
 
 #[derive(ScryptoSbor, NonFungibleData)]
@@ -19,6 +20,9 @@ mod generic_marketplace {
         marketplace_fee: Decimal,
         fee_vaults: KeyValueStore<ResourceAddress, Vault>,
         mint_fee: Decimal,
+        /// Outposts must be instances of the OpenTrader blueprint in this package, unless registered in the open hub
+        outpost_package: PackageAddress,
+        open_hub: Option<Global<OpenHub>>,
     }
 
     impl GenericMarketplace {
@@ -65,6 +69,8 @@ mod generic_marketplace {
                 marketplace_fee,
                 fee_vaults: KeyValueStore::<ResourceAddress, Vault>::new_with_registered_type(),
                 mint_fee,
+                outpost_package: Runtime::package_address(),
+                open_hub: None,
             }
             .instantiate()
             .prepare_to_globalize(OwnerRole::Fixed(rule!(require(admin_key))))
@@ -110,6 +116,18 @@ mod generic_marketplace {
             fee
         }
 
+        /// Sets the open hub used to recognise genuine Outposts. Outposts registered in the hub are accepted even when they were
+        /// instantiated from a different package than the one this marketplace expects.
+        pub fn set_open_hub(&mut self, open_hub: Option<ComponentAddress>, proof: Proof) {
+            proof.check(self.marketplace_admin);
+            self.open_hub = open_hub.map(Global::<OpenHub>::from);
+        }
+
+        pub fn set_outpost_package(&mut self, outpost_package: PackageAddress, proof: Proof) {
+            proof.check(self.marketplace_admin);
+            self.outpost_package = outpost_package;
+        }
+
         pub fn purchase_royal_listing(
             &mut self,
             nfgid: NonFungibleGlobalId,
@@ -117,6 +135,8 @@ mod generic_marketplace {
             open_sale_address: Global<AnyComponent>,
             account_recipient: Global<Account>,
         ) -> (Bucket, Bucket) {
+            self.assert_genuine_outpost(&open_sale_address);
+
             let nflid = NonFungibleLocalId::integer(1u64.into());
             let proof_creation: Proof = self
                 .marketplace_listing_key_vault
@@ -127,9 +147,11 @@ mod generic_marketplace {
             let fee_and_nft: (Bucket, Bucket, Option<Bucket>) =
                 open_sale_address.call_raw::<(Bucket, Bucket, Option<Bucket>)>(
                     "purchase_royal_listing",
-                    scrypto_args!(nfgid, payment, proof_creation, account_recipient),
+                    scrypto_args!(nfgid.clone(), payment, proof_creation, account_recipient),
                 );
 
+            Self::assert_nfts_returned(&[&fee_and_nft.0], &[nfgid]);
+
             let is_fee_returned = fee_and_nft.2.is_some();
 
             if is_fee_returned {
@@ -176,6 +198,8 @@ mod generic_marketplace {
             let mut all_nfts: Vec<Bucket> = Vec::new();
 
             for (address, nfgid, payment) in orders {
+                self.assert_genuine_outpost(&address);
+
                 grouped_orders
                     .entry(address)
                     .or_insert_with(Vec::new)
@@ -195,9 +219,16 @@ mod generic_marketplace {
 
                     let result = address.call_raw::<(Bucket, Bucket, Option<Bucket>)>(
                         "purchase_multi_royal_listings",
-                        scrypto_args!([nfgid], payment, account_recipient, proof_creation.clone()),
+                        scrypto_args!(
+                            [nfgid.clone()],
+                            payment,
+                            account_recipient,
+                            proof_creation.clone()
+                        ),
                     );
 
+                    Self::assert_nfts_returned(&[&result.0], &[nfgid]);
+
                     if result.2.is_some() {
                         let fee_returned = result.2.unwrap();
                         let fee_resource = fee_returned.resource_address();
@@ -235,13 +266,15 @@ mod generic_marketplace {
                     let result = address.call_raw::<(Bucket, Bucket, Option<Bucket>)>(
                         "purchase_multi_royal_listings",
                         scrypto_args!(
-                            nfgids,
+                            nfgids.clone(),
                             combined_payment,
                             account_recipient,
                             proof_creation.clone()
                         ),
                     );
 
+                    Self::assert_nfts_returned(&[&result.0], &nfgids);
+
                     if result.2.is_some() {
                         let fee_returned = result.2.unwrap();
                         let fee_resource = fee_returned.resource_address();
@@ -273,6 +306,7 @@ mod generic_marketplace {
             &mut self,
             orders: Vec<(Global<AnyComponent>, NonFungibleGlobalId, Decimal)>,
             mut full_payment: FungibleBucket,
+            account_recipient: Global<Account>,
         ) -> Vec<Bucket> {
             // Group orders by trader account address
             let mut grouped_orders: HashMap<
@@ -281,6 +315,8 @@ mod generic_marketplace {
             > = HashMap::new();
 
             for (address, nfgid, payment) in orders {
+                self.assert_genuine_outpost(&address);
+
                 grouped_orders
                     .entry(address)
                     .or_insert_with(Vec::new)
@@ -311,9 +347,16 @@ mod generic_marketplace {
 
                     let mut result = address.call_raw::<(Vec<Bucket>, Vec<Bucket>)>(
                         "multi_purchase_listing",
-                        scrypto_args!([nfgid], payment, proof_creation.clone()),
+                        scrypto_args!(
+                            [nfgid.clone()],
+                            payment,
+                            proof_creation.clone(),
+                            account_recipient
+                        ),
                     );
 
+                    Self::assert_nfts_returned(&result.0.iter().collect::<Vec<_>>(), &[nfgid]);
+
                     // Handle fee
                     let fee_returned = result.1.pop().unwrap();
                     let fee_resource = fee_returned.resource_address();
@@ -349,9 +392,16 @@ mod generic_marketplace {
 
                     let mut result = address.call_raw::<(Vec<Bucket>, Vec<Bucket>)>(
                         "multi_purchase_listing",
-                        scrypto_args!(nfgids, combined_payment, proof_creation.clone()),
+                        scrypto_args!(
+                            nfgids.clone(),
+                            combined_payment,
+                            proof_creation.clone(),
+                            account_recipient
+                        ),
                     );
 
+                    Self::assert_nfts_returned(&result.0.iter().collect::<Vec<_>>(), &nfgids);
+
                     // Handle fee
                     let fee_returned = result.1.pop().unwrap();
                     let fee_resource = fee_returned.resource_address();
@@ -381,6 +431,7 @@ mod generic_marketplace {
             &mut self,
             orders: Vec<(Global<AnyComponent>, NonFungibleGlobalId, Decimal)>,
             mut full_payment: FungibleBucket,
+            account_recipient: Global<Account>,
         ) -> Vec<Bucket> {
             // Group orders by trader account address
             let mut grouped_orders: HashMap<
@@ -389,6 +440,8 @@ mod generic_marketplace {
             > = HashMap::new();
 
             for (address, nfgid, payment) in orders {
+                self.assert_genuine_outpost(&address);
+
                 grouped_orders
                     .entry(address)
                     .or_insert_with(Vec::new)
@@ -419,9 +472,16 @@ mod generic_marketplace {
 
                     let mut result = address.call_raw::<(Vec<Bucket>, Vec<Bucket>)>(
                         "multi_purchase_honour_listing",
-                        scrypto_args!([nfgid], payment, proof_creation.clone()),
+                        scrypto_args!(
+                            [nfgid.clone()],
+                            payment,
+                            proof_creation.clone(),
+                            account_recipient
+                        ),
                     );
 
+                    Self::assert_nfts_returned(&result.0.iter().collect::<Vec<_>>(), &[nfgid]);
+
                     // Handle fee
                     let fee_returned = result.1.pop().unwrap();
                     let fee_resource = fee_returned.resource_address();
@@ -457,9 +517,16 @@ mod generic_marketplace {
 
                     let mut result = address.call_raw::<(Vec<Bucket>, Vec<Bucket>)>(
                         "multi_purchase_honour_listing",
-                        scrypto_args!(nfgids, combined_payment, proof_creation.clone()),
+                        scrypto_args!(
+                            nfgids.clone(),
+                            combined_payment,
+                            proof_creation.clone(),
+                            account_recipient
+                        ),
                     );
 
+                    Self::assert_nfts_returned(&result.0.iter().collect::<Vec<_>>(), &nfgids);
+
                     // Handle fee
                     let fee_returned = result.1.pop().unwrap();
                     let fee_resource = fee_returned.resource_address();
@@ -490,7 +557,10 @@ mod generic_marketplace {
             nfgid: NonFungibleGlobalId,
             payment: FungibleBucket,
             trader_account_address: Global<AnyComponent>,
+            account_recipient: Global<Account>,
         ) -> Vec<Bucket> {
+            self.assert_genuine_outpost(&trader_account_address);
+
             let nflid = NonFungibleLocalId::integer(1u64.into());
             let proof_creation: Proof = self
                 .marketplace_listing_key_vault
@@ -501,9 +571,11 @@ mod generic_marketplace {
             let mut fee_and_nft: (Vec<Bucket>, Vec<Bucket>) =
                 trader_account_address.call_raw::<(Vec<Bucket>, Vec<Bucket>)>(
                     "purchase_listing",
-                    scrypto_args!(nfgid, payment, proof_creation),
+                    scrypto_args!(nfgid.clone(), payment, proof_creation, account_recipient),
                 );
 
+            Self::assert_nfts_returned(&fee_and_nft.0.iter().collect::<Vec<_>>(), &[nfgid]);
+
             let fee_returned = fee_and_nft.1.pop().unwrap();
 
             let fee_resource = fee_returned.resource_address();
@@ -649,5 +721,46 @@ mod generic_marketplace {
                 transient_token_opt_bucket,
             )
         }
+    
+        // utility methods
+
+        /// Panics unless the component is an OpenTrader from the expected package or an Outpost registered in the open hub.
+        fn assert_genuine_outpost(&self, outpost: &Global<AnyComponent>) {
+            if let Some(open_hub) = &self.open_hub {
+                if open_hub.is_registered_outpost(outpost.address()) {
+                    return;
+                }
+            }
+
+            assert!(
+                outpost.blueprint_id() == BlueprintId::new(&self.outpost_package, "OpenTrader"),
+                "[purchase] Component is not a recognised Outpost"
+            );
+        }
+
+        /// Panics unless the NFT buckets returned by an Outpost hold exactly the requested NFTs.
+        fn assert_nfts_returned(nft_buckets: &[&Bucket], requested: &[NonFungibleGlobalId]) {
+            let mut returned: Vec<NonFungibleGlobalId> = nft_buckets
+                .iter()
+                .flat_map(|bucket| {
+                    let resource_address = bucket.resource_address();
+                    bucket
+                        .as_non_fungible()
+                        .non_fungible_local_ids()
+                        .into_iter()
+                        .map(move |local_id| NonFungibleGlobalId::new(resource_address, local_id))
+                })
+                .collect();
+
+            let mut requested = requested.to_vec();
+
+            returned.sort();
+            requested.sort();
+
+            assert!(
+                returned == requested,
+                "[purchase] Outpost did not return the requested NFTs"
+            );
+        }
     }
 }
@@ -6,4 +6,5 @@ pub mod minter;
 pub mod outpost_account;
 pub mod outpost_event;
 pub mod outpost_factory;
+pub mod price_oracle;
 pub mod royalty_standalone_component;
@@ -56,6 +56,117 @@ struct RoyaltyConfig {
     /// royalties more relaxed even if locked - i.e. remove mininimum royalties, allow all buyers, etc.
     royalty_configuration_locked: bool,
     honoured: bool,
+    /// Optional price bands per currency that replace the flat royalty percentage, e.g. 10% under 100 XRD, 7% from 100 to 1000 XRD
+    /// and 5% above. Every band rate must be within the maximum royalty percentage.
+    royalty_bands: KeyValueStore<ResourceAddress, Vec<RoyaltyBand>>,
+    /// The currencies that have royalty bands set
+    royalty_band_currencies: Vec<ResourceAddress>,
+    /// Optional fixed royalty amounts per NFT for each currency, charged instead of, or as a floor to, the percentage royalty
+    flat_royalties: KeyValueStore<ResourceAddress, FlatRoyalty>,
+    /// The currencies that have a flat royalty set
+    flat_royalty_currencies: Vec<ResourceAddress>,
+    /// Beneficiaries that receive a share of every royalty payment. Whatever isn't split out stays in the creator's royalty vault.
+    royalty_splits: Vec<RoyaltySplit>,
+    /// How the beneficiaries' shares are paid out
+    split_payout: SplitPayout,
+    /// Royalty percentages for specific permitted currencies that override royalty_percent, e.g. a lower rate for payments in
+    /// the creator's community token
+    currency_royalty_percents: KeyValueStore<ResourceAddress, Decimal>,
+    /// The currencies that have a royalty percentage override
+    currency_royalty_percent_currencies: Vec<ResourceAddress>,
+    /// Royalty percentages for individual NFTs (e.g. hand-made 1/1s) that override the collection's percentage in every currency
+    nft_royalty_percents: KeyValueStore<NonFungibleLocalId, Decimal>,
+    /// The NFTs that have a royalty percentage override
+    nft_royalty_percent_ids: Vec<NonFungibleLocalId>,
+    /// How long restrictive changes (raising any royalty rate or minimum, limiting buyers, dApps or currencies, removing
+    /// permissions) wait before they apply, so NFTs listed under the old terms can be delisted. 0 applies them immediately.
+    royalty_change_delay_seconds: i64,
+    /// Restrictive changes that have been announced and are waiting for their effective time
+    pending_royalty_changes: Vec<PendingRoyaltyChange>,
+    /// The id given to the next scheduled change - ids are never reused, so a cancel can't hit a different change
+    next_royalty_change_id: u64,
+}
+
+/// A restrictive royalty configuration change that can be timelocked.
+#[derive(ScryptoSbor, Clone, PartialEq, Eq, Debug)]
+pub enum RoyaltyChange {
+    RoyaltyPercent(Decimal),
+    LimitBuyers,
+    LimitDapps,
+    RemovePermittedCurrency(ResourceAddress),
+    ChangeDelay(i64),
+    RoyaltyBands(ResourceAddress, Vec<RoyaltyBand>),
+    RemoveRoyaltyBands(ResourceAddress),
+    FlatRoyalty(ResourceAddress, FlatRoyalty),
+    RemoveFlatRoyalty(ResourceAddress),
+    CurrencyRoyaltyPercent(ResourceAddress, Decimal),
+    RemoveCurrencyRoyaltyPercent(ResourceAddress),
+    NftRoyaltyPercent(NonFungibleLocalId, Decimal),
+    RemoveNftRoyaltyPercent(NonFungibleLocalId),
+    MinimumRoyalties,
+    MinimumRoyaltyAmount(ResourceAddress, Decimal),
+    LimitCurrencies,
+    RemovePermissionedBuyer(ResourceAddress),
+    RemovePermissionedDapp(ComponentAddress),
+}
+
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct PendingRoyaltyChange {
+    /// Used to cancel the change
+    pub id: u64,
+    pub change: RoyaltyChange,
+    pub effective_from: Instant,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct RoyaltyChangeScheduled {
+    pub royalty_component: ComponentAddress,
+    pub id: u64,
+    pub change: RoyaltyChange,
+    pub effective_from: Instant,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct RoyaltyChangeApplied {
+    pub royalty_component: ComponentAddress,
+    pub change: RoyaltyChange,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct RoyaltyChangeCancelled {
+    pub royalty_component: ComponentAddress,
+    pub id: u64,
+    pub change: RoyaltyChange,
+}
+
+/// A beneficiary's share of royalty revenue, in basis points (1/100th of a percent) of each royalty payment.
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct RoyaltySplit {
+    pub beneficiary: Global<Account>,
+    pub basis_points: u16,
+}
+
+#[derive(ScryptoSbor, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SplitPayout {
+    /// Shares are sent at sale time through the royalty AccountLocker, which first tries to deposit directly to the beneficiary
+    Locker,
+    /// Shares accrue in the royalty component until each beneficiary claims them
+    Accrue,
+}
+
+/// A fixed royalty amount per NFT sold in a currency. If greater_of_percent is set, the creator receives whichever is higher
+/// of the flat amount and the percentage royalty - otherwise the flat amount replaces the percentage royalty.
+#[derive(ScryptoSbor, Clone, PartialEq, Eq, Debug)]
+pub struct FlatRoyalty {
+    pub amount: Decimal,
+    pub greater_of_percent: bool,
+}
+
+/// A royalty rate that applies to sales at or above a price (per NFT) until the next band starts.
+#[derive(ScryptoSbor, Clone, PartialEq, Eq, Debug)]
+pub struct RoyaltyBand {
+    pub from_price: Decimal,
+    pub royalty_percent: Decimal,
 }
 
 #[derive(ScryptoSbor, NonFungibleData, Clone)]
@@ -194,7 +305,14 @@ pub struct TransactionTracking {
 }
 
 #[blueprint]
-#[events(RevealMint, MintComplete, CancelMint)]
+#[events(
+    RevealMint,
+    MintComplete,
+    CancelMint,
+    RoyaltyChangeScheduled,
+    RoyaltyChangeApplied,
+    RoyaltyChangeCancelled
+)]
 mod royal_nft {
 
     enable_method_auth! {
@@ -212,6 +330,27 @@ mod royal_nft {
         transfer_royalty_nft_to_dapp => PUBLIC;
         change_royalty_percentage_fee => restrict_to: [admin];
         lower_maximum_royalty_percentage => restrict_to: [admin];
+        set_royalty_bands => restrict_to: [admin];
+        remove_royalty_bands => restrict_to: [admin];
+        get_royalty_bands => PUBLIC;
+        set_flat_royalty => restrict_to: [admin];
+        remove_flat_royalty => restrict_to: [admin];
+        get_flat_royalty => PUBLIC;
+        set_royalty_splits => restrict_to: [admin];
+        get_royalty_splits => PUBLIC;
+        claim_royalty_share => PUBLIC;
+        get_accrued_royalty => PUBLIC;
+        set_currency_royalty_percent => restrict_to: [admin];
+        remove_currency_royalty_percent => restrict_to: [admin];
+        get_currency_royalty_percent => PUBLIC;
+        set_nft_royalty_percent => restrict_to: [admin];
+        remove_nft_royalty_percent => restrict_to: [admin];
+        get_nft_royalty_percent => PUBLIC;
+        set_royalty_change_delay => restrict_to: [admin];
+        cancel_pending_royalty_change => restrict_to: [admin];
+        apply_pending_royalty_changes => PUBLIC;
+        get_pending_royalty_changes => PUBLIC;
+        get_royalty_change_delay => PUBLIC;
         restrict_currencies_true => restrict_to: [admin];
         restrict_currencies_false => restrict_to: [admin];
         add_permitted_currency => restrict_to: [admin];
@@ -249,6 +388,8 @@ mod royal_nft {
         remove_from_allow_list => restrict_to: [admin];
         restrict_mint_list => restrict_to: [admin];
         pay_royalty_basic => PUBLIC;
+        pay_rental_royalty => PUBLIC;
+        pay_collateral_royalty => PUBLIC;
     }
     }
 
@@ -260,6 +401,10 @@ mod royal_nft {
         admin_config: AdminConfig,
         royalty_config: RoyaltyConfig,
         royalty_vaults: KeyValueStore<ResourceAddress, Vault>,
+        /// Pays out royalty splits at sale time
+        royalty_locker: Global<AccountLocker>,
+        /// Royalty shares waiting to be claimed, per beneficiary account and currency
+        accrued_royalties: KeyValueStore<(ComponentAddress, ResourceAddress), Vault>,
         royalty_component: ComponentAddress,
         transaction_tracking: TransactionTracking,
     }
@@ -301,6 +446,19 @@ mod royal_nft {
                 permissioned_dapps: KeyValueStore::new(),
                 permissioned_buyers: KeyValueStore::new(),
                 royalty_configuration_locked: false,
+                royalty_bands: KeyValueStore::new(),
+                royalty_band_currencies: vec![],
+                flat_royalties: KeyValueStore::new(),
+                flat_royalty_currencies: vec![],
+                royalty_splits: vec![],
+                split_payout: SplitPayout::Locker,
+                currency_royalty_percents: KeyValueStore::new(),
+                currency_royalty_percent_currencies: vec![],
+                nft_royalty_percents: KeyValueStore::new(),
+                nft_royalty_percent_ids: vec![],
+                royalty_change_delay_seconds: 0,
+                pending_royalty_changes: vec![],
+                next_royalty_change_id: 0,
             };
 
             let admin_name = format!("{} OP Admin", setup_metadata.name);
@@ -343,6 +501,16 @@ mod royal_nft {
             // create the rules for the global caller badge
             let global_caller_badge_rule = rule!(require(global_caller(royalty_component_address)));
 
+            // only this component stores royalty shares for beneficiaries, and nothing can be recovered from their claims
+            let royalty_locker = Blueprint::<AccountLocker>::instantiate(
+                OwnerRole::None,
+                global_caller_badge_rule.clone(),
+                rule!(deny_all),
+                rule!(deny_all),
+                rule!(deny_all),
+                None,
+            );
+
             // This is the key rule that allows trader accounts to trade royalty NFTs.
             // In this example, we're allowing the component and trader accounts to deposit NFT NFTs.
             let depositer_admin_rule: AccessRule;
@@ -562,6 +730,8 @@ mod royal_nft {
                 admin_config,
                 royalty_config,
                 royalty_vaults: KeyValueStore::new(),
+                royalty_locker,
+                accrued_royalties: KeyValueStore::new(),
                 royalty_component: royalty_component_address.clone(),
                 transaction_tracking,
             }
@@ -1106,12 +1276,13 @@ mod royal_nft {
         pub fn pay_royalty(
             &mut self,
             nft: ResourceAddress,
-            local_ids: indexmap::IndexSet<NonFungibleLocalId>,
+            prices: indexmap::IndexMap<NonFungibleLocalId, Decimal>,
             mut payment: Bucket,
             buyer: ResourceAddress,
             account: Global<Account>,
         ) -> Bucket {
-            let payment_amount = payment.amount();
+            // announced changes that have passed their effective time apply from this payment onwards
+            self.apply_pending_royalty_changes();
 
             // check the correct NFT for this royalty component has been passed
             assert!(
@@ -1142,59 +1313,8 @@ mod royal_nft {
                 );
             }
 
-            // send the royalty to the royalty vault
-
-            let vault_exists = self.royalty_vaults.get(&currency).is_some();
-
-            if !vault_exists {
-                // check the correct amount has been passed
-                let royalty = payment.take_advanced(
-                    payment_amount
-                        .checked_mul(self.royalty_config.royalty_percent)
-                        .unwrap(),
-                    WithdrawStrategy::Rounded(RoundingMode::ToZero),
-                );
-
-                if limit_currencies {
-                    if self.royalty_config.minimum_royalties {
-                        let minimum_royalty = self
-                            .royalty_config
-                            .minimum_royalty_amounts
-                            .get(&currency)
-                            .unwrap();
-                        assert!(
-                            royalty.amount() >= minimum_royalty.clone(),
-                            "Royalty amount is below the minimum required"
-                        );
-                    }
-                }
-
-                self.royalty_vaults
-                    .insert(currency.clone(), Vault::with_bucket(royalty));
-            } else {
-                // check the correct amount has been passed
-                let royalty = payment.take_advanced(
-                    payment_amount
-                        .checked_mul(self.royalty_config.royalty_percent)
-                        .unwrap(),
-                    WithdrawStrategy::Rounded(RoundingMode::ToZero),
-                );
-
-                if limit_currencies {
-                    if self.royalty_config.minimum_royalties {
-                        let minimum_royalty = self
-                            .royalty_config
-                            .minimum_royalty_amounts
-                            .get(&currency)
-                            .unwrap();
-                        assert!(
-                            royalty.amount() >= minimum_royalty.clone(),
-                            "Royalty amount is below the minimum required"
-                        );
-                    }
-                }
-                self.royalty_vaults.get_mut(&currency).unwrap().put(royalty);
-            }
+            // royalties are worked out per NFT, so buying in bulk pays the same royalty as buying one at a time
+            self.collect_royalty(&mut payment, &prices);
 
             // payment minus royalty returned to the trading account that called this method
             payment
@@ -1203,10 +1323,12 @@ mod royal_nft {
         pub fn pay_royalty_basic(
             &mut self,
             nft: ResourceAddress,
+            prices: indexmap::IndexMap<NonFungibleLocalId, Decimal>,
             mut payment: Bucket,
             buyer: ResourceAddress,
         ) -> Bucket {
-            let payment_amount = payment.amount();
+            // announced changes that have passed their effective time apply from this payment onwards
+            self.apply_pending_royalty_changes();
 
             // check the correct NFT for this royalty component has been passed
             assert!(
@@ -1237,61 +1359,90 @@ mod royal_nft {
                 );
             }
 
-            // send the royalty to the royalty vault
+            self.collect_royalty(&mut payment, &prices);
 
-            let vault_exists = self.royalty_vaults.get(&currency).is_some();
+            // payment minus royalty returned to the trading account that called this method
+            payment
+        }
 
-            if !vault_exists {
-                // check the correct amount has been passed
-                let royalty = payment.take_advanced(
-                    payment_amount
-                        .checked_mul(self.royalty_config.royalty_percent)
-                        .unwrap(),
-                    WithdrawStrategy::Rounded(RoundingMode::ToZero),
+        /// Rental fees for Royalty NFTs escrowed in an Outpost are passed through here so the creator can take their cut.
+        /// The royalty is worked out in the same way as for a sale of the rented NFT at the rental fee - price bands, flat
+        /// royalties, per-NFT percentages and minimum royalties all apply, as do the buyer and currency limits.
+        pub fn pay_rental_royalty(
+            &mut self,
+            nft: ResourceAddress,
+            prices: indexmap::IndexMap<NonFungibleLocalId, Decimal>,
+            mut payment: Bucket,
+            renter: ResourceAddress,
+        ) -> Bucket {
+            // announced changes that have passed their effective time apply from this payment onwards
+            self.apply_pending_royalty_changes();
+
+            assert!(
+                nft == self.nft_manager.address(),
+                "[pay_rental_royalty] Incorrect resource passed"
+            );
+
+            if self.royalty_config.limit_buyers {
+                assert!(
+                    self.royalty_config
+                        .permissioned_buyers
+                        .get(&renter)
+                        .is_some(),
+                    "This renter is not permissioned to trade this NFT"
                 );
+            }
 
-                if limit_currencies {
-                    if self.royalty_config.minimum_royalties {
-                        let minimum_royalty = self
-                            .royalty_config
-                            .minimum_royalty_amounts
-                            .get(&currency)
-                            .unwrap();
-                        assert!(
-                            royalty.amount() >= minimum_royalty.clone(),
-                            "Royalty amount is below the minimum required"
-                        );
-                    }
-                }
+            let currency = payment.resource_address();
 
-                self.royalty_vaults
-                    .insert(currency.clone(), Vault::with_bucket(royalty));
-            } else {
-                // check the correct amount has been passed
-                let royalty = payment.take_advanced(
-                    payment_amount
-                        .checked_mul(self.royalty_config.royalty_percent)
-                        .unwrap(),
-                    WithdrawStrategy::Rounded(RoundingMode::ToZero),
+            if self.royalty_config.limit_currencies {
+                assert!(
+                    self.royalty_config
+                        .permitted_currencies
+                        .get(&currency)
+                        .is_some(),
+                    "This currency is not permitted for royalties"
                 );
+            }
 
-                if limit_currencies {
-                    if self.royalty_config.minimum_royalties {
-                        let minimum_royalty = self
-                            .royalty_config
-                            .minimum_royalty_amounts
-                            .get(&currency)
-                            .unwrap();
-                        assert!(
-                            royalty.amount() >= minimum_royalty.clone(),
-                            "Royalty amount is below the minimum required"
-                        );
-                    }
-                }
-                self.royalty_vaults.get_mut(&currency).unwrap().put(royalty);
+            self.collect_royalty(&mut payment, &prices);
+
+            // rental fee minus royalty returned to the Outpost that called this method
+            payment
+        }
+
+        /// Royalty NFTs seized by a lender when a loan defaults are passed through here, with the payment being the value the
+        /// lender and borrower agreed for each NFT. The royalty is worked out in the same way as for a sale at that value, and the
+        /// currency limit applies. There is no marketplace or private buyer involved, so the buyer limit doesn't apply.
+        pub fn pay_collateral_royalty(
+            &mut self,
+            nft: ResourceAddress,
+            prices: indexmap::IndexMap<NonFungibleLocalId, Decimal>,
+            mut payment: Bucket,
+        ) -> Bucket {
+            // announced changes that have passed their effective time apply from this payment onwards
+            self.apply_pending_royalty_changes();
+
+            assert!(
+                nft == self.nft_manager.address(),
+                "[pay_collateral_royalty] Incorrect resource passed"
+            );
+
+            let currency = payment.resource_address();
+
+            if self.royalty_config.limit_currencies {
+                assert!(
+                    self.royalty_config
+                        .permitted_currencies
+                        .get(&currency)
+                        .is_some(),
+                    "This currency is not permitted for royalties"
+                );
             }
 
-            // payment minus royalty returned to the trading account that called this method
+            self.collect_royalty(&mut payment, &prices);
+
+            // payment minus royalty returned to the Outpost that called this method
             payment
         }
 
@@ -1368,6 +1519,35 @@ mod royal_nft {
             self.royalty_config.restricted_movement = false;
             self.royalty_config.royalty_percent = dec!(0);
 
+            for currency in self.royalty_config.flat_royalty_currencies.drain(..) {
+                self.royalty_config.flat_royalties.remove(&currency);
+            }
+
+            for currency in self
+                .royalty_config
+                .currency_royalty_percent_currencies
+                .drain(..)
+            {
+                self.royalty_config.currency_royalty_percents.remove(&currency);
+            }
+
+            for local_id in self.royalty_config.nft_royalty_percent_ids.drain(..) {
+                self.royalty_config.nft_royalty_percents.remove(&local_id);
+            }
+
+            for currency in self.royalty_config.royalty_band_currencies.drain(..) {
+                self.royalty_config.royalty_bands.remove(&currency);
+            }
+
+            // a scheduled change would bring part of the removed configuration back once it applies
+            for pending in self.royalty_config.pending_royalty_changes.drain(..) {
+                Runtime::emit_event(RoyaltyChangeCancelled {
+                    royalty_component: self.royalty_component,
+                    id: pending.id,
+                    change: pending.change,
+                });
+            }
+
             self.admin_config
                 .internal_creator_admin
                 .as_fungible()
@@ -1387,7 +1567,12 @@ mod royal_nft {
                 "New royalty percentage is greater than maximum allowed"
             );
 
-            self.royalty_config.royalty_percent = new_royalty_percent;
+            // lowering the royalty is always immediate, raising it waits for the change delay
+            if new_royalty_percent > self.royalty_config.royalty_percent {
+                self.schedule_royalty_change(RoyaltyChange::RoyaltyPercent(new_royalty_percent));
+            } else {
+                self.royalty_config.royalty_percent = new_royalty_percent;
+            }
         }
 
         /// you can always lower the maximum royalty percentage - even if the configuration is locked.
@@ -1397,9 +1582,204 @@ mod royal_nft {
                 "New maximum royalty percentage is less than current royalty percentage"
             );
 
+            for currency in self.royalty_config.royalty_band_currencies.iter() {
+                assert!(
+                    self.royalty_config
+                        .royalty_bands
+                        .get(currency)
+                        .unwrap()
+                        .iter()
+                        .all(|band| band.royalty_percent <= new_max_royalty_percent),
+                    "New maximum royalty percentage is less than a royalty band percentage"
+                );
+            }
+
+            for currency in self.royalty_config.currency_royalty_percent_currencies.iter() {
+                assert!(
+                    *self
+                        .royalty_config
+                        .currency_royalty_percents
+                        .get(currency)
+                        .unwrap()
+                        <= new_max_royalty_percent,
+                    "New maximum royalty percentage is less than a currency royalty percentage"
+                );
+            }
+
+            for local_id in self.royalty_config.nft_royalty_percent_ids.iter() {
+                assert!(
+                    *self
+                        .royalty_config
+                        .nft_royalty_percents
+                        .get(local_id)
+                        .unwrap()
+                        <= new_max_royalty_percent,
+                    "New maximum royalty percentage is less than an NFT royalty percentage"
+                );
+            }
+
             self.royalty_config.maximum_royalty_percent = new_max_royalty_percent;
         }
 
+        /// Only possible if the royalty configuration is not locked.
+        /// Bands are priced per NFT in the given currency, and must start from a price of 0 in ascending order of price.
+        pub fn set_royalty_bands(&mut self, currency: ResourceAddress, bands: Vec<RoyaltyBand>) {
+            assert!(
+                !self.royalty_config.royalty_configuration_locked,
+                "Royalty configuration is locked"
+            );
+
+            assert!(
+                !bands.is_empty() && bands[0].from_price == dec!(0),
+                "The first royalty band must start from a price of 0"
+            );
+
+            for pair in bands.windows(2) {
+                assert!(
+                    pair[0].from_price < pair[1].from_price,
+                    "Royalty bands must be in ascending order of price"
+                );
+            }
+
+            for band in bands.iter() {
+                assert!(
+                    band.royalty_percent >= dec!(0)
+                        && band.royalty_percent <= self.royalty_config.maximum_royalty_percent,
+                    "Royalty band percentage is greater than maximum allowed"
+                );
+            }
+
+            // bands that charge no more than the current terms at any price are immediate, otherwise they wait for the change delay
+            if self.bands_lower_royalty(currency, &bands) {
+                self.apply_royalty_change(RoyaltyChange::RoyaltyBands(currency, bands));
+            } else {
+                self.schedule_royalty_change(RoyaltyChange::RoyaltyBands(currency, bands));
+            }
+        }
+
+        /// Only possible if the royalty configuration is not locked - the flat royalty percentage applies to the currency again.
+        /// That percentage may be higher than the bands, so this waits for the change delay.
+        pub fn remove_royalty_bands(&mut self, currency: ResourceAddress) {
+            assert!(
+                !self.royalty_config.royalty_configuration_locked,
+                "Royalty configuration is locked"
+            );
+
+            self.schedule_royalty_change(RoyaltyChange::RemoveRoyaltyBands(currency));
+        }
+
+        pub fn get_royalty_bands(&self, currency: ResourceAddress) -> Option<Vec<RoyaltyBand>> {
+            self.royalty_config
+                .royalty_bands
+                .get(&currency)
+                .map(|bands| bands.clone())
+        }
+
+        /// Only possible if the royalty configuration is not locked.
+        /// The flat amount is charged per NFT in the given currency, so sales in that currency must be priced above it.
+        pub fn set_flat_royalty(&mut self, currency: ResourceAddress, flat_royalty: FlatRoyalty) {
+            assert!(
+                !self.royalty_config.royalty_configuration_locked,
+                "Royalty configuration is locked"
+            );
+
+            assert!(
+                flat_royalty.amount > dec!(0),
+                "Flat royalty amount must be greater than zero"
+            );
+
+            // lowering an existing flat royalty is immediate, as long as it doesn't start acting as a floor on the percentage royalty
+            let lowers_royalty = self
+                .royalty_config
+                .flat_royalties
+                .get(&currency)
+                .map_or(false, |current| {
+                    flat_royalty.amount <= current.amount
+                        && (!flat_royalty.greater_of_percent || current.greater_of_percent)
+                });
+
+            if lowers_royalty {
+                self.apply_royalty_change(RoyaltyChange::FlatRoyalty(currency, flat_royalty));
+            } else {
+                self.schedule_royalty_change(RoyaltyChange::FlatRoyalty(currency, flat_royalty));
+            }
+        }
+
+        /// Only possible if the royalty configuration is not locked - the percentage royalty applies to the currency again.
+        /// The percentage royalty may be higher than the flat amount, so this waits for the change delay.
+        pub fn remove_flat_royalty(&mut self, currency: ResourceAddress) {
+            assert!(
+                !self.royalty_config.royalty_configuration_locked,
+                "Royalty configuration is locked"
+            );
+
+            self.schedule_royalty_change(RoyaltyChange::RemoveFlatRoyalty(currency));
+        }
+
+        pub fn get_flat_royalty(&self, currency: ResourceAddress) -> Option<FlatRoyalty> {
+            self.royalty_config
+                .flat_royalties
+                .get(&currency)
+                .map(|flat_royalty| flat_royalty.clone())
+        }
+
+        /// Only possible if the royalty configuration is not locked. Replaces the whole split table - an empty table sends every
+        /// royalty to the creator's royalty vault. Shares already accrued stay claimable if the payout is changed.
+        pub fn set_royalty_splits(&mut self, splits: Vec<RoyaltySplit>, payout: SplitPayout) {
+            assert!(
+                !self.royalty_config.royalty_configuration_locked,
+                "Royalty configuration is locked"
+            );
+
+            assert!(
+                splits.iter().all(|split| split.basis_points > 0),
+                "Royalty split shares must be greater than zero"
+            );
+
+            assert!(
+                splits
+                    .iter()
+                    .map(|split| split.basis_points as u32)
+                    .sum::<u32>()
+                    <= 10000,
+                "Royalty split shares add up to more than 100%"
+            );
+
+            self.royalty_config.royalty_splits = splits;
+            self.royalty_config.split_payout = payout;
+        }
+
+        pub fn get_royalty_splits(&self) -> (Vec<RoyaltySplit>, SplitPayout) {
+            (
+                self.royalty_config.royalty_splits.clone(),
+                self.royalty_config.split_payout,
+            )
+        }
+
+        /// Beneficiaries claim their accrued royalty shares here - the caller must be able to prove they own the account.
+        pub fn claim_royalty_share(
+            &mut self,
+            beneficiary: Global<Account>,
+            currency: ResourceAddress,
+        ) -> Bucket {
+            Runtime::assert_access_rule(beneficiary.get_owner_role().rule);
+
+            self.accrued_royalties
+                .get_mut(&(beneficiary.address(), currency))
+                .expect("No royalties have accrued for this beneficiary")
+                .take_all()
+        }
+
+        pub fn get_accrued_royalty(
+            &self,
+            beneficiary: ComponentAddress,
+            currency: ResourceAddress,
+        ) -> Decimal {
+            self.accrued_royalties
+                .get(&(beneficiary, currency))
+                .map_or(dec!(0), |vault| vault.amount())
+        }
+
         /// Only possible if the royalty configuration is not locked.
         /// You can always turn this setting off even if the configuration is locked.
         pub fn restrict_currencies_true(&mut self) {
@@ -1407,7 +1787,7 @@ mod royal_nft {
                 !self.royalty_config.royalty_configuration_locked,
                 "Royalty configuration is locked"
             );
-            self.royalty_config.limit_currencies = true;
+            self.schedule_royalty_change(RoyaltyChange::LimitCurrencies);
         }
 
         pub fn restrict_currencies_false(&mut self) {
@@ -1437,19 +1817,134 @@ mod royal_nft {
                 !self.royalty_config.royalty_configuration_locked,
                 "Royalty configuration is locked"
             );
-            self.royalty_config.permitted_currencies.remove(&currency);
+            self.schedule_royalty_change(RoyaltyChange::RemovePermittedCurrency(currency));
+        }
+
+        // You can only set currency royalty percentages for permitted currencies, so the restricted currency setting must be turned on.
+        // You can't set them if the configuration is locked.
+        pub fn set_currency_royalty_percent(
+            &mut self,
+            currency: ResourceAddress,
+            royalty_percent: Decimal,
+        ) {
+            assert!(
+                self.royalty_config.limit_currencies,
+                "Restricted currency setting is not turned on"
+            );
+            assert!(
+                self.royalty_config
+                    .permitted_currencies
+                    .get(&currency)
+                    .is_some(),
+                "This currency is not permitted for royalties"
+            );
+            assert!(
+                !self.royalty_config.royalty_configuration_locked,
+                "Royalty configuration is locked"
+            );
+            assert!(
+                royalty_percent >= dec!(0)
+                    && royalty_percent <= self.royalty_config.maximum_royalty_percent,
+                "New royalty percentage is greater than maximum allowed"
+            );
+
+            // lowering the currency's percentage is immediate, raising it waits for the change delay
+            if royalty_percent <= self.currency_royalty_percent(currency) {
+                self.apply_royalty_change(RoyaltyChange::CurrencyRoyaltyPercent(
+                    currency,
+                    royalty_percent,
+                ));
+            } else {
+                self.schedule_royalty_change(RoyaltyChange::CurrencyRoyaltyPercent(
+                    currency,
+                    royalty_percent,
+                ));
+            }
         }
 
-        // You can only set minimum royalty amounts if the restricted currency setting is turned on.
+        // You can't remove currency royalty percentages if the configuration is locked, as the currency would fall back to royalty_percent.
+        // Falling back to a higher royalty_percent waits for the change delay.
+        pub fn remove_currency_royalty_percent(&mut self, currency: ResourceAddress) {
+            assert!(
+                !self.royalty_config.royalty_configuration_locked,
+                "Royalty configuration is locked"
+            );
+
+            if self.royalty_config.royalty_percent <= self.currency_royalty_percent(currency) {
+                self.apply_royalty_change(RoyaltyChange::RemoveCurrencyRoyaltyPercent(currency));
+            } else {
+                self.schedule_royalty_change(RoyaltyChange::RemoveCurrencyRoyaltyPercent(currency));
+            }
+        }
+
+        /// Only possible if the royalty configuration is not locked.
+        /// The NFT's percentage replaces price bands and currency percentages, while flat royalties still apply to it.
+        pub fn set_nft_royalty_percent(
+            &mut self,
+            local_id: NonFungibleLocalId,
+            royalty_percent: Decimal,
+        ) {
+            assert!(
+                !self.royalty_config.royalty_configuration_locked,
+                "Royalty configuration is locked"
+            );
+            assert!(
+                royalty_percent >= dec!(0)
+                    && royalty_percent <= self.royalty_config.maximum_royalty_percent,
+                "New royalty percentage is greater than maximum allowed"
+            );
+
+            // only lowering an NFT's existing percentage is immediate - a new percentage replaces rates that depend on the price
+            let lowers_royalty = self
+                .royalty_config
+                .nft_royalty_percents
+                .get(&local_id)
+                .map_or(false, |current| royalty_percent <= *current);
+
+            if lowers_royalty {
+                self.apply_royalty_change(RoyaltyChange::NftRoyaltyPercent(local_id, royalty_percent));
+            } else {
+                self.schedule_royalty_change(RoyaltyChange::NftRoyaltyPercent(
+                    local_id,
+                    royalty_percent,
+                ));
+            }
+        }
+
+        /// Only possible if the royalty configuration is not locked - the NFT falls back to the collection's royalty percentage.
+        /// That percentage may be higher, so this waits for the change delay.
+        pub fn remove_nft_royalty_percent(&mut self, local_id: NonFungibleLocalId) {
+            assert!(
+                !self.royalty_config.royalty_configuration_locked,
+                "Royalty configuration is locked"
+            );
+
+            self.schedule_royalty_change(RoyaltyChange::RemoveNftRoyaltyPercent(local_id));
+        }
+
+        pub fn get_nft_royalty_percent(&self, local_id: NonFungibleLocalId) -> Option<Decimal> {
+            self.royalty_config
+                .nft_royalty_percents
+                .get(&local_id)
+                .map(|royalty_percent| *royalty_percent)
+        }
+
+        /// The royalty percentage applied to sales in a currency, before any price bands.
+        pub fn get_currency_royalty_percent(&self, currency: ResourceAddress) -> Decimal {
+            self.currency_royalty_percent(currency)
+        }
+
+        // Minimum royalties work with or without the restricted currency setting. While they're enabled, payments are rejected in any
+        // currency that doesn't have a minimum amount set.
 
         // enable minimum royalties
 
         pub fn enable_minimum_royalties(&mut self) {
             assert!(
-                self.royalty_config.limit_currencies,
-                "Restricted currency setting is not turned on"
+                !self.royalty_config.royalty_configuration_locked,
+                "Royalty configuration is locked"
             );
-            self.royalty_config.minimum_royalties = true;
+            self.schedule_royalty_change(RoyaltyChange::MinimumRoyalties);
         }
 
         pub fn disable_minimum_royalties(&mut self) {
@@ -1462,26 +1957,33 @@ mod royal_nft {
             currency: ResourceAddress,
             minimum_royalty_amount: Decimal,
         ) {
-            assert!(
-                self.royalty_config.limit_currencies,
-                "Restricted currency setting is not turned on"
-            );
             assert!(
                 !self.royalty_config.royalty_configuration_locked,
                 "Royalty configuration is locked"
             );
-            self.royalty_config
+
+            // a minimum for a new currency only opens that currency up, raising an existing minimum waits for the change delay
+            let raises_minimum = self
+                .royalty_config
                 .minimum_royalty_amounts
-                .insert(currency, minimum_royalty_amount);
+                .get(&currency)
+                .map_or(false, |current| minimum_royalty_amount > *current);
+
+            if raises_minimum {
+                self.schedule_royalty_change(RoyaltyChange::MinimumRoyaltyAmount(
+                    currency,
+                    minimum_royalty_amount,
+                ));
+            } else {
+                self.apply_royalty_change(RoyaltyChange::MinimumRoyaltyAmount(
+                    currency,
+                    minimum_royalty_amount,
+                ));
+            }
         }
 
-        // You can only remove minimum royalty amounts if the restricted currency setting is turned on.
         // You can remove even if the configuration is locked.
         pub fn remove_minimum_royalty_amount(&mut self, currency: ResourceAddress) {
-            assert!(
-                self.royalty_config.limit_currencies,
-                "Restricted currency setting is not turned on"
-            );
             self.royalty_config
                 .minimum_royalty_amounts
                 .remove(&currency);
@@ -1494,7 +1996,7 @@ mod royal_nft {
                 !self.royalty_config.royalty_configuration_locked,
                 "Royalty configuration is locked"
             );
-            self.royalty_config.limit_dapps = true;
+            self.schedule_royalty_change(RoyaltyChange::LimitDapps);
         }
 
         pub fn limit_dapps_false(&mut self) {
@@ -1512,7 +2014,7 @@ mod royal_nft {
                 !self.royalty_config.royalty_configuration_locked,
                 "Royalty configuration is locked"
             );
-            self.royalty_config.permissioned_dapps.remove(&dapp);
+            self.schedule_royalty_change(RoyaltyChange::RemovePermissionedDapp(dapp));
         }
 
         // Permissioned buyers settings only work with advanced royalty enforcement settings.
@@ -1527,7 +2029,7 @@ mod royal_nft {
                 !self.royalty_config.royalty_configuration_locked,
                 "Royalty configuration is locked"
             );
-            self.royalty_config.permissioned_buyers.remove(&buyer);
+            self.schedule_royalty_change(RoyaltyChange::RemovePermissionedBuyer(buyer));
         }
 
         // You can't change to deny_all buyers if the configuration is locked.
@@ -1536,7 +2038,7 @@ mod royal_nft {
                 !self.royalty_config.royalty_configuration_locked,
                 "Royalty configuration is locked"
             );
-            self.royalty_config.limit_buyers = true;
+            self.schedule_royalty_change(RoyaltyChange::LimitBuyers);
         }
 
         // You can allow all buyers even if the configuration is locked
@@ -1544,12 +2046,439 @@ mod royal_nft {
             self.royalty_config.limit_buyers = false;
         }
 
+        /// Scheduled changes would otherwise keep changing a locked configuration once they apply, so they have to be applied or
+        /// cancelled first.
         pub fn lock_royalty_configuration(&mut self) {
+            assert!(
+                self.royalty_config.pending_royalty_changes.is_empty(),
+                "Pending royalty changes must be applied or cancelled before the configuration is locked"
+            );
+
             self.royalty_config.royalty_configuration_locked = true;
         }
 
+        /// A longer delay gives traders more notice, so it applies immediately. Shortening the delay is itself timelocked by the
+        /// current delay. This can be changed even if the configuration is locked.
+        pub fn set_royalty_change_delay(&mut self, delay_seconds: i64) {
+            assert!(delay_seconds >= 0, "Royalty change delay can't be negative");
+
+            if delay_seconds >= self.royalty_config.royalty_change_delay_seconds {
+                self.royalty_config.royalty_change_delay_seconds = delay_seconds;
+            } else {
+                self.schedule_royalty_change(RoyaltyChange::ChangeDelay(delay_seconds));
+            }
+        }
+
+        // Cancelling a restrictive change is always allowed, even if the configuration is locked.
+        pub fn cancel_pending_royalty_change(&mut self, id: u64) {
+            let index = self
+                .royalty_config
+                .pending_royalty_changes
+                .iter()
+                .position(|pending| pending.id == id)
+                .expect("No pending royalty change with this id");
+
+            let pending = self.royalty_config.pending_royalty_changes.remove(index);
+
+            Runtime::emit_event(RoyaltyChangeCancelled {
+                royalty_component: self.royalty_component,
+                id,
+                change: pending.change,
+            });
+        }
+
+        /// Applies every pending change whose effective time has passed. Payments call this first, so anyone calling it is only
+        /// needed to make the getters reflect the new terms before the next sale.
+        pub fn apply_pending_royalty_changes(&mut self) {
+            let now = Clock::current_time_rounded_to_seconds();
+
+            let (due, pending): (Vec<PendingRoyaltyChange>, Vec<PendingRoyaltyChange>) = self
+                .royalty_config
+                .pending_royalty_changes
+                .drain(..)
+                .partition(|pending| now >= pending.effective_from);
+
+            self.royalty_config.pending_royalty_changes = pending;
+
+            for pending in due {
+                self.apply_royalty_change(pending.change);
+            }
+        }
+
+        pub fn get_pending_royalty_changes(&self) -> Vec<PendingRoyaltyChange> {
+            self.royalty_config.pending_royalty_changes.clone()
+        }
+
+        pub fn get_royalty_change_delay(&self) -> i64 {
+            self.royalty_config.royalty_change_delay_seconds
+        }
+
         pub fn toggle_temp_admin(&mut self) {
             self.admin_config.temp_admin = !self.admin_config.temp_admin;
         }
+
+        /// Applies a restrictive change straight away if there's no change delay, otherwise announces it as pending.
+        fn schedule_royalty_change(&mut self, change: RoyaltyChange) {
+            if self.royalty_config.royalty_change_delay_seconds == 0 {
+                self.apply_royalty_change(change);
+                return;
+            }
+
+            let effective_from = Clock::current_time_rounded_to_seconds()
+                .add_seconds(self.royalty_config.royalty_change_delay_seconds)
+                .unwrap();
+
+            let id = self.royalty_config.next_royalty_change_id;
+
+            self.royalty_config.next_royalty_change_id += 1;
+
+            self.royalty_config
+                .pending_royalty_changes
+                .push(PendingRoyaltyChange {
+                    id,
+                    change: change.clone(),
+                    effective_from,
+                });
+
+            Runtime::emit_event(RoyaltyChangeScheduled {
+                royalty_component: self.royalty_component,
+                id,
+                change,
+                effective_from,
+            });
+        }
+
+        fn apply_royalty_change(&mut self, change: RoyaltyChange) {
+            // the maximum may have been lowered while a change was pending
+            let maximum_royalty_percent = self.royalty_config.maximum_royalty_percent;
+
+            match change.clone() {
+                RoyaltyChange::RoyaltyPercent(royalty_percent) => {
+                    // the maximum may have been lowered while the change was pending
+                    self.royalty_config.royalty_percent =
+                        royalty_percent.min(self.royalty_config.maximum_royalty_percent);
+                }
+                RoyaltyChange::LimitBuyers => {
+                    self.royalty_config.limit_buyers = true;
+                }
+                RoyaltyChange::LimitDapps => {
+                    self.royalty_config.limit_dapps = true;
+                }
+                RoyaltyChange::RemovePermittedCurrency(currency) => {
+                    self.royalty_config.permitted_currencies.remove(&currency);
+
+                    // a currency that can't be traded in doesn't need its own royalty percentage
+                    self.royalty_config.currency_royalty_percents.remove(&currency);
+                    self.royalty_config
+                        .currency_royalty_percent_currencies
+                        .retain(|override_currency| *override_currency != currency);
+                }
+                RoyaltyChange::ChangeDelay(delay_seconds) => {
+                    self.royalty_config.royalty_change_delay_seconds = delay_seconds;
+                }
+                RoyaltyChange::RoyaltyBands(currency, bands) => {
+                    let bands: Vec<RoyaltyBand> = bands
+                        .into_iter()
+                        .map(|band| RoyaltyBand {
+                            from_price: band.from_price,
+                            royalty_percent: band.royalty_percent.min(maximum_royalty_percent),
+                        })
+                        .collect();
+
+                    if self.royalty_config.royalty_bands.get(&currency).is_none() {
+                        self.royalty_config.royalty_band_currencies.push(currency);
+                    }
+
+                    self.royalty_config.royalty_bands.insert(currency, bands);
+                }
+                RoyaltyChange::RemoveRoyaltyBands(currency) => {
+                    self.royalty_config.royalty_bands.remove(&currency);
+                    self.royalty_config
+                        .royalty_band_currencies
+                        .retain(|band_currency| *band_currency != currency);
+                }
+                RoyaltyChange::FlatRoyalty(currency, flat_royalty) => {
+                    if self.royalty_config.flat_royalties.get(&currency).is_none() {
+                        self.royalty_config.flat_royalty_currencies.push(currency);
+                    }
+
+                    self.royalty_config
+                        .flat_royalties
+                        .insert(currency, flat_royalty);
+                }
+                RoyaltyChange::RemoveFlatRoyalty(currency) => {
+                    self.royalty_config.flat_royalties.remove(&currency);
+                    self.royalty_config
+                        .flat_royalty_currencies
+                        .retain(|flat_currency| *flat_currency != currency);
+                }
+                RoyaltyChange::CurrencyRoyaltyPercent(currency, royalty_percent) => {
+                    // the currency may have been removed while the change was pending
+                    if self
+                        .royalty_config
+                        .permitted_currencies
+                        .get(&currency)
+                        .is_some()
+                    {
+                        if self
+                            .royalty_config
+                            .currency_royalty_percents
+                            .get(&currency)
+                            .is_none()
+                        {
+                            self.royalty_config
+                                .currency_royalty_percent_currencies
+                                .push(currency);
+                        }
+
+                        self.royalty_config
+                            .currency_royalty_percents
+                            .insert(currency, royalty_percent.min(maximum_royalty_percent));
+                    }
+                }
+                RoyaltyChange::RemoveCurrencyRoyaltyPercent(currency) => {
+                    self.royalty_config.currency_royalty_percents.remove(&currency);
+                    self.royalty_config
+                        .currency_royalty_percent_currencies
+                        .retain(|override_currency| *override_currency != currency);
+                }
+                RoyaltyChange::NftRoyaltyPercent(local_id, royalty_percent) => {
+                    if self
+                        .royalty_config
+                        .nft_royalty_percents
+                        .get(&local_id)
+                        .is_none()
+                    {
+                        self.royalty_config
+                            .nft_royalty_percent_ids
+                            .push(local_id.clone());
+                    }
+
+                    self.royalty_config
+                        .nft_royalty_percents
+                        .insert(local_id, royalty_percent.min(maximum_royalty_percent));
+                }
+                RoyaltyChange::RemoveNftRoyaltyPercent(local_id) => {
+                    self.royalty_config.nft_royalty_percents.remove(&local_id);
+                    self.royalty_config
+                        .nft_royalty_percent_ids
+                        .retain(|override_id| *override_id != local_id);
+                }
+                RoyaltyChange::MinimumRoyalties => {
+                    self.royalty_config.minimum_royalties = true;
+                }
+                RoyaltyChange::MinimumRoyaltyAmount(currency, minimum_royalty_amount) => {
+                    self.royalty_config
+                        .minimum_royalty_amounts
+                        .insert(currency, minimum_royalty_amount);
+                }
+                RoyaltyChange::LimitCurrencies => {
+                    self.royalty_config.limit_currencies = true;
+                }
+                RoyaltyChange::RemovePermissionedBuyer(buyer) => {
+                    self.royalty_config.permissioned_buyers.remove(&buyer);
+                }
+                RoyaltyChange::RemovePermissionedDapp(dapp) => {
+                    self.royalty_config.permissioned_dapps.remove(&dapp);
+                }
+            }
+
+            Runtime::emit_event(RoyaltyChangeApplied {
+                royalty_component: self.royalty_component,
+                change,
+            });
+        }
+
+        /// Takes the royalty owed on a payment for a set of NFTs, checks it against the currency's minimum royalty and stores
+        /// the creator's share in the royalty vault for the currency.
+        fn collect_royalty(
+            &mut self,
+            payment: &mut Bucket,
+            prices: &indexmap::IndexMap<NonFungibleLocalId, Decimal>,
+        ) {
+            let currency = payment.resource_address();
+
+            let royalty_amount = self.royalty_amount(currency, payment.amount(), prices);
+
+            let royalty = payment.take_advanced(
+                royalty_amount,
+                WithdrawStrategy::Rounded(RoundingMode::ToZero),
+            );
+
+            // with minimum royalties enabled, only currencies that have a minimum set can be used. The minimum applies to each
+            // NFT, so a bulk purchase has to clear it once per NFT bought.
+            if self.royalty_config.minimum_royalties {
+                let minimum_royalty = *self
+                    .royalty_config
+                    .minimum_royalty_amounts
+                    .get(&currency)
+                    .expect("No minimum royalty is set for this currency");
+                assert!(
+                    royalty.amount() >= minimum_royalty.checked_mul(prices.len()).unwrap(),
+                    "Royalty amount is below the minimum required"
+                );
+            }
+
+            let royalty = self.split_royalty(royalty);
+
+            let vault_exists = self.royalty_vaults.get(&currency).is_some();
+
+            if !vault_exists {
+                self.royalty_vaults
+                    .insert(currency, Vault::with_bucket(royalty));
+            } else {
+                self.royalty_vaults.get_mut(&currency).unwrap().put(royalty);
+            }
+        }
+
+        /// Pays each beneficiary's share of a royalty through the locker or into their accrued balance, returning the rest for the
+        /// creator's royalty vault.
+        fn split_royalty(&mut self, mut royalty: Bucket) -> Bucket {
+            let royalty_total = royalty.amount();
+            let currency = royalty.resource_address();
+
+            for split in self.royalty_config.royalty_splits.clone() {
+                let share = royalty.take_advanced(
+                    royalty_total
+                        .checked_mul(Decimal::from(split.basis_points))
+                        .unwrap()
+                        .checked_div(dec!(10000))
+                        .unwrap(),
+                    WithdrawStrategy::Rounded(RoundingMode::ToZero),
+                );
+
+                if share.is_empty() {
+                    royalty.put(share);
+                    continue;
+                }
+
+                match self.royalty_config.split_payout {
+                    SplitPayout::Locker => {
+                        self.royalty_locker.store(split.beneficiary, share, true);
+                    }
+                    SplitPayout::Accrue => {
+                        let key = (split.beneficiary.address(), currency);
+
+                        let vault_exists = self.accrued_royalties.get(&key).is_some();
+
+                        if vault_exists {
+                            self.accrued_royalties.get_mut(&key).unwrap().put(share);
+                        } else {
+                            self.accrued_royalties
+                                .insert(key, Vault::with_bucket(share));
+                        }
+                    }
+                }
+            }
+
+            royalty
+        }
+
+        /// The royalty owed on a payment for a set of NFTs, given the price paid for each of them. The prices must add up to the
+        /// payment. Each NFT pays its own royalty percentage or the percentage for its price (using price bands), then the flat
+        /// royalty for the currency replaces it or sets a floor.
+        fn royalty_amount(
+            &self,
+            currency: ResourceAddress,
+            payment_amount: Decimal,
+            prices: &indexmap::IndexMap<NonFungibleLocalId, Decimal>,
+        ) -> Decimal {
+            assert!(!prices.is_empty(), "[pay_royalty] No NFTs passed");
+
+            let total_price = prices
+                .values()
+                .fold(dec!(0), |total, price| total.checked_add(*price).unwrap());
+
+            assert!(
+                total_price == payment_amount,
+                "[pay_royalty] NFT prices must add up to the payment"
+            );
+
+            let flat_royalty = self
+                .royalty_config
+                .flat_royalties
+                .get(&currency)
+                .map(|flat_royalty| flat_royalty.clone());
+
+            let royalty = prices.iter().fold(dec!(0), |total, (local_id, price)| {
+                let royalty_percent = self
+                    .royalty_config
+                    .nft_royalty_percents
+                    .get(local_id)
+                    .map_or_else(
+                        || self.royalty_rate(currency, *price),
+                        |royalty_percent| *royalty_percent,
+                    );
+
+                let percentage_royalty = price.checked_mul(royalty_percent).unwrap();
+
+                let nft_royalty = match &flat_royalty {
+                    Some(flat_royalty) if flat_royalty.greater_of_percent => {
+                        flat_royalty.amount.max(percentage_royalty)
+                    }
+                    Some(flat_royalty) => flat_royalty.amount,
+                    None => percentage_royalty,
+                };
+
+                total.checked_add(nft_royalty).unwrap()
+            });
+
+            assert!(
+                royalty <= payment_amount,
+                "Payment does not cover the flat royalty"
+            );
+
+            royalty
+        }
+
+        /// Whether a new set of bands charges no more than the current terms at any price. Rates only change at band boundaries,
+        /// so comparing at the start of every current and new band covers every price.
+        fn bands_lower_royalty(&self, currency: ResourceAddress, bands: &[RoyaltyBand]) -> bool {
+            let current_boundaries: Vec<Decimal> = self
+                .royalty_config
+                .royalty_bands
+                .get(&currency)
+                .map_or(vec![], |current| {
+                    current.iter().map(|band| band.from_price).collect()
+                });
+
+            current_boundaries
+                .into_iter()
+                .chain(bands.iter().map(|band| band.from_price))
+                .all(|price| {
+                    let new_rate = bands
+                        .iter()
+                        .rev()
+                        .find(|band| band.from_price <= price)
+                        .map_or(dec!(0), |band| band.royalty_percent);
+
+                    new_rate <= self.royalty_rate(currency, price)
+                })
+        }
+
+        /// The royalty percentage for a sale at this price - the last band starting at or below the price, or the currency's
+        /// royalty percentage if the creator hasn't set bands for it.
+        fn royalty_rate(&self, currency: ResourceAddress, price: Decimal) -> Decimal {
+            match self.royalty_config.royalty_bands.get(&currency) {
+                Some(bands) => bands
+                    .iter()
+                    .rev()
+                    .find(|band| band.from_price <= price)
+                    .map_or(self.currency_royalty_percent(currency), |band| {
+                        band.royalty_percent
+                    }),
+                None => self.currency_royalty_percent(currency),
+            }
+        }
+
+        /// The currency's royalty percentage override, falling back to royalty_percent.
+        fn currency_royalty_percent(&self, currency: ResourceAddress) -> Decimal {
+            self.royalty_config
+                .currency_royalty_percents
+                .get(&currency)
+                .map_or(self.royalty_config.royalty_percent, |royalty_percent| {
+                    *royalty_percent
+                })
+        }
     }
 }
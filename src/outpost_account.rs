@@ -1,4 +1,7 @@
 use crate::outpost_event::event;
+use crate::outpost_event::Settlement;
+use crate::outpost_factory::openhub::OpenHub;
+use crate::outpost_factory::{CollectionFlag, FlagSeverity};
 use scrypto::component::AccountLocker;
 use scrypto::prelude::*;
 /// This blueprint is a trader account - where they can list items and where items are purchased from. Each method calls the event emitter component.
@@ -23,13 +26,124 @@ pub struct Listing {
     pub nfgid: NonFungibleGlobalId,
     /// trader's account address - helpful for aggregators to know where to fetch listings from.
     pub outpost_account: ComponentAddress,
+    /// An optional time before which the listing can not be purchased. This lets sellers set up listings ahead of a drop
+    /// or coordinated sale, while marketplaces can already show them as upcoming items.
+    pub start_time: Option<Instant>,
+    /// When set, any marketplace registered in the OpenHub can also purchase the NFT - in addition to the permissions above.
+    pub registered_marketplaces: bool,
+}
+
+impl Listing {
+    /// Whether the listing is open to marketplaces - either to every registered marketplace or to at least one marketplace
+    /// badge. Listings that only permit private deal badges are not.
+    pub fn is_public(&self) -> bool {
+        self.registered_marketplaces
+            || self
+                .secondary_seller_permissions
+                .iter()
+                .any(|permission| is_marketplace_badge(*permission))
+    }
+}
+
+/// Marketplaces advertise their fee rate in the metadata of their permission badge, which sets them apart from the badges
+/// issued for private deals.
+pub fn is_marketplace_badge(permission: ResourceAddress) -> bool {
+    let fee_rate: Result<Option<Decimal>, _> =
+        ResourceManager::from_address(permission).get_metadata("marketplace_fee");
+
+    matches!(fee_rate, Ok(Some(_)))
+}
+
+/// A rental listing lets a seller lend out an NFT for a fixed period. The NFT itself never leaves the Outpost - the renter
+/// receives a non-withdrawable rental pass that dApps can check to grant the renter usage of the NFT until the pass expires.
+#[derive(ScryptoSbor, Clone)]
+pub struct RentalListing {
+    /// The marketplaces or private renters that are permitted to rent the NFT.
+    pub secondary_seller_permissions: Vec<ResourceAddress>,
+    /// When set, any marketplace registered in the OpenHub can also rent out the NFT - in addition to the permissions above.
+    pub registered_marketplaces: bool,
+    /// The currency the rental fee is paid in
+    pub currency: ResourceAddress,
+    /// The fee for a single rental period - subject to marketplace fees and creator royalties in the same way as a sale.
+    pub fee: Decimal,
+    /// The length of a single rental period in seconds
+    pub duration_seconds: i64,
+    pub nfgid: NonFungibleGlobalId,
+    pub outpost_account: ComponentAddress,
+    /// The time the current rental expires. Rentals expire lazily - once this time has passed the NFT can be rented again
+    /// or the rental listing cancelled, without the owner needing to do anything.
+    pub rented_until: Option<Instant>,
+}
+
+/// The non-withdrawable pass issued to a renter. dApps can check the rented NFT and expiry time to grant usage rights.
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct RentalPass {
+    pub nfgid: NonFungibleGlobalId,
+    pub outpost_account: ComponentAddress,
+    pub expires_at: Instant,
+}
+
+#[derive(ScryptoSbor, Clone, PartialEq, Eq, Debug)]
+pub enum LoanStatus {
+    /// The lender has escrowed the principal and is waiting for the borrower to accept
+    Offered,
+    /// The borrower has accepted and the collateral is locked until repayment or the deadline
+    Active,
+    /// The borrower has repaid and the repayment is waiting to be claimed by the lender
+    Repaid,
+    /// The lender has claimed the principal (cancelled offer), the repayment or the collateral (default)
+    Claimed,
+}
+
+/// A loan offered against NFTs escrowed in an Outpost. The lender escrows the principal and receives a loan note, which is
+/// used to claim the repayment, the collateral on default, or the principal back if the offer is never accepted.
+#[derive(ScryptoSbor, Clone)]
+pub struct Loan {
+    pub principal_currency: ResourceAddress,
+    pub principal_amount: Decimal,
+    /// The amount the borrower must repay (in the principal currency) before the deadline to release the collateral
+    pub repayment_amount: Decimal,
+    pub duration_seconds: i64,
+    /// The escrowed NFTs the lender wants as collateral, each with the share of the principal it is valued at. Royalties
+    /// on seized Royalty NFTs are paid on these values.
+    pub collateral: Vec<(NonFungibleGlobalId, Decimal)>,
+    /// The account defaulted Royalty NFTs are deposited to
+    pub lender_account: Global<Account>,
+    /// Set when the borrower accepts the loan
+    pub deadline: Option<Instant>,
+    pub status: LoanStatus,
+}
+
+#[derive(ScryptoSbor, NonFungibleData)]
+pub struct LoanNote {
+    pub loan_id: u64,
+    pub outpost_account: ComponentAddress,
+}
+
+/// An entry on an Outpost's buyer blocklist. Sellers can refuse a whole marketplace or private deal resource,
+/// a single badge (e.g. one private deal badge out of a shared resource) or the account receiving the NFTs.
+#[derive(ScryptoSbor, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum BlockedBuyer {
+    Permission(ResourceAddress),
+    Badge(NonFungibleGlobalId),
+    Recipient(ComponentAddress),
 }
 
 type Unit = ();
 
 #[blueprint]
-#[types(Listing, ResourceAddress, NonFungibleGlobalId, Vault, Hash, Unit)]
-#[events(ListingCreated, ListingUpdated, ListingCanceled, ListingPurchased)]
+#[types(
+    Listing,
+    ResourceAddress,
+    NonFungibleGlobalId,
+    Vault,
+    Hash,
+    Unit,
+    BlockedBuyer,
+    RentalListing,
+    Loan,
+    u64
+)]
 mod opentrader {
 
     enable_package_royalties! {
@@ -40,6 +154,7 @@ mod opentrader {
         transfer_royal_nft_to_component => Free;
         revoke_market_permission => Xrd(dec!(0.000000000000000001).into());
         add_buyer_permission => Xrd(dec!(0.000000000000000001).into());
+        set_registered_marketplaces => Xrd(dec!(0.000000000000000001).into());
         change_price => Xrd(dec!(0.000000000000000001).into());
         cancel_listing => Xrd(dec!(0.000000000000000001).into());
         cancel_royal_listing => Xrd(dec!(0.000000000000000001).into());
@@ -54,11 +169,31 @@ mod opentrader {
         multi_purchase_honour_listing => Free;
         multi_cleared => Free;
         transient_token_address => Free;
+        close_outpost => Free;
+        receive_migration => Free;
+        export_for_upgrade => Free;
+        import_upgrade => Free;
+        linked_account => Free;
+        block_buyer => Free;
+        unblock_buyer => Free;
+        is_buyer_blocked => Free;
+        list_rental => Xrd(dec!(0.000000000000000001).into());
+        cancel_rental_listing => Xrd(dec!(0.000000000000000001).into());
+        rent => Xrd(dec!(0.000000000000000001).into());
+        rental_pass_address => Free;
+        offer_loan => Xrd(dec!(0.000000000000000001).into());
+        accept_loan => Xrd(dec!(0.000000000000000001).into());
+        repay_loan => Free;
+        claim_loan => Free;
+        get_loan => Free;
+        loan_note_address => Free;
+        relink_account => Free;
     }
 
     enable_method_auth! {
     roles {
         admin => updatable_by: [];
+        hub => updatable_by: [];
     },
     methods {
         list => restrict_to: [admin];
@@ -68,6 +203,7 @@ mod opentrader {
         transfer_royal_nft_to_component => restrict_to: [admin];
         revoke_market_permission => restrict_to: [admin];
         add_buyer_permission => restrict_to: [admin];
+        set_registered_marketplaces => restrict_to: [admin];
         change_price => restrict_to: [admin];
         cancel_listing => restrict_to: [admin];
         cancel_royal_listing => restrict_to: [admin];
@@ -81,6 +217,25 @@ mod opentrader {
         transient_token_address => PUBLIC;
         multi_purchase_honour_listing => PUBLIC;
         purchase_multi_royal_listings => PUBLIC;
+        close_outpost => restrict_to: [admin];
+        receive_migration => PUBLIC;
+        export_for_upgrade => restrict_to: [hub];
+        import_upgrade => restrict_to: [hub];
+        linked_account => PUBLIC;
+        block_buyer => restrict_to: [admin];
+        unblock_buyer => restrict_to: [admin];
+        is_buyer_blocked => PUBLIC;
+        list_rental => restrict_to: [admin];
+        cancel_rental_listing => restrict_to: [admin];
+        rent => PUBLIC;
+        rental_pass_address => PUBLIC;
+        offer_loan => PUBLIC;
+        accept_loan => restrict_to: [admin];
+        repay_loan => restrict_to: [admin];
+        claim_loan => PUBLIC;
+        get_loan => PUBLIC;
+        loan_note_address => PUBLIC;
+        relink_account => restrict_to: [hub];
     }
     }
 
@@ -89,10 +244,9 @@ mod opentrader {
         listings: KeyValueStore<NonFungibleGlobalId, Listing>,
         /// The key value store of vaults that store all the NFTs that the user has listed for sale.
         nft_vaults: KeyValueStore<ResourceAddress, Vault>,
-        /// The key value store of vaults that store all the revenue the user has made from sales.
-        /// This is used to store the revenue until the user claims it. However a future ambition is to use AccountLockers.
-        /// Multiple currencies are supported.
-        sales_revenue: KeyValueStore<ResourceAddress, Vault>,
+        /// The resource addresses that have a vault in nft_vaults. Key value stores can't be iterated, so we track these
+        /// in order to be able to empty every vault when the Outpost is closed or migrated.
+        nft_vault_resources: Vec<ResourceAddress>,
         /// The royal admin badge that is used to authenticate deposits of Royalty NFTs.
         /// A user should never be able to withdraw this badge or access it in a unintended manner.
         royal_admin: Vault,
@@ -123,6 +277,32 @@ mod opentrader {
         transient_tokens: Vault,
         /// Transient token address
         transient_token_address: ResourceAddress,
+        /// Once an Outpost has been closed (or migrated to a new Outpost) no new listings can be created on it.
+        closed: bool,
+        /// Outpost-wide deny list of marketplaces, badges and recipient accounts. Checked by every purchase path before settlement.
+        blocklist: KeyValueStore<BlockedBuyer, ()>,
+        /// The entries of the blocklist, kept alongside the key value store so the blocklist can be carried over in an upgrade
+        blocked_buyers: Vec<BlockedBuyer>,
+        /// The key value store of rental listings. NFTs listed for rent are held in the same nft_vaults as NFTs listed for sale.
+        rental_listings: KeyValueStore<NonFungibleGlobalId, RentalListing>,
+        /// The resource manager of the rental passes issued by this Outpost. Only this component can mint them.
+        rental_pass_manager: NonFungibleResourceManager,
+        /// Counter used for the local ids of rental passes
+        rental_pass_count: u64,
+        /// Loans offered against NFTs escrowed in this Outpost, keyed by loan id
+        loans: KeyValueStore<u64, Loan>,
+        /// The principal of each loan while it is on offer, and the repayment once it has been repaid
+        loan_vaults: KeyValueStore<u64, Vault>,
+        /// Escrowed NFTs locked as collateral, mapped to the id of the loan they are locked by
+        locked_collateral: KeyValueStore<NonFungibleGlobalId, u64>,
+        /// The resource manager of the loan notes issued to lenders. Only this component can mint and burn them.
+        loan_note_manager: NonFungibleResourceManager,
+        /// Counter used for loan ids, which are also the local ids of the loan notes
+        loan_count: u64,
+        /// The number of accepted loans that have not been repaid or claimed yet, and so still lock escrowed collateral
+        active_loans: u64,
+        /// The hub that created this Outpost, checked for emergency pauses before any listing or purchase
+        open_hub: Global<OpenHub>,
     }
 
     impl OpenTrader {
@@ -137,10 +317,68 @@ mod opentrader {
             dapp_global: GlobalAddress,
             locker: Global<AccountLocker>,
             transient_token: FungibleBucket,
+            hub: ComponentAddress,
         ) -> Global<OpenTrader> {
             let (trader_address_reservation, trader_component_address) =
                 Runtime::allocate_component_address(OpenTrader::blueprint_id());
-            // let global_caller_badge_rule = rule!(require(global_caller(trader_component_address)));
+            let global_caller_badge_rule = rule!(require(global_caller(trader_component_address)));
+
+            // Rental passes can't be withdrawn so that a renter can't pass on an NFT they have rented.
+            let rental_pass_manager =
+                ResourceBuilder::new_integer_non_fungible::<RentalPass>(OwnerRole::None)
+                    .metadata(metadata!(
+                        roles {
+                            metadata_setter => rule!(deny_all);
+                            metadata_setter_updater => rule!(deny_all);
+                            metadata_locker => rule!(deny_all);
+                            metadata_locker_updater => rule!(deny_all);
+                        },
+                        init {
+                            "name" => "Outpost Rental Pass".to_owned(), locked;
+                            "description" => "Grants usage of an NFT rented from an Outpost until the pass expires".to_owned(), locked;
+                            "dapp_definition" => dapp_global, locked;
+                            "icon_url" => Url::of("https://www.outpost.trade/img/outpost_symbol.png"), locked;
+                        }
+                    ))
+                    .mint_roles(mint_roles!(
+                        minter => global_caller_badge_rule.clone();
+                        minter_updater => rule!(deny_all);
+                    ))
+                    .burn_roles(burn_roles!(
+                        burner => rule!(allow_all);
+                        burner_updater => rule!(deny_all);
+                    ))
+                    .withdraw_roles(withdraw_roles!(
+                        withdrawer => rule!(deny_all);
+                        withdrawer_updater => rule!(deny_all);
+                    ))
+                    .create_with_no_initial_supply();
+
+            let loan_note_manager =
+                ResourceBuilder::new_integer_non_fungible::<LoanNote>(OwnerRole::None)
+                    .metadata(metadata!(
+                        roles {
+                            metadata_setter => rule!(deny_all);
+                            metadata_setter_updater => rule!(deny_all);
+                            metadata_locker => rule!(deny_all);
+                            metadata_locker_updater => rule!(deny_all);
+                        },
+                        init {
+                            "name" => "Outpost Loan Note".to_owned(), locked;
+                            "description" => "A claim on a loan made against NFTs escrowed in an Outpost".to_owned(), locked;
+                            "dapp_definition" => dapp_global, locked;
+                            "icon_url" => Url::of("https://www.outpost.trade/img/outpost_symbol.png"), locked;
+                        }
+                    ))
+                    .mint_roles(mint_roles!(
+                        minter => global_caller_badge_rule.clone();
+                        minter_updater => rule!(deny_all);
+                    ))
+                    .burn_roles(burn_roles!(
+                        burner => global_caller_badge_rule.clone();
+                        burner_updater => rule!(deny_all);
+                    ))
+                    .create_with_no_initial_supply();
 
             let (auth_key_resource, auth_key_local) = auth_key.clone().into_parts();
 
@@ -154,6 +392,12 @@ mod opentrader {
                 transient_token_vault.put(transient_token.into());
             });
 
+            event_manager.outpost_created_event(
+                trader_component_address,
+                my_account.address(),
+                emitter_badge.create_proof_of_all(),
+            );
+
             Self {
                 auth_key_local,
                 auth_key_resource,
@@ -165,13 +409,27 @@ mod opentrader {
                 event_manager,
                 trader_account_component_address: trader_component_address,
                 nft_vaults: KeyValueStore::<ResourceAddress, Vault>::new_with_registered_type(),
-                sales_revenue: KeyValueStore::<ResourceAddress, Vault>::new_with_registered_type(),
+                nft_vault_resources: vec![],
                 royal_admin: Vault::with_bucket(depositer_admin),
                 transactions: KeyValueStore::<Hash, Unit>::new_with_registered_type(),
                 latest_transaction: None,
                 latest_bulk_transaction: None,
                 transient_tokens: transient_token_vault,
                 transient_token_address,
+                closed: false,
+                blocklist: KeyValueStore::<BlockedBuyer, Unit>::new_with_registered_type(),
+                blocked_buyers: vec![],
+                rental_listings:
+                    KeyValueStore::<NonFungibleGlobalId, RentalListing>::new_with_registered_type(),
+                rental_pass_manager,
+                rental_pass_count: 0,
+                loans: KeyValueStore::<u64, Loan>::new_with_registered_type(),
+                loan_vaults: KeyValueStore::<u64, Vault>::new_with_registered_type(),
+                locked_collateral: KeyValueStore::<NonFungibleGlobalId, u64>::new_with_registered_type(),
+                loan_note_manager,
+                loan_count: 0,
+                active_loans: 0,
+                open_hub: Global::from(hub),
             }
             .instantiate()
             .prepare_to_globalize(OwnerRole::None)
@@ -191,6 +449,7 @@ mod opentrader {
             ))
             .roles(roles!(
                 admin => rule!(require(auth_key));
+                hub => rule!(require(global_caller(hub)));
             ))
             .with_address(trader_address_reservation)
             .globalize()
@@ -204,7 +463,15 @@ mod opentrader {
             currency: ResourceAddress,
             permissions: Vec<ResourceAddress>,
             items: NonFungibleBucket,
+            start_time: Option<Instant>,
+            registered_marketplaces: bool,
         ) {
+            assert!(!self.closed, "[multi_list] This Outpost has been closed");
+
+            self.assert_trading_open(items.resource_address());
+
+            self.check_collection_flag(items.resource_address());
+
             // We take the hash of the listing as to prevent a user from listing and selling an NFT in the same tx - i.e.
             // calling the list method and purchase method within the same transaction which could be used to send an NFT to another user for free
             // without any risk of someone sniping it.
@@ -223,6 +490,8 @@ mod opentrader {
                         price: *price,
                         nfgid: nfgid.clone(),
                         outpost_account,
+                        start_time,
+                        registered_marketplaces,
                     };
 
                     self.listings.insert(nfgid.clone(), new_listing.clone());
@@ -278,6 +547,7 @@ mod opentrader {
                 } else {
                     self.nft_vaults
                         .insert(nft_address.clone(), Vault::with_bucket(items.into()));
+                    self.nft_vault_resources.push(nft_address);
                 }
             });
         }
@@ -298,11 +568,21 @@ mod opentrader {
             // The permissions that a secondary seller must have to sell an NFT. This is used to ensure that only selected
             // marketplaces or private buyers can buy an NFT.
             permissions: Vec<ResourceAddress>,
+            // An optional time before which the listing can not be purchased (e.g. for a scheduled drop)
+            start_time: Option<Instant>,
+            // Whether every marketplace registered in the OpenHub can also purchase the NFT, in addition to the permissions above
+            registered_marketplaces: bool,
             // The badge that is used to authenticate the user listing the NFT
             // trader_badge: Proof,
         ) {
             // authenticate user happens at a system level
 
+            assert!(!self.closed, "[list_nft] This Outpost has been closed");
+
+            self.assert_trading_open(nft_to_list.resource_address());
+
+            self.check_collection_flag(nft_to_list.resource_address());
+
             assert!(
                 price > Decimal::zero(),
                 "[list_nft] Listing price must be greater than zero"
@@ -337,6 +617,8 @@ mod opentrader {
                 price,
                 nfgid: nfgid.clone(),
                 outpost_account,
+                start_time,
+                registered_marketplaces,
             };
 
             // add the listing information. We don't need to worry about
@@ -360,6 +642,7 @@ mod opentrader {
                 } else {
                     self.nft_vaults
                         .insert(nft_address, Vault::with_bucket(nft_to_list.into()));
+                    self.nft_vault_resources.push(nft_address);
                 }
             });
 
@@ -376,6 +659,12 @@ mod opentrader {
             // set the latest bulk transaction for later verification and clearing
             self.latest_bulk_transaction = Some((account_recipient, nfgids.clone()));
 
+            self.assert_buyer_not_blocked(&permission, account_recipient.address());
+
+            for nfgid in nfgids.iter() {
+                self.assert_trading_open(nfgid.resource_address());
+            }
+
             let marketplace = permission.resource_address();
 
             let listings: Vec<Listing> = nfgids
@@ -387,10 +676,16 @@ mod opentrader {
                         .expect("[purchase] Listing not found");
 
                     assert!(
-                        listing.secondary_seller_permissions.contains(&marketplace),
+                        self.is_marketplace_permitted(
+                            &listing.secondary_seller_permissions,
+                            listing.registered_marketplaces,
+                            marketplace
+                        ),
                         "[purchase] Marketplace does not have permission to purchase this listing"
                     );
 
+                    Self::assert_listing_started(&listing);
+
                     listing.clone()
                 })
                 .collect();
@@ -416,11 +711,8 @@ mod opentrader {
             );
 
             // Calculate marketplace fee
-            let marketplace_fee_option: Option<Decimal> = permission
-                .skip_checking()
-                .resource_manager()
-                .get_metadata("marketplace_fee")
-                .unwrap();
+            let marketplace_fee_option: Option<Decimal> =
+                self.marketplace_fee_rate(permission.resource_address());
 
             let marketplace_fee = if let Some(marketplace_fee_rate) = marketplace_fee_option {
                 payment.amount().checked_mul(marketplace_fee_rate).unwrap()
@@ -430,6 +722,7 @@ mod opentrader {
             // Prepare return buckets
             let nft_bucket: Bucket;
             let mut marketplace_fee_bucket: Option<Bucket> = None;
+            let settlement: Settlement;
 
             {
                 let transaction_hash = Runtime::transaction_hash();
@@ -447,12 +740,10 @@ mod opentrader {
                     .collect();
 
                 // Take NFT from vault
-                let nft = self
+                nft_bucket = self
                     .nft_vaults
                     .get_mut(&nft_address)
-                    .expect("[purchase] NFT not found");
-
-                nft_bucket = nft
+                    .expect("[purchase] NFT not found")
                     .as_non_fungible()
                     .take_non_fungibles(&local_id_index_set)
                     .into();
@@ -480,6 +771,8 @@ mod opentrader {
                     ObjectStubHandle::Global(GlobalAddress::from(royalty_component)),
                 ));
 
+                let payment_amount = payment.amount();
+
                 // We send the full payment to the royalty component so that it can take its %fee.
                 // We also provide the trading permission to check against any other permissions the creator has set.
                 let mut remainder_after_royalty: Bucket =
@@ -487,13 +780,17 @@ mod opentrader {
                         "pay_royalty",
                         scrypto_args!(
                             nft_address,
-                            local_id_index_set,
+                            Self::listing_prices(&listings),
                             payment,
                             marketplace,
                             account_recipient
                         ),
                     );
 
+                let royalty_amount = payment_amount
+                    .checked_sub(remainder_after_royalty.amount())
+                    .unwrap();
+
                 // we then take the marketplaces fee (we've already calculated this earlier based on the full payment amount).
 
                 if marketplace_fee_option.is_some() {
@@ -505,26 +802,29 @@ mod opentrader {
                     marketplace_fee_bucket = None;
                 }
 
-                // Create emitter proof and emit bulk event
-                let emitter_proof = self
-                    .emitter_badge
-                    .as_non_fungible()
-                    .create_proof_of_non_fungibles(&indexset![self.emitter_badge_local.clone()]);
+                let protocol_fee = Self::take_protocol_fee(
+                    self.open_hub,
+                    &mut remainder_after_royalty,
+                    marketplace,
+                    payment_amount,
+                );
+
+                settlement = Settlement {
+                    permission: marketplace,
+                    recipient: account_recipient.address(),
+                    marketplace_fee: marketplace_fee_bucket
+                        .as_ref()
+                        .map_or(dec!(0), |fee| fee.amount()),
+                    royalty_amount,
+                    protocol_fee,
+                    seller_proceeds: remainder_after_royalty.amount(),
+                };
+
                 // Store the remaining payment
-                emitter_proof.authorize(|| {
-                    self.account_locker.store(
-                        self.my_account,
-                        remainder_after_royalty.into(),
-                        true,
-                    );
-                });
+                self.store_revenue(remainder_after_royalty.into());
             }
 
-            // self.event_manager
-            //     .multi_purchase_event(listings, emitter_proof.clone().into());
-            {
-                self.multi_purchase_event(listings);
-            }
+            self.multi_purchase_event(listings, settlement);
 
             // We turn off deposit restrictions. However a transient token will be emitted by this method that will be used to clear the transaction
             // an set the deposit rules again.
@@ -604,6 +904,8 @@ mod opentrader {
             // The account that the NFT should be sent to
             account_recipient: Global<Account>,
         ) -> (Bucket, Bucket, Option<Bucket>) {
+            self.assert_trading_open(nfgid.resource_address());
+
             let purchased_nft: Bucket;
 
             // log current transaction for later verification and clearing
@@ -615,6 +917,7 @@ mod opentrader {
 
             let mut marketplace_fee_bucket: Option<Bucket> = None;
             let listing_event: Listing;
+            let settlement: Settlement;
 
             let (nft_address, nft_local) = nfgid.clone().into_parts();
 
@@ -624,6 +927,8 @@ mod opentrader {
 
             let trading_permission = permission.resource_address();
 
+            self.assert_buyer_not_blocked(&permission, account_recipient.address());
+
             {
                 let listing_permission = self
                     .listings
@@ -631,11 +936,15 @@ mod opentrader {
                     .expect("[purchase] Listing not found");
 
                 assert!(
-                    listing_permission
-                        .secondary_seller_permissions
-                        .contains(&trading_permission),
+                    self.is_marketplace_permitted(
+                        &listing_permission.secondary_seller_permissions,
+                        listing_permission.registered_marketplaces,
+                        trading_permission
+                    ),
                     "[purchase] Marketplace does not have permission to purchase this listing"
                 );
+
+                Self::assert_listing_started(&listing_permission);
             }
 
             // We get the marketplace fee rate from the metadata of the proof
@@ -643,11 +952,8 @@ mod opentrader {
             // This could be an unsafe decimal at this point - however when taking from the payment we use a safe rounding mode.
             // If not marketplace fee is set, we set the rate to 0.
 
-            let marketplace_fee_option: Option<Decimal> = permission
-                .skip_checking()
-                .resource_manager()
-                .get_metadata("marketplace_fee")
-                .unwrap();
+            let marketplace_fee_option: Option<Decimal> =
+                self.marketplace_fee_rate(permission.resource_address());
 
             let marketplace_fee_rate: Decimal;
             let marketplace_fee: Decimal;
@@ -660,21 +966,20 @@ mod opentrader {
 
             // We retrieve basic information about the listing, such as price, currency and time of the listing.
             {
-                let listing = self
+                listing_event = self
                     .listings
-                    .get_mut(&nfgid)
-                    .expect("[purchase] Listing not found");
-
-                listing_event = listing.clone();
+                    .get(&nfgid)
+                    .expect("[purchase] Listing not found")
+                    .clone();
 
-                let price = listing.price;
+                let price = listing_event.price;
 
                 assert!(
                     payment.amount() == price,
                     "[purchase] Payment amount does not match listing price"
                 );
 
-                let currency = listing.currency;
+                let currency = listing_event.currency;
 
                 assert!(
                     payment.resource_address() == currency,
@@ -694,12 +999,13 @@ mod opentrader {
 
                 // We get the NFT from the vault
 
-                let vault = self
+                purchased_nft = self
                     .nft_vaults
                     .get_mut(&nft_address)
-                    .expect("[purchase] NFT not found");
-
-                purchased_nft = vault.as_non_fungible().take_non_fungible(&nft_local).into();
+                    .expect("[purchase] NFT not found")
+                    .as_non_fungible()
+                    .take_non_fungible(&nft_local)
+                    .into();
 
                 // We get the royalty component address from the NFT metadata
 
@@ -717,14 +1023,25 @@ mod opentrader {
                     ObjectStubHandle::Global(GlobalAddress::from(royalty_component)),
                 ));
 
+                let payment_amount = payment.amount();
+
                 // We send the full payment to the royalty component so that it can take its %fee.
                 // We also provide the trading permission to check against any other permissions the creator has set.
                 let mut remainder_after_royalty: Bucket =
                     Global::<AnyComponent>::from(call_address).call_raw(
                         "pay_royalty_basic",
-                        scrypto_args!(nft_address, payment, trading_permission),
+                        scrypto_args!(
+                            nft_address,
+                            indexmap::IndexMap::from([(nft_local.clone(), payment_amount)]),
+                            payment,
+                            trading_permission
+                        ),
                     );
 
+                let royalty_amount = payment_amount
+                    .checked_sub(remainder_after_royalty.amount())
+                    .unwrap();
+
                 // we then take the marketplaces fee (we've already calculated this earlier based on the full payment amount).
 
                 if marketplace_fee_option.is_some() {
@@ -734,18 +1051,26 @@ mod opentrader {
                     ));
                 }
 
-                let locker_proof = self
-                    .emitter_badge
-                    .as_non_fungible()
-                    .create_proof_of_non_fungibles(&indexset![self.emitter_badge_local.clone()]);
+                let protocol_fee = Self::take_protocol_fee(
+                    self.open_hub,
+                    &mut remainder_after_royalty,
+                    trading_permission,
+                    payment_amount,
+                );
+
+                settlement = Settlement {
+                    permission: trading_permission,
+                    recipient: account_recipient.address(),
+                    marketplace_fee: marketplace_fee_bucket
+                        .as_ref()
+                        .map_or(dec!(0), |fee| fee.amount()),
+                    royalty_amount,
+                    protocol_fee,
+                    seller_proceeds: remainder_after_royalty.amount(),
+                };
+
                 // Take the payment for the NFT
-                locker_proof.authorize(|| {
-                    self.account_locker.store(
-                        self.my_account,
-                        remainder_after_royalty.into(),
-                        true,
-                    );
-                });
+                self.store_revenue(remainder_after_royalty.into());
 
                 // We turn off deposit restrictions. However a transient token will be emitted by this method that will be used to clear the transaction
                 // an set the deposit rules again.
@@ -758,7 +1083,7 @@ mod opentrader {
 
             // finally we emit a listing event via the event emitter component
 
-            self.purchase_listing_event(listing_event, nfgid);
+            self.purchase_listing_event(listing_event, nfgid, settlement);
 
             (
                 purchased_nft,
@@ -861,9 +1186,23 @@ mod opentrader {
                 // Assertion passed - the caller is the owner of the account.
             }
 
+            let nft_ids = Self::global_ids(&royalty_nft);
+
             self.royal_admin.as_fungible().authorize_with_amount(1, || {
                 recipient.try_deposit_or_abort(royalty_nft.into(), None);
             });
+
+            let emitter_proof = self
+                .emitter_badge
+                .as_non_fungible()
+                .create_proof_of_non_fungibles(&indexset![self.emitter_badge_local.clone()]);
+
+            self.event_manager.same_owner_transfer_event(
+                self.trader_account_component_address,
+                nft_ids,
+                recipient.address(),
+                emitter_proof.into(),
+            );
         }
 
         /// Transfers an NFT to a component. This method is used to transfer an NFT to a component that is not an account.
@@ -918,6 +1257,21 @@ mod opentrader {
                 ObjectStubHandle::Global(GlobalAddress::from(royalty_component)),
             ));
 
+            let nft_ids = Self::global_ids(&royalty_nft);
+
+            let emitter_proof = self
+                .emitter_badge
+                .as_non_fungible()
+                .create_proof_of_non_fungibles(&indexset![self.emitter_badge_local.clone()]);
+
+            self.event_manager.dapp_transfer_event(
+                self.trader_account_component_address,
+                nft_ids,
+                component.address(),
+                custom_method.clone(),
+                emitter_proof.into(),
+            );
+
             // We don't need to authorise anything here as deposits will be authorised from the royalty component.
 
             let returned_buckets_full: Option<Vec<Bucket>> =
@@ -939,7 +1293,15 @@ mod opentrader {
             currency: ResourceAddress,
             permissions: Vec<ResourceAddress>,
             items: NonFungibleBucket,
+            start_time: Option<Instant>,
+            registered_marketplaces: bool,
         ) {
+            assert!(!self.closed, "[multi_list] This Outpost has been closed");
+
+            self.assert_trading_open(items.resource_address());
+
+            self.check_collection_flag(items.resource_address());
+
             let full_listings: Vec<Listing> = listings
                 .iter()
                 .map(|(nfgid, price)| {
@@ -951,6 +1313,8 @@ mod opentrader {
                         price: *price,
                         nfgid: nfgid.clone(),
                         outpost_account,
+                        start_time,
+                        registered_marketplaces,
                     };
 
                     self.listings.insert(nfgid.clone(), new_listing.clone());
@@ -1004,6 +1368,7 @@ mod opentrader {
             } else {
                 self.nft_vaults
                     .insert(nft_address.clone(), Vault::with_bucket(items.into()));
+                self.nft_vault_resources.push(nft_address);
             }
         }
 
@@ -1013,9 +1378,17 @@ mod opentrader {
             currency: ResourceAddress,
             price: Decimal,
             permissions: Vec<ResourceAddress>,
+            start_time: Option<Instant>,
+            registered_marketplaces: bool,
         ) {
+            assert!(!self.closed, "[list_nft] This Outpost has been closed");
+
             assert!(!nft_bucket.is_empty(), "[list_nft] No NFT provided");
 
+            self.assert_trading_open(nft_bucket.resource_address());
+
+            self.check_collection_flag(nft_bucket.resource_address());
+
             assert!(
                 price > Decimal::zero(),
                 "[list_nft] Listing price must be greater than zero"
@@ -1039,6 +1412,8 @@ mod opentrader {
                 price,
                 nfgid: nfgid.clone(),
                 outpost_account,
+                start_time,
+                registered_marketplaces,
             };
 
             let nft_address = nft_bucket.resource_address();
@@ -1054,6 +1429,7 @@ mod opentrader {
             } else {
                 self.nft_vaults
                     .insert(nft_address.clone(), Vault::with_bucket(nft_bucket.into()));
+                self.nft_vault_resources.push(nft_address);
             }
 
             self.listings.insert(nfgid.clone(), new_listing.clone());
@@ -1107,6 +1483,26 @@ mod opentrader {
             self.update_listing_event(listing.clone(), nft_id);
         }
 
+        /// Opts a listing into (or out of) being purchasable by every marketplace registered in the OpenHub, rather than
+        /// only the marketplaces listed in its permissions.
+        pub fn set_registered_marketplaces(&mut self, nft_id: NonFungibleGlobalId, enabled: bool) {
+            {
+                let mut listing = self
+                    .listings
+                    .get_mut(&nft_id)
+                    .expect("[registered_marketplaces] Listing not found");
+
+                listing.registered_marketplaces = enabled;
+            }
+
+            let listing = self
+                .listings
+                .get(&nft_id)
+                .expect("[registered_marketplaces] Listing not found");
+
+            self.update_listing_event(listing.clone(), nft_id);
+        }
+
         pub fn change_price(&mut self, nft_id: NonFungibleGlobalId, new_price: Decimal) {
             {
                 let mut listing = self
@@ -1156,7 +1552,14 @@ mod opentrader {
             nfgids: Vec<NonFungibleGlobalId>,
             mut payment: FungibleBucket,
             permission: Proof,
+            account_recipient: Global<Account>,
         ) -> (Vec<Bucket>, Vec<Bucket>) {
+            self.assert_buyer_not_blocked(&permission, account_recipient.address());
+
+            for nfgid in nfgids.iter() {
+                self.assert_trading_open(nfgid.resource_address());
+            }
+
             // Validate all listings exist and marketplace has permission
             let marketplace = permission.resource_address();
             let listings: Vec<Listing> = nfgids
@@ -1168,10 +1571,16 @@ mod opentrader {
                         .expect("[purchase] Listing not found");
 
                     assert!(
-                        listing.secondary_seller_permissions.contains(&marketplace),
+                        self.is_marketplace_permitted(
+                            &listing.secondary_seller_permissions,
+                            listing.registered_marketplaces,
+                            marketplace
+                        ),
                         "[purchase] Marketplace does not have permission to purchase this listing"
                     );
 
+                    Self::assert_listing_started(&listing);
+
                     listing.clone()
                 })
                 .collect();
@@ -1197,11 +1606,8 @@ mod opentrader {
             );
 
             // Calculate marketplace fee
-            let marketplace_fee_option: Option<Decimal> = permission
-                .skip_checking()
-                .resource_manager()
-                .get_metadata("marketplace_fee")
-                .unwrap();
+            let marketplace_fee_option: Option<Decimal> =
+                self.marketplace_fee_rate(permission.resource_address());
 
             let marketplace_fee = if let Some(marketplace_fee_rate) = marketplace_fee_option {
                 payment.amount().checked_mul(marketplace_fee_rate).unwrap()
@@ -1238,19 +1644,28 @@ mod opentrader {
                 self.listings.remove(nfgid);
             }
 
-            // Create emitter proof and emit bulk event
-            let emitter_proof = self
-                .emitter_badge
-                .as_non_fungible()
-                .create_proof_of_non_fungibles(&indexset![self.emitter_badge_local.clone()]);
+            let protocol_fee = Self::take_protocol_fee(
+                self.open_hub,
+                &mut payment.0,
+                marketplace,
+                total_price,
+            );
+
+            let settlement = Settlement {
+                permission: marketplace,
+                recipient: account_recipient.address(),
+                marketplace_fee: fee_buckets.iter().fold(dec!(0), |acc, fee| {
+                    acc.checked_add(fee.amount()).unwrap()
+                }),
+                royalty_amount: dec!(0),
+                protocol_fee,
+                seller_proceeds: payment.amount(),
+            };
 
-            self.multi_purchase_event(listings);
+            self.multi_purchase_event(listings, settlement);
 
             // Store the remaining payment
-            emitter_proof.authorize(|| {
-                self.account_locker
-                    .store(self.my_account, payment.into(), true);
-            });
+            self.store_revenue(payment.into());
 
             (nft_buckets, fee_buckets)
         }
@@ -1260,7 +1675,14 @@ mod opentrader {
             nfgids: Vec<NonFungibleGlobalId>,
             mut payment: FungibleBucket,
             permission: Proof,
+            account_recipient: Global<Account>,
         ) -> (Vec<Bucket>, Vec<Bucket>) {
+            self.assert_buyer_not_blocked(&permission, account_recipient.address());
+
+            for nfgid in nfgids.iter() {
+                self.assert_trading_open(nfgid.resource_address());
+            }
+
             // Validate all listings exist and marketplace has permission
             let marketplace = permission.resource_address();
             let listings: Vec<Listing> = nfgids
@@ -1272,10 +1694,16 @@ mod opentrader {
                         .expect("[purchase] Listing not found");
 
                     assert!(
-                        listing.secondary_seller_permissions.contains(&marketplace),
+                        self.is_marketplace_permitted(
+                            &listing.secondary_seller_permissions,
+                            listing.registered_marketplaces,
+                            marketplace
+                        ),
                         "[purchase] Marketplace does not have permission to purchase this listing"
                     );
 
+                    Self::assert_listing_started(&listing);
+
                     listing.clone()
                 })
                 .collect();
@@ -1324,15 +1752,17 @@ mod opentrader {
             let mut remainder_after_royalty: Bucket = Global::<AnyComponent>::from(call_address)
                 .call_raw(
                     "pay_royalty_basic",
-                    scrypto_args!(nft_address, payment, marketplace),
+                    scrypto_args!(
+                        nft_address,
+                        Self::listing_prices(&listings),
+                        payment,
+                        marketplace
+                    ),
                 );
 
             // Calculate marketplace fee
-            let marketplace_fee_option: Option<Decimal> = permission
-                .skip_checking()
-                .resource_manager()
-                .get_metadata("marketplace_fee")
-                .unwrap();
+            let marketplace_fee_option: Option<Decimal> =
+                self.marketplace_fee_rate(permission.resource_address());
 
             let marketplace_fee = if let Some(marketplace_fee_rate) = marketplace_fee_option {
                 payment_cache.checked_mul(marketplace_fee_rate).unwrap()
@@ -1369,19 +1799,36 @@ mod opentrader {
                 self.listings.remove(nfgid);
             }
 
-            // Create emitter proof and emit bulk event
-            let emitter_proof = self
-                .emitter_badge
-                .as_non_fungible()
-                .create_proof_of_non_fungibles(&indexset![self.emitter_badge_local.clone()]);
+            let marketplace_fee_taken = fee_buckets.iter().fold(dec!(0), |acc, fee| {
+                acc.checked_add(fee.amount()).unwrap()
+            });
+
+            let protocol_fee = Self::take_protocol_fee(
+                self.open_hub,
+                &mut remainder_after_royalty,
+                marketplace,
+                payment_cache,
+            );
+
+            let settlement = Settlement {
+                permission: marketplace,
+                recipient: account_recipient.address(),
+                marketplace_fee: marketplace_fee_taken,
+                royalty_amount: payment_cache
+                    .checked_sub(marketplace_fee_taken)
+                    .unwrap()
+                    .checked_sub(protocol_fee)
+                    .unwrap()
+                    .checked_sub(remainder_after_royalty.amount())
+                    .unwrap(),
+                protocol_fee,
+                seller_proceeds: remainder_after_royalty.amount(),
+            };
 
-            self.multi_purchase_event(listings);
+            self.multi_purchase_event(listings, settlement);
 
             // Store the remaining payment
-            emitter_proof.authorize(|| {
-                self.account_locker
-                    .store(self.my_account, remainder_after_royalty.into(), true);
-            });
+            self.store_revenue(remainder_after_royalty.into());
 
             (nft_buckets, fee_buckets)
         }
@@ -1391,14 +1838,20 @@ mod opentrader {
             nfgid: NonFungibleGlobalId,
             mut payment: FungibleBucket,
             permission: Proof,
+            account_recipient: Global<Account>,
         ) -> (Vec<Bucket>, Vec<Bucket>) {
             let (nft_address, nft_local) = nfgid.clone().into_parts();
 
             let mut return_buckets: (Vec<Bucket>, Vec<Bucket>) = (vec![], vec![]);
             let listing_event: Listing;
 
+            self.assert_buyer_not_blocked(&permission, account_recipient.address());
+
+            self.assert_trading_open(nft_address);
+
+            let marketplace = permission.resource_address();
+
             {
-                let marketplace = permission.resource_address();
 
                 let listing_permission = self
                     .listings
@@ -1406,22 +1859,23 @@ mod opentrader {
                     .expect("[purchase] Listing not found");
 
                 assert!(
-                    listing_permission
-                        .secondary_seller_permissions
-                        .contains(&marketplace),
+                    self.is_marketplace_permitted(
+                        &listing_permission.secondary_seller_permissions,
+                        listing_permission.registered_marketplaces,
+                        marketplace
+                    ),
                     "[purchase] Marketplace does not have permission to purchase this listing"
                 );
+
+                Self::assert_listing_started(&listing_permission);
             }
             // We get the marketplace fee rate from the metadata of the proof
             // We calculate the marketplace fee from the payment amount.
             // This could be an unsafe decimal at this point - however when taking from the payment we use a safe rounding mode.
             // If not marketplace fee is set, we set the rate to 0.
 
-            let marketplace_fee_option: Option<Decimal> = permission
-                .skip_checking()
-                .resource_manager()
-                .get_metadata("marketplace_fee")
-                .unwrap();
+            let marketplace_fee_option: Option<Decimal> =
+                self.marketplace_fee_rate(permission.resource_address());
 
             let marketplace_fee_rate: Decimal;
             let marketplace_fee: Decimal;
@@ -1476,112 +1930,1373 @@ mod opentrader {
                 return_buckets.1.push(marketplace_payment.into());
             }
 
-            // finally we emit a listing event via the event emitter component
-            let emitter_proof = self
-                .emitter_badge
-                .as_non_fungible()
-                .create_proof_of_non_fungibles(&indexset![self.emitter_badge_local.clone()]);
+            let protocol_fee = Self::take_protocol_fee(
+                self.open_hub,
+                &mut payment.0,
+                marketplace,
+                listing_event.price,
+            );
 
-            // // Take the payment for the NFT
-            emitter_proof.clone().authorize(|| {
-                self.account_locker
-                    .store(self.my_account, payment.into(), true);
-            });
+            let settlement = Settlement {
+                permission: marketplace,
+                recipient: account_recipient.address(),
+                marketplace_fee: return_buckets
+                    .1
+                    .iter()
+                    .fold(dec!(0), |acc, fee| acc.checked_add(fee.amount()).unwrap()),
+                royalty_amount: dec!(0),
+                protocol_fee,
+                seller_proceeds: payment.amount(),
+            };
 
-            self.purchase_listing_event(listing_event, nfgid.clone());
+            // Take the payment for the NFT
+            self.store_revenue(payment.into());
+
+            // finally we emit a listing event via the event emitter component
+            self.purchase_listing_event(listing_event, nfgid.clone(), settlement);
 
             self.listings.remove(&nfgid);
 
             return_buckets
         }
 
-        // utility methods
+        /// Retires this Outpost. Every listing across all NFT vaults is cancelled (emitting a cancellation event for each) and the escrowed NFTs
+        /// are either returned to the linked account - Royalty NFTs via the royal deposit path - or, if a migration target is provided,
+        /// sent directly to another Outpost registered with the same hub and linked to the same account, where their listings are recreated.
+        /// Sales revenue is never held here - it goes to the linked account's locker at sale time - so there is nothing else to sweep.
+        pub fn close_outpost(&mut self, migrate_to: Option<Global<OpenTrader>>) {
+            assert!(!self.closed, "[close] This Outpost has already been closed");
 
-        pub fn fetch_auth_key(&self) -> (ResourceAddress, NonFungibleLocalId) {
-            (self.auth_key_resource, self.auth_key_local.clone())
-        }
+            if let Some(target) = migrate_to {
+                assert!(
+                    target.address() != self.trader_account_component_address,
+                    "[close] An Outpost can not be migrated to itself"
+                );
+                assert!(
+                    self.is_sibling_outpost(target.address()),
+                    "[close] Migration target must be an Outpost registered with the hub to the same account"
+                );
+            }
 
-        // event emittors
-        fn listing_event(&self, listing: Listing, nft_id: NonFungibleGlobalId) {
-            Runtime::emit_event(ListingCreated {
-                listing: listing.clone(),
-                outpost_account: listing.outpost_account,
-                nft_id,
-            });
-        }
+            self.closed = true;
 
-        fn update_listing_event(&self, listing: Listing, nft_id: NonFungibleGlobalId) {
-            Runtime::emit_event(ListingUpdated {
-                listing: listing.clone(),
-                outpost_account: listing.outpost_account,
-                nft_id,
-            });
-        }
+            let (escrowed_nfts, migrated_listings, migrated_rentals) = self.drain_escrow();
 
-        fn cancel_listing_event(&self, listing: Listing, nft_id: NonFungibleGlobalId) {
-            Runtime::emit_event(ListingCanceled {
-                listing: listing.clone(),
-                outpost_account: listing.outpost_account,
-                nft_id,
-            });
+            if migrate_to.is_none() {
+                for rental in migrated_rentals.iter() {
+                    self.rental_canceled_event(rental.clone());
+                }
+            }
+
+            let mut migrated_nfts: Vec<Bucket> = vec![];
+
+            for nft_bucket in escrowed_nfts {
+                if migrate_to.is_some() {
+                    migrated_nfts.push(nft_bucket);
+                } else {
+                    self.return_to_account(nft_bucket);
+                }
+            }
+
+            if let Some(target) = migrate_to {
+                target.receive_migration(
+                    self.trader_account_component_address,
+                    migrated_nfts,
+                    migrated_listings,
+                    migrated_rentals,
+                );
+            }
         }
 
-        fn purchase_listing_event(&self, listing: Listing, nft_id: NonFungibleGlobalId) {
-            Runtime::emit_event(ListingPurchased {
-                listing: listing.clone(),
-                outpost_account: listing.outpost_account,
-                nft_id,
-            });
+        /// Receives the escrowed NFTs, listings and rental listings of another Outpost that is being closed and migrated to this one.
+        /// The caller must be the Outpost it claims to be, and the hub must have it registered to the same account as this Outpost.
+        pub fn receive_migration(
+            &mut self,
+            from: ComponentAddress,
+            nfts: Vec<Bucket>,
+            listings: Vec<Listing>,
+            rentals: Vec<RentalListing>,
+        ) {
+            Runtime::assert_access_rule(rule!(require(global_caller(from))));
+
+            assert!(
+                self.is_sibling_outpost(from),
+                "[migrate] Migrations are only accepted from Outposts registered with the hub to the same account"
+            );
+
+            assert!(!self.closed, "[migrate] This Outpost has been closed");
+
+            self.import_escrow(nfts, listings, rentals);
         }
 
-        fn multi_listing_event(&self, listings: Vec<Listing>) {
-            // Use zip to iterate over both vectors simultaneously
-            for listing in listings {
-                Runtime::emit_event(ListingCreated {
-                    listing: listing.clone(),
-                    outpost_account: listing.outpost_account,
-                    nft_id: listing.nfgid, // Wrap single NFT ID in a vector to match event structure
-                });
+        /// Removes every escrowed NFT, listing, rental listing and blocklist entry so that this Outpost can be upgraded to a new package
+        /// version, handing back the emitter, depositer and transient badges so they can be locked in the new Outpost. Active loans lock
+        /// escrowed collateral, so they must be repaid or claimed first. Offered and repaid loans stay claimable here with their
+        /// loan notes - without a LoanClaimed event, as the emitter badge moves on - but offers can no longer be accepted. Only the hub can call this, and the Outpost is permanently
+        /// disabled afterwards - it is marked closed and holds nothing its key could still move.
+        pub fn export_for_upgrade(
+            &mut self,
+        ) -> (
+            Bucket,
+            Bucket,
+            Bucket,
+            Vec<Bucket>,
+            Vec<Listing>,
+            Vec<RentalListing>,
+            Vec<BlockedBuyer>,
+        ) {
+            assert!(!self.closed, "[upgrade] This Outpost has been closed");
+
+            assert!(
+                self.active_loans == 0,
+                "[upgrade] Every active loan must be repaid or claimed before the Outpost can be upgraded"
+            );
+
+            self.closed = true;
+
+            let (nfts, listings, rentals) = self.drain_escrow();
+
+            for buyer in self.blocked_buyers.iter() {
+                self.blocklist.remove(buyer);
             }
+
+            (
+                self.emitter_badge.take_all(),
+                self.royal_admin.take_all(),
+                self.transient_tokens.take_all(),
+                nfts,
+                listings,
+                rentals,
+                std::mem::take(&mut self.blocked_buyers),
+            )
         }
 
-        fn multi_purchase_event(&self, listings: Vec<Listing>) {
-            // Use zip to iterate over both vectors simultaneously
-            for listing in listings {
-                Runtime::emit_event(ListingPurchased {
-                    listing: listing.clone(),
-                    outpost_account: listing.outpost_account,
-                    nft_id: listing.nfgid, // Wrap single NFT ID in a vector to match event structure
-                });
+        /// Receives the escrowed NFTs, listings, rental listings and blocklist of the Outpost this one replaces in a package upgrade.
+        /// Only the hub can call this.
+        pub fn import_upgrade(
+            &mut self,
+            nfts: Vec<Bucket>,
+            listings: Vec<Listing>,
+            rentals: Vec<RentalListing>,
+            blocked_buyers: Vec<BlockedBuyer>,
+        ) {
+            assert!(!self.closed, "[upgrade] This Outpost has been closed");
+
+            self.import_escrow(nfts, listings, rentals);
+
+            for buyer in blocked_buyers {
+                self.block_buyer(buyer);
             }
         }
-    }
-}
 
-#[derive(ScryptoSbor, ScryptoEvent)]
-struct ListingCreated {
-    listing: Listing,
-    outpost_account: ComponentAddress,
-    nft_id: NonFungibleGlobalId,
-}
+        //🔑🔑🔑  Rental Methods 🔑🔑🔑 //
 
-#[derive(ScryptoSbor, ScryptoEvent)]
-struct ListingUpdated {
-    listing: Listing,
-    outpost_account: ComponentAddress,
-    nft_id: NonFungibleGlobalId,
-}
+        /// Lists an NFT for rent. The NFT is escrowed in the Outpost for as long as the rental listing exists - renters only ever
+        /// receive a rental pass. Royalty NFTs are deposited using the royal admin badge in the same way as a sale listing.
+        pub fn list_rental(
+            &mut self,
+            nft_to_rent: NonFungibleBucket,
+            fee: Decimal,
+            currency: ResourceAddress,
+            duration_seconds: i64,
+            permissions: Vec<ResourceAddress>,
+            registered_marketplaces: bool,
+        ) {
+            assert!(!self.closed, "[list_rental] This Outpost has been closed");
 
-#[derive(ScryptoSbor, ScryptoEvent)]
-struct ListingCanceled {
-    listing: Listing,
-    outpost_account: ComponentAddress,
-    nft_id: NonFungibleGlobalId,
-}
+            self.assert_trading_open(nft_to_rent.resource_address());
+
+            self.check_collection_flag(nft_to_rent.resource_address());
+
+            assert!(
+                fee > Decimal::zero(),
+                "[list_rental] Rental fee must be greater than zero"
+            );
 
-#[derive(ScryptoSbor, ScryptoEvent)]
-struct ListingPurchased {
-    listing: Listing,
-    outpost_account: ComponentAddress,
-    nft_id: NonFungibleGlobalId,
+            assert!(
+                duration_seconds > 0,
+                "[list_rental] Rental duration must be greater than zero"
+            );
+
+            assert!(
+                nft_to_rent.amount() == dec!(1),
+                "[list_rental] Only one NFT can be listed at a time"
+            );
+
+            let nft_address = nft_to_rent.resource_address();
+
+            let nfgid = NonFungibleGlobalId::new(nft_address, nft_to_rent.non_fungible_local_id());
+
+            // As with sale listings, an NFT can't be listed and rented within the same transaction.
+            let transaction_hash = Runtime::transaction_hash();
+
+            self.transactions.insert(transaction_hash, ());
+
+            let rental_listing = RentalListing {
+                secondary_seller_permissions: permissions,
+                registered_marketplaces,
+                currency,
+                fee,
+                duration_seconds,
+                nfgid: nfgid.clone(),
+                outpost_account: self.trader_account_component_address,
+                rented_until: None,
+            };
+
+            self.rental_listings
+                .insert(nfgid.clone(), rental_listing.clone());
+
+            self.royal_admin.as_fungible().authorize_with_amount(1, || {
+                let vault_exists = self.nft_vaults.get(&nft_address).is_some();
+
+                if vault_exists {
+                    let mut vault = self
+                        .nft_vaults
+                        .get_mut(&nft_address)
+                        .expect("[list_rental] NFT not found");
+                    vault.put(nft_to_rent.into());
+                } else {
+                    self.nft_vaults
+                        .insert(nft_address, Vault::with_bucket(nft_to_rent.into()));
+                    self.nft_vault_resources.push(nft_address);
+                }
+            });
+
+            self.rental_listed_event(rental_listing);
+        }
+
+        /// Cancels a rental listing and returns the NFT to the linked account. This is only possible once any active rental has expired.
+        pub fn cancel_rental_listing(&mut self, nfgid: NonFungibleGlobalId) {
+            let rental_listing = self
+                .rental_listings
+                .get(&nfgid)
+                .expect("[cancel_rental] Rental listing not found")
+                .clone();
+
+            Self::assert_not_rented(&rental_listing);
+
+            self.rental_listings.remove(&nfgid);
+
+            let (nft_address, nft_local) = nfgid.clone().into_parts();
+
+            let nft_bucket: Bucket = self
+                .nft_vaults
+                .get_mut(&nft_address)
+                .expect("[cancel_rental] NFT not found")
+                .as_non_fungible()
+                .take_non_fungible(&nft_local)
+                .into();
+
+            if Self::is_royalty_nft(nft_address) {
+                self.royal_admin.as_fungible().authorize_with_amount(1, || {
+                    self.my_account.try_deposit_or_abort(nft_bucket, None);
+                });
+            } else {
+                self.my_account.try_deposit_or_abort(nft_bucket, None);
+            }
+
+            self.rental_canceled_event(rental_listing);
+        }
+
+        /// Rents an NFT for a single rental period. The fee is split between the marketplace, the creator (for Royalty NFTs,
+        /// via the royalty component's pay_rental_royalty method) and the seller, whose share is sent to the account locker.
+        /// The renter receives a rental pass that expires at the end of the rental period.
+        pub fn rent(
+            &mut self,
+            nfgid: NonFungibleGlobalId,
+            payment: FungibleBucket,
+            permission: Proof,
+            account_recipient: Global<Account>,
+        ) -> (Bucket, Option<Bucket>) {
+            self.assert_buyer_not_blocked(&permission, account_recipient.address());
+
+            self.assert_trading_open(nfgid.resource_address());
+
+            let renter_permission = permission.resource_address();
+
+            let mut rental_listing = self
+                .rental_listings
+                .get(&nfgid)
+                .expect("[rent] Rental listing not found")
+                .clone();
+
+            assert!(
+                self.is_marketplace_permitted(
+                    &rental_listing.secondary_seller_permissions,
+                    rental_listing.registered_marketplaces,
+                    renter_permission
+                ),
+                "[rent] Marketplace does not have permission to rent this NFT"
+            );
+
+            Self::assert_not_rented(&rental_listing);
+
+            assert!(
+                payment.amount() == rental_listing.fee,
+                "[rent] Payment amount does not match rental fee"
+            );
+
+            assert!(
+                payment.resource_address() == rental_listing.currency,
+                "[rent] Payment currency does not match rental currency"
+            );
+
+            let transaction_hash = Runtime::transaction_hash();
+
+            assert!(
+                self.transactions.get(&transaction_hash).is_none(),
+                "[rent] Renting an NFT within the same transaction it is listed is blocked."
+            );
+
+            let marketplace_fee_option: Option<Decimal> =
+                self.marketplace_fee_rate(permission.resource_address());
+
+            let marketplace_fee = match marketplace_fee_option {
+                Some(rate) => payment.amount().checked_mul(rate).unwrap(),
+                None => dec!(0),
+            };
+
+            let nft_address = nfgid.resource_address();
+
+            let mut remainder: Bucket = payment.into();
+
+            if Self::is_royalty_nft(nft_address) {
+                let royalty_component_global_address: GlobalAddress =
+                    ResourceManager::from_address(nft_address)
+                        .get_metadata("royalty_component")
+                        .unwrap()
+                        .unwrap();
+
+                let call_address: Global<AnyComponent> = Global(ObjectStub::new(
+                    ObjectStubHandle::Global(royalty_component_global_address),
+                ));
+
+                let fee = remainder.amount();
+
+                remainder = call_address.call_raw(
+                    "pay_rental_royalty",
+                    scrypto_args!(
+                        nft_address,
+                        indexmap::IndexMap::from([(nfgid.local_id().clone(), fee)]),
+                        remainder,
+                        renter_permission
+                    ),
+                );
+            }
+
+            let mut marketplace_fee_bucket: Option<Bucket> = None;
+
+            if marketplace_fee > dec!(0) {
+                marketplace_fee_bucket = Some(remainder.take_advanced(
+                    marketplace_fee,
+                    WithdrawStrategy::Rounded(RoundingMode::ToZero),
+                ));
+            }
+
+            let protocol_fee = Self::take_protocol_fee(
+                self.open_hub,
+                &mut remainder,
+                renter_permission,
+                rental_listing.fee,
+            );
+
+            self.store_revenue(remainder);
+
+            let expires_at = Clock::current_time_rounded_to_seconds()
+                .add_seconds(rental_listing.duration_seconds)
+                .expect("[rent] Invalid rental duration");
+
+            rental_listing.rented_until = Some(expires_at);
+
+            self.rental_listings
+                .insert(nfgid.clone(), rental_listing.clone());
+
+            self.rental_pass_count += 1;
+
+            let rental_pass: Bucket = self
+                .rental_pass_manager
+                .mint_non_fungible(
+                    &NonFungibleLocalId::integer(self.rental_pass_count),
+                    RentalPass {
+                        nfgid: nfgid.clone(),
+                        outpost_account: self.trader_account_component_address,
+                        expires_at,
+                    },
+                )
+                .into();
+
+            self.nft_rented_event(
+                rental_listing,
+                account_recipient.address(),
+                expires_at,
+                protocol_fee,
+            );
+
+            (rental_pass, marketplace_fee_bucket)
+        }
+
+        pub fn rental_pass_address(&self) -> ResourceAddress {
+            self.rental_pass_manager.address()
+        }
+
+        //💰💰💰  Lending Methods 💰💰💰 //
+
+        /// Offers a loan against NFTs escrowed in this Outpost. The principal is escrowed here until the borrower accepts
+        /// and the lender receives a loan note that is used to claim whatever is owed to them when the loan ends.
+        pub fn offer_loan(
+            &mut self,
+            principal: FungibleBucket,
+            repayment_amount: Decimal,
+            duration_seconds: i64,
+            collateral: Vec<(NonFungibleGlobalId, Decimal)>,
+            lender_account: Global<Account>,
+        ) -> Bucket {
+            assert!(
+                !principal.is_empty(),
+                "[offer_loan] No principal provided"
+            );
+
+            assert!(
+                repayment_amount >= principal.amount(),
+                "[offer_loan] Repayment amount must be at least the principal"
+            );
+
+            assert!(
+                duration_seconds > 0,
+                "[offer_loan] Loan duration must be greater than zero"
+            );
+
+            assert!(
+                !collateral.is_empty(),
+                "[offer_loan] No collateral requested"
+            );
+
+            assert!(
+                collateral.iter().all(|(_, value)| *value > Decimal::zero()),
+                "[offer_loan] Every NFT must be valued at more than zero"
+            );
+
+            let collateral_value = collateral
+                .iter()
+                .fold(dec!(0), |total, (_, value)| total.checked_add(*value).unwrap());
+
+            assert!(
+                collateral_value == principal.amount(),
+                "[offer_loan] Collateral values must add up to the principal"
+            );
+
+            self.loan_count += 1;
+
+            let loan_id = self.loan_count;
+
+            let loan = Loan {
+                principal_currency: principal.resource_address(),
+                principal_amount: principal.amount(),
+                repayment_amount,
+                duration_seconds,
+                collateral,
+                lender_account,
+                deadline: None,
+                status: LoanStatus::Offered,
+            };
+
+            self.loans.insert(loan_id, loan.clone());
+
+            self.loan_vaults
+                .insert(loan_id, Vault::with_bucket(principal.into()));
+
+            self.loan_offered_event(loan_id, loan);
+
+            self.loan_note_manager
+                .mint_non_fungible(
+                    &NonFungibleLocalId::integer(loan_id),
+                    LoanNote {
+                        loan_id,
+                        outpost_account: self.trader_account_component_address,
+                    },
+                )
+                .into()
+        }
+
+        /// Accepts a loan offer. Every NFT requested as collateral must be escrowed in this Outpost - any sale or rental listing
+        /// for them is cancelled and they stay locked until the loan is repaid or claimed by the lender. The principal is returned to the borrower.
+        pub fn accept_loan(&mut self, loan_id: u64) -> Bucket {
+            assert!(!self.closed, "[accept_loan] This Outpost has been closed");
+
+            let mut loan = self
+                .loans
+                .get(&loan_id)
+                .expect("[accept_loan] Loan not found")
+                .clone();
+
+            assert!(
+                loan.status == LoanStatus::Offered,
+                "[accept_loan] Loan is not on offer"
+            );
+
+            for (nfgid, _) in loan.collateral.iter() {
+                let (nft_address, nft_local) = nfgid.clone().into_parts();
+
+                {
+                    let vault = self
+                        .nft_vaults
+                        .get(&nft_address)
+                        .expect("[accept_loan] Collateral is not escrowed in this Outpost");
+
+                    assert!(
+                        vault.as_non_fungible().contains_non_fungible(&nft_local),
+                        "[accept_loan] Collateral is not escrowed in this Outpost"
+                    );
+                }
+
+                assert!(
+                    self.locked_collateral.get(nfgid).is_none(),
+                    "[accept_loan] Collateral is already locked by another loan"
+                );
+
+                let listing = self.listings.get(nfgid).map(|listing| listing.clone());
+
+                if let Some(listing) = listing {
+                    self.cancel_listing_event(listing, nfgid.clone());
+                    self.listings.remove(nfgid);
+                }
+
+                let rental = self
+                    .rental_listings
+                    .get(nfgid)
+                    .map(|rental| rental.clone());
+
+                if let Some(rental) = rental {
+                    Self::assert_not_rented(&rental);
+                    self.rental_listings.remove(nfgid);
+                    self.rental_canceled_event(rental);
+                }
+
+                self.locked_collateral.insert(nfgid.clone(), loan_id);
+            }
+
+            let deadline = Clock::current_time_rounded_to_seconds()
+                .add_seconds(loan.duration_seconds)
+                .expect("[accept_loan] Invalid loan duration");
+
+            loan.deadline = Some(deadline);
+            loan.status = LoanStatus::Active;
+
+            self.active_loans += 1;
+
+            self.loans.insert(loan_id, loan.clone());
+
+            self.loan_accepted_event(loan_id, loan);
+
+            self.loan_vaults
+                .get_mut(&loan_id)
+                .expect("[accept_loan] Loan vault not found")
+                .take_all()
+        }
+
+        /// Repays an active loan before its deadline. The collateral is returned to the linked account - Royalty NFTs via the royal
+        /// deposit path - as its listings were cancelled when the loan was accepted. The repayment is held until the lender claims it
+        /// with their loan note.
+        pub fn repay_loan(&mut self, loan_id: u64, repayment: FungibleBucket) {
+            let mut loan = self
+                .loans
+                .get(&loan_id)
+                .expect("[repay_loan] Loan not found")
+                .clone();
+
+            assert!(
+                loan.status == LoanStatus::Active,
+                "[repay_loan] Loan is not active"
+            );
+
+            assert!(
+                Clock::current_time_rounded_to_seconds() < loan.deadline.unwrap(),
+                "[repay_loan] Loan deadline has passed"
+            );
+
+            assert!(
+                repayment.resource_address() == loan.principal_currency,
+                "[repay_loan] Repayment currency does not match the principal currency"
+            );
+
+            assert!(
+                repayment.amount() == loan.repayment_amount,
+                "[repay_loan] Repayment amount does not match the loan terms"
+            );
+
+            self.loan_vaults
+                .get_mut(&loan_id)
+                .expect("[repay_loan] Loan vault not found")
+                .put(repayment.into());
+
+            for (nfgid, _) in loan.collateral.iter() {
+                let (nft_address, nft_local) = nfgid.clone().into_parts();
+
+                self.locked_collateral.remove(nfgid);
+
+                let nft_bucket: Bucket = self
+                    .nft_vaults
+                    .get_mut(&nft_address)
+                    .expect("[repay_loan] NFT not found")
+                    .as_non_fungible()
+                    .take_non_fungible(&nft_local)
+                    .into();
+
+                self.return_to_account(nft_bucket);
+            }
+
+            loan.status = LoanStatus::Repaid;
+
+            self.active_loans -= 1;
+
+            self.loans.insert(loan_id, loan.clone());
+
+            self.loan_repaid_event(loan_id, loan);
+        }
+
+        /// Claims whatever is owed to the holder of a loan note, burning the note. If the offer was never accepted the principal is returned,
+        /// if the loan was repaid the repayment is returned, and if the deadline has passed without repayment the collateral is seized.
+        ///
+        /// Seizing a Royalty NFT is treated as a sale at the value agreed for it in the loan terms - a royalty payment (in the principal currency)
+        /// covering the values of the seized Royalty NFTs must be provided, and each NFT's royalty component takes its royalty on that NFT's value.
+        /// The remainder is returned. Seized Royalty NFTs are deposited directly to the lender account, all other assets are returned.
+        pub fn claim_loan(
+            &mut self,
+            loan_note: Bucket,
+            mut royalty_payment: Option<FungibleBucket>,
+        ) -> Vec<Bucket> {
+            assert!(
+                loan_note.resource_address() == self.loan_note_manager.address(),
+                "[claim_loan] Invalid loan note"
+            );
+
+            let loan_id = loan_note
+                .as_non_fungible()
+                .non_fungible::<LoanNote>()
+                .data()
+                .loan_id;
+
+            let mut loan = self
+                .loans
+                .get(&loan_id)
+                .expect("[claim_loan] Loan not found")
+                .clone();
+
+            let mut returned: Vec<Bucket> = vec![];
+
+            match loan.status {
+                LoanStatus::Offered | LoanStatus::Repaid => {
+                    returned.push(
+                        self.loan_vaults
+                            .get_mut(&loan_id)
+                            .expect("[claim_loan] Loan vault not found")
+                            .take_all(),
+                    );
+                }
+                LoanStatus::Active => {
+                    assert!(
+                        Clock::current_time_rounded_to_seconds() >= loan.deadline.unwrap(),
+                        "[claim_loan] Loan has not defaulted yet"
+                    );
+
+                    self.active_loans -= 1;
+
+                    let royal_collateral_value = loan
+                        .collateral
+                        .iter()
+                        .filter(|(nfgid, _)| Self::is_royalty_nft(nfgid.resource_address()))
+                        .fold(dec!(0), |total, (_, value)| total.checked_add(*value).unwrap());
+
+                    if royal_collateral_value > dec!(0) {
+                        let payment = royalty_payment
+                            .as_ref()
+                            .expect("[claim_loan] A royalty payment is required to claim Royalty NFTs");
+
+                        assert!(
+                            payment.resource_address() == loan.principal_currency,
+                            "[claim_loan] Royalty payment must be in the principal currency"
+                        );
+
+                        assert!(
+                            payment.amount() >= royal_collateral_value,
+                            "[claim_loan] Royalty payment must cover the value of the seized Royalty NFTs"
+                        );
+                    }
+
+                    for (nfgid, value) in loan.collateral.iter() {
+                        let (nft_address, nft_local) = nfgid.clone().into_parts();
+
+                        self.locked_collateral.remove(nfgid);
+
+                        let nft_bucket: Bucket = self
+                            .nft_vaults
+                            .get_mut(&nft_address)
+                            .expect("[claim_loan] NFT not found")
+                            .as_non_fungible()
+                            .take_non_fungible(&nft_local)
+                            .into();
+
+                        if Self::is_royalty_nft(nft_address) {
+                            let payment = royalty_payment.as_mut().unwrap().take_advanced(
+                                *value,
+                                WithdrawStrategy::Rounded(RoundingMode::ToZero),
+                            );
+
+                            let royalty_component_global_address: GlobalAddress =
+                                ResourceManager::from_address(nft_address)
+                                    .get_metadata("royalty_component")
+                                    .unwrap()
+                                    .unwrap();
+
+                            let call_address: Global<AnyComponent> = Global(ObjectStub::new(
+                                ObjectStubHandle::Global(royalty_component_global_address),
+                            ));
+
+                            let remainder: Bucket = call_address.call_raw(
+                                "pay_collateral_royalty",
+                                scrypto_args!(
+                                    nft_address,
+                                    indexmap::IndexMap::from([(nft_local.clone(), *value)]),
+                                    Bucket::from(payment)
+                                ),
+                            );
+
+                            returned.push(remainder);
+
+                            self.royal_admin.as_fungible().authorize_with_amount(1, || {
+                                loan.lender_account.try_deposit_or_abort(nft_bucket, None);
+                            });
+                        } else {
+                            returned.push(nft_bucket);
+                        }
+                    }
+                }
+                LoanStatus::Claimed => {
+                    panic!("[claim_loan] Loan has already been claimed");
+                }
+            }
+
+            if let Some(payment) = royalty_payment {
+                returned.push(payment.into());
+            }
+
+            loan.status = LoanStatus::Claimed;
+
+            self.loans.insert(loan_id, loan.clone());
+
+            self.loan_note_manager.burn(loan_note);
+
+            self.loan_claimed_event(loan_id, loan);
+
+            returned
+        }
+
+        pub fn get_loan(&self, loan_id: u64) -> Option<Loan> {
+            self.loans.get(&loan_id).map(|loan| loan.clone())
+        }
+
+        pub fn loan_note_address(&self) -> ResourceAddress {
+            self.loan_note_manager.address()
+        }
+
+        /// Adds a marketplace, badge or recipient account to this Outpost's blocklist. Blocked buyers can't purchase
+        /// any listing on this Outpost, regardless of the permissions set on the individual listings.
+        pub fn block_buyer(&mut self, buyer: BlockedBuyer) {
+            assert!(
+                self.blocklist.get(&buyer).is_none(),
+                "[block_buyer] Buyer is already blocked"
+            );
+
+            self.blocklist.insert(buyer.clone(), ());
+            self.blocked_buyers.push(buyer.clone());
+
+            let emitter_proof = self
+                .emitter_badge
+                .as_non_fungible()
+                .create_proof_of_non_fungibles(&indexset![self.emitter_badge_local.clone()]);
+
+            self.event_manager.buyer_blocked_event(
+                self.trader_account_component_address,
+                buyer,
+                emitter_proof.into(),
+            );
+        }
+
+        /// Removes an entry from this Outpost's blocklist.
+        pub fn unblock_buyer(&mut self, buyer: BlockedBuyer) {
+            assert!(
+                self.blocklist.get(&buyer).is_some(),
+                "[unblock_buyer] Buyer is not blocked"
+            );
+
+            self.blocklist.remove(&buyer);
+            self.blocked_buyers.retain(|blocked| *blocked != buyer);
+
+            let emitter_proof = self
+                .emitter_badge
+                .as_non_fungible()
+                .create_proof_of_non_fungibles(&indexset![self.emitter_badge_local.clone()]);
+
+            self.event_manager.buyer_unblocked_event(
+                self.trader_account_component_address,
+                buyer,
+                emitter_proof.into(),
+            );
+        }
+
+        pub fn is_buyer_blocked(&self, buyer: BlockedBuyer) -> bool {
+            self.blocklist.get(&buyer).is_some()
+        }
+
+        /// Links the Outpost to a different account. Sales revenue, returned NFTs and loan repayments go to the new account from
+        /// now on. Only the hub can call this, once the owners of both accounts have signed.
+        pub fn relink_account(&mut self, new_account: Global<Account>) {
+            assert!(!self.closed, "[relink] This Outpost has been closed");
+
+            self.my_account = new_account;
+        }
+
+        /// The account this Outpost is linked to
+        pub fn linked_account(&self) -> ComponentAddress {
+            self.my_account.address()
+        }
+
+        // utility methods
+
+        pub fn fetch_auth_key(&self) -> (ResourceAddress, NonFungibleLocalId) {
+            (self.auth_key_resource, self.auth_key_local.clone())
+        }
+
+        fn global_ids(nfts: &Bucket) -> Vec<NonFungibleGlobalId> {
+            let nft_address = nfts.resource_address();
+
+            nfts.as_non_fungible()
+                .non_fungible_local_ids()
+                .into_iter()
+                .map(|local_id| NonFungibleGlobalId::new(nft_address, local_id))
+                .collect()
+        }
+
+        /// Deposits NFTs taken out of escrow back into the linked account, using the royal admin badge for Royalty NFTs.
+        fn return_to_account(&mut self, nfts: Bucket) {
+            if Self::is_royalty_nft(nfts.resource_address()) {
+                self.royal_admin.as_fungible().authorize_with_amount(1, || {
+                    self.my_account.try_deposit_or_abort(nfts, None);
+                });
+            } else {
+                self.my_account.try_deposit_or_abort(nfts, None);
+            }
+        }
+
+        /// Royalty NFTs carry the address of their royalty component in their resource metadata.
+        fn is_royalty_nft(nft_address: ResourceAddress) -> bool {
+            let royalty_component: Option<GlobalAddress> = ResourceManager::from_address(nft_address)
+                .get_metadata("royalty_component")
+                .unwrap_or(None);
+
+            royalty_component.is_some()
+        }
+
+        /// Checks the purchasing permission and the recipient account against the blocklist. If the permission is a non-fungible
+        /// badge, each of the badge ids in the proof is checked as well. Royalty NFTs are deposited straight to the recipient, while
+        /// for other NFTs the recipient is the account the buyer declares the purchase is for.
+        fn assert_buyer_not_blocked(&self, permission: &Proof, recipient: ComponentAddress) {
+            let permission_resource = permission.resource_address();
+
+            assert!(
+                self.blocklist
+                    .get(&BlockedBuyer::Permission(permission_resource))
+                    .is_none(),
+                "[purchase] This marketplace or buyer has been blocked by the seller"
+            );
+
+            if !permission_resource.is_fungible() {
+                let badge_ids = permission
+                    .clone()
+                    .skip_checking()
+                    .as_non_fungible()
+                    .non_fungible_local_ids();
+
+                for local_id in badge_ids {
+                    assert!(
+                        self.blocklist
+                            .get(&BlockedBuyer::Badge(NonFungibleGlobalId::new(
+                                permission_resource,
+                                local_id
+                            )))
+                            .is_none(),
+                        "[purchase] This badge has been blocked by the seller"
+                    );
+                }
+            }
+
+            assert!(
+                self.blocklist
+                    .get(&BlockedBuyer::Recipient(recipient))
+                    .is_none(),
+                "[purchase] This recipient account has been blocked by the seller"
+            );
+        }
+
+        /// Rentals expire lazily, so a rental listing is only considered rented while its expiry time is in the future.
+        fn assert_not_rented(rental_listing: &RentalListing) {
+            if let Some(rented_until) = rental_listing.rented_until {
+                assert!(
+                    Clock::current_time_rounded_to_seconds() >= rented_until,
+                    "[rental] This NFT is rented out until {}",
+                    rented_until.seconds_since_unix_epoch
+                );
+            }
+        }
+
+        /// Takes every escrowed NFT out of its vault and removes its sale and rental listings, ready for the NFTs to be returned to
+        /// the linked account or moved to another Outpost. Both kinds of listing are returned so they can be recreated elsewhere.
+        fn drain_escrow(&mut self) -> (Vec<Bucket>, Vec<Listing>, Vec<RentalListing>) {
+            let mut nfts: Vec<Bucket> = vec![];
+            let mut listings: Vec<Listing> = vec![];
+            let mut rentals: Vec<RentalListing> = vec![];
+
+            for nft_address in self.nft_vault_resources.clone() {
+                let nft_bucket = self
+                    .nft_vaults
+                    .get_mut(&nft_address)
+                    .expect("[close] NFT vault not found")
+                    .as_non_fungible()
+                    .take_all();
+
+                if nft_bucket.is_empty() {
+                    continue;
+                }
+
+                for local_id in nft_bucket.non_fungible_local_ids() {
+                    let nfgid = NonFungibleGlobalId::new(nft_address, local_id);
+
+                    assert!(
+                        self.locked_collateral.get(&nfgid).is_none(),
+                        "[close] An NFT is locked as collateral for an active loan"
+                    );
+
+                    let listing = self.listings.get(&nfgid).map(|listing| listing.clone());
+
+                    if let Some(listing) = listing {
+                        self.cancel_listing_event(listing.clone(), nfgid.clone());
+                        self.listings.remove(&nfgid);
+                        listings.push(listing);
+                    }
+
+                    // An NFT that is currently rented out can't be withdrawn, as the rental pass is only valid on this Outpost.
+                    let rental = self
+                        .rental_listings
+                        .get(&nfgid)
+                        .map(|rental| rental.clone());
+
+                    if let Some(rental) = rental {
+                        Self::assert_not_rented(&rental);
+                        self.rental_listings.remove(&nfgid);
+                        rentals.push(rental);
+                    }
+                }
+
+                nfts.push(nft_bucket.into());
+            }
+
+            (nfts, listings, rentals)
+        }
+
+        /// Escrows NFTs and recreates sale and rental listings moved over from another Outpost.
+        fn import_escrow(
+            &mut self,
+            nfts: Vec<Bucket>,
+            listings: Vec<Listing>,
+            rentals: Vec<RentalListing>,
+        ) {
+            // As with a fresh listing, migrated listings can't be purchased within the same transaction.
+            let transaction_hash = Runtime::transaction_hash();
+
+            self.transactions.insert(transaction_hash, ());
+
+            for nft_bucket in nfts {
+                let nft_address = nft_bucket.resource_address();
+
+                self.royal_admin.as_fungible().authorize_with_amount(1, || {
+                    let vault_exists = self.nft_vaults.get(&nft_address).is_some();
+
+                    if vault_exists {
+                        let mut vault = self
+                            .nft_vaults
+                            .get_mut(&nft_address)
+                            .expect("[migrate] NFT not found");
+                        vault.put(nft_bucket);
+                    } else {
+                        self.nft_vaults
+                            .insert(nft_address, Vault::with_bucket(nft_bucket));
+                        self.nft_vault_resources.push(nft_address);
+                    }
+                });
+            }
+
+            for listing in listings {
+                let nfgid = listing.nfgid.clone();
+
+                let new_listing = Listing {
+                    outpost_account: self.trader_account_component_address,
+                    ..listing
+                };
+
+                self.listings.insert(nfgid.clone(), new_listing.clone());
+
+                self.listing_event(new_listing, nfgid);
+            }
+
+            for rental in rentals {
+                let nfgid = rental.nfgid.clone();
+
+                let rental_listing = RentalListing {
+                    outpost_account: self.trader_account_component_address,
+                    rented_until: None,
+                    ..rental
+                };
+
+                self.rental_listings
+                    .insert(nfgid.clone(), rental_listing.clone());
+
+                self.rental_listed_event(rental_listing);
+            }
+        }
+
+        /// Stores sale or rental proceeds in the account locker for the linked account - which deposits them straight into the
+        /// account if it accepts them - and emits a revenue claimed event through the event component.
+        fn store_revenue(&mut self, revenue: Bucket) {
+            let currency = revenue.resource_address();
+            let amount = revenue.amount();
+
+            let emitter_proof = self
+                .emitter_badge
+                .as_non_fungible()
+                .create_proof_of_non_fungibles(&indexset![self.emitter_badge_local.clone()]);
+
+            emitter_proof.clone().authorize(|| {
+                self.account_locker.store(self.my_account, revenue, true);
+            });
+
+            if amount > dec!(0) {
+                self.event_manager.revenue_claimed_event(
+                    self.trader_account_component_address,
+                    self.my_account.address(),
+                    currency,
+                    amount,
+                    emitter_proof.into(),
+                );
+            } else {
+                emitter_proof.drop();
+            }
+        }
+
+        /// Takes the OpenHub protocol fee from what is left of a payment once royalties and the marketplace fee have been taken,
+        /// sends it to the hub treasury and returns the amount taken. The fee base is the full sale price, before any royalties or
+        /// marketplace fees. If those leave less than the fee, the fee is capped at what is left and the seller receives nothing.
+        /// The fee is charged on every purchase through a marketplace badge - private trades are exempt.
+        fn take_protocol_fee(
+            open_hub: Global<OpenHub>,
+            payment: &mut Bucket,
+            marketplace: ResourceAddress,
+            sale_price: Decimal,
+        ) -> Decimal {
+            if !is_marketplace_badge(marketplace) {
+                return dec!(0);
+            }
+
+            let protocol_fee_rate = open_hub.get_protocol_fee();
+
+            if protocol_fee_rate == dec!(0) {
+                return dec!(0);
+            }
+
+            let protocol_fee = payment.take_advanced(
+                sale_price
+                    .checked_mul(protocol_fee_rate)
+                    .unwrap()
+                    .min(payment.amount()),
+                WithdrawStrategy::Rounded(RoundingMode::ToZero),
+            );
+
+            let amount = protocol_fee.amount();
+
+            open_hub.deposit_protocol_fee(protocol_fee);
+
+            amount
+        }
+
+        /// The price of each NFT in a bulk purchase, passed to the royalty component so each NFT pays royalties on its own price.
+        fn listing_prices(listings: &[Listing]) -> indexmap::IndexMap<NonFungibleLocalId, Decimal> {
+            listings
+                .iter()
+                .map(|listing| (listing.nfgid.local_id().clone(), listing.price))
+                .collect()
+        }
+
+        fn is_marketplace_permitted(
+            &self,
+            permissions: &[ResourceAddress],
+            registered_marketplaces: bool,
+            marketplace: ResourceAddress,
+        ) -> bool {
+            permissions.contains(&marketplace)
+                || (registered_marketplaces && self.open_hub.get_marketplace(marketplace).is_some())
+        }
+
+        /// The marketplace fee rate a purchase is charged, taken from the metadata of the marketplace's permission badge.
+        /// Marketplaces registered in the OpenHub have committed to a fee cap, so a rate above the cap is rejected.
+        fn marketplace_fee_rate(&self, marketplace: ResourceAddress) -> Option<Decimal> {
+            let fee_rate: Option<Decimal> = ResourceManager::from_address(marketplace)
+                .get_metadata("marketplace_fee")
+                .unwrap();
+
+            if let (Some(fee_rate), Some(registration)) =
+                (fee_rate, self.open_hub.get_marketplace(marketplace))
+            {
+                assert!(
+                    fee_rate <= registration.fee_cap,
+                    "[purchase] Marketplace fee is above the cap it registered with"
+                );
+            }
+
+            fee_rate
+        }
+
+        /// Listing and purchasing are blocked while the hub has paused the protocol or the collection. Cancellations and
+        /// withdrawals never check this, so NFTs can always be taken back out of a paused Outpost.
+        fn assert_trading_open(&self, collection: ResourceAddress) {
+            assert!(
+                !self.open_hub.is_trading_paused(collection),
+                "[paused] Trading of this collection has been paused by the Outpost admin"
+            );
+        }
+
+        /// Whether a component is an Outpost registered with this Outpost's hub to the same linked account.
+        fn is_sibling_outpost(&self, outpost: ComponentAddress) -> bool {
+            self.open_hub
+                .get_outpost_owner(outpost)
+                .map_or(false, |(account, _)| account == self.my_account.address())
+        }
+
+        /// Collections the hub has blocked can't be listed. Listing a collection flagged with a warning still works, but emits
+        /// an event so marketplaces can label or hide the listing.
+        fn check_collection_flag(&self, collection: ResourceAddress) {
+            if let Some(flag) = self.open_hub.get_collection_flag(collection) {
+                assert!(
+                    flag.severity != FlagSeverity::Block,
+                    "[list_nft] This collection has been flagged as {:?} by the Outpost admin",
+                    flag.reason
+                );
+
+                self.flagged_collection_listed_event(collection, flag);
+            }
+        }
+
+        /// Scheduled listings are visible as soon as they are created, but can only be purchased once their start time has passed.
+        fn assert_listing_started(listing: &Listing) {
+            if let Some(start_time) = listing.start_time {
+                assert!(
+                    Clock::current_time_rounded_to_seconds() >= start_time,
+                    "[purchase] Listing is not open for purchase until {}",
+                    start_time.seconds_since_unix_epoch
+                );
+            }
+        }
+
+        // event emittors - every Outpost event is emitted by the central event component,
+        // which versions their schema so indexers can follow every Outpost from a single place.
+        fn listing_event(&self, listing: Listing, nft_id: NonFungibleGlobalId) {
+            let emitter_proof = self
+                .emitter_badge
+                .as_non_fungible()
+                .create_proof_of_non_fungibles(&indexset![self.emitter_badge_local.clone()]);
+
+            self.event_manager
+                .listing_event(listing, nft_id, emitter_proof.into());
+        }
+
+        fn update_listing_event(&self, listing: Listing, nft_id: NonFungibleGlobalId) {
+            let emitter_proof = self
+                .emitter_badge
+                .as_non_fungible()
+                .create_proof_of_non_fungibles(&indexset![self.emitter_badge_local.clone()]);
+
+            self.event_manager
+                .update_listing_event(listing, nft_id, emitter_proof.into());
+        }
+
+        fn cancel_listing_event(&self, listing: Listing, nft_id: NonFungibleGlobalId) {
+            let emitter_proof = self
+                .emitter_badge
+                .as_non_fungible()
+                .create_proof_of_non_fungibles(&indexset![self.emitter_badge_local.clone()]);
+
+            self.event_manager
+                .cancel_listing_event(listing, nft_id, emitter_proof.into());
+        }
+
+        fn purchase_listing_event(
+            &self,
+            listing: Listing,
+            nft_id: NonFungibleGlobalId,
+            settlement: Settlement,
+        ) {
+            let emitter_proof = self
+                .emitter_badge
+                .as_non_fungible()
+                .create_proof_of_non_fungibles(&indexset![self.emitter_badge_local.clone()]);
+
+            self.event_manager.purchase_listing_event(
+                listing,
+                nft_id,
+                settlement,
+                emitter_proof.into(),
+            );
+        }
+
+        fn multi_listing_event(&self, listings: Vec<Listing>) {
+            let emitter_proof = self
+                .emitter_badge
+                .as_non_fungible()
+                .create_proof_of_non_fungibles(&indexset![self.emitter_badge_local.clone()]);
+
+            self.event_manager
+                .multi_listing_event(listings, emitter_proof.into());
+        }
+
+        fn multi_purchase_event(&self, listings: Vec<Listing>, settlement: Settlement) {
+            let emitter_proof = self
+                .emitter_badge
+                .as_non_fungible()
+                .create_proof_of_non_fungibles(&indexset![self.emitter_badge_local.clone()]);
+
+            self.event_manager
+                .multi_purchase_event(listings, settlement, emitter_proof.into());
+        }
+
+        fn rental_listed_event(&self, rental_listing: RentalListing) {
+            let emitter_proof = self
+                .emitter_badge
+                .as_non_fungible()
+                .create_proof_of_non_fungibles(&indexset![self.emitter_badge_local.clone()]);
+
+            let nft_id = rental_listing.nfgid.clone();
+
+            self.event_manager
+                .rental_listed_event(rental_listing, nft_id, emitter_proof.into());
+        }
+
+        fn rental_canceled_event(&self, rental_listing: RentalListing) {
+            let emitter_proof = self
+                .emitter_badge
+                .as_non_fungible()
+                .create_proof_of_non_fungibles(&indexset![self.emitter_badge_local.clone()]);
+
+            let nft_id = rental_listing.nfgid.clone();
+
+            self.event_manager
+                .rental_canceled_event(rental_listing, nft_id, emitter_proof.into());
+        }
+
+        fn nft_rented_event(
+            &self,
+            rental_listing: RentalListing,
+            renter: ComponentAddress,
+            expires_at: Instant,
+            protocol_fee: Decimal,
+        ) {
+            let emitter_proof = self
+                .emitter_badge
+                .as_non_fungible()
+                .create_proof_of_non_fungibles(&indexset![self.emitter_badge_local.clone()]);
+
+            let nft_id = rental_listing.nfgid.clone();
+
+            self.event_manager.nft_rented_event(
+                rental_listing,
+                nft_id,
+                renter,
+                expires_at,
+                protocol_fee,
+                emitter_proof.into(),
+            );
+        }
+
+        fn loan_offered_event(&self, loan_id: u64, loan: Loan) {
+            let emitter_proof = self
+                .emitter_badge
+                .as_non_fungible()
+                .create_proof_of_non_fungibles(&indexset![self.emitter_badge_local.clone()]);
+
+            self.event_manager.loan_offered_event(
+                self.trader_account_component_address,
+                loan_id,
+                loan,
+                emitter_proof.into(),
+            );
+        }
+
+        fn loan_accepted_event(&self, loan_id: u64, loan: Loan) {
+            let emitter_proof = self
+                .emitter_badge
+                .as_non_fungible()
+                .create_proof_of_non_fungibles(&indexset![self.emitter_badge_local.clone()]);
+
+            self.event_manager.loan_accepted_event(
+                self.trader_account_component_address,
+                loan_id,
+                loan,
+                emitter_proof.into(),
+            );
+        }
+
+        fn loan_repaid_event(&self, loan_id: u64, loan: Loan) {
+            let emitter_proof = self
+                .emitter_badge
+                .as_non_fungible()
+                .create_proof_of_non_fungibles(&indexset![self.emitter_badge_local.clone()]);
+
+            self.event_manager.loan_repaid_event(
+                self.trader_account_component_address,
+                loan_id,
+                loan,
+                emitter_proof.into(),
+            );
+        }
+
+        fn loan_claimed_event(&self, loan_id: u64, loan: Loan) {
+            // Offered and repaid loans can still be claimed after an upgrade, once the emitter badge has moved to the new Outpost.
+            if self.emitter_badge.is_empty() {
+                return;
+            }
+
+            let emitter_proof = self
+                .emitter_badge
+                .as_non_fungible()
+                .create_proof_of_non_fungibles(&indexset![self.emitter_badge_local.clone()]);
+
+            self.event_manager.loan_claimed_event(
+                self.trader_account_component_address,
+                loan_id,
+                loan,
+                emitter_proof.into(),
+            );
+        }
+
+        fn flagged_collection_listed_event(&self, collection: ResourceAddress, flag: CollectionFlag) {
+            let emitter_proof = self
+                .emitter_badge
+                .as_non_fungible()
+                .create_proof_of_non_fungibles(&indexset![self.emitter_badge_local.clone()]);
+
+            self.event_manager.flagged_collection_listed_event(
+                self.trader_account_component_address,
+                collection,
+                flag,
+                emitter_proof.into(),
+            );
+        }
+    }
 }
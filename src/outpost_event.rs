@@ -1,137 +1,1395 @@
 use scrypto::prelude::*;
 
-use crate::outpost_account::Listing;
+use crate::outpost_account::{BlockedBuyer, Listing, Loan, RentalListing};
+use crate::outpost_factory::CollectionFlag;
+use crate::price_oracle::price_oracle::PriceOracle;
 /// This component acts as the central hub for all trade emitted events, such as listing creation, listing updates, listing cancellations, and listing purchases.
+///
+/// Every event carries an explicit schema version alongside an enum-wrapped payload. The schema version of an event is always
+/// the number of its payload variant, so a `ListingCreated` with a `V3` payload has schema version 3. When the shape of an event
+/// (or of the listing it embeds) changes, a new payload variant is added rather than changing an existing one, and every variant
+/// embeds a frozen listing snapshot rather than the live `Listing`, so decoders built against older versions keep working.
+///
+/// Events emitted before schema versioning was introduced were bare structs and can't be decoded as the versioned events -
+/// decode them with `UnversionedListingEvent`. Those events were also emitted by each Outpost itself; every Outpost event - and
+/// the hub's relink and upgrade events - is now only emitted by this component, so one event stream captures all Outpost activity.
+pub trait VersionedPayload {
+    fn schema_version(&self) -> u8;
+}
+
+/// The shape of the listing, update, cancel and purchase events emitted before schema versioning was introduced.
+/// This must never change.
+#[derive(ScryptoSbor, Clone, Debug, PartialEq, Eq)]
+pub struct UnversionedListingEvent {
+    pub listing: ListingV1,
+    pub outpost_account: ComponentAddress,
+    pub nft_id: NonFungibleGlobalId,
+}
+
+/// The shape of a listing as emitted in version 1 events - before listings could be scheduled with a start time.
+/// This must never change.
+#[derive(ScryptoSbor, Clone, Debug, PartialEq, Eq)]
+pub struct ListingV1 {
+    pub secondary_seller_permissions: Vec<ResourceAddress>,
+    pub currency: ResourceAddress,
+    pub price: Decimal,
+    pub nfgid: NonFungibleGlobalId,
+    pub outpost_account: ComponentAddress,
+}
+
+/// The shape of a listing as emitted in version 2 events - before listings could opt into every registered marketplace.
+/// This must never change.
+#[derive(ScryptoSbor, Clone, Debug, PartialEq, Eq)]
+pub struct ListingV2 {
+    pub secondary_seller_permissions: Vec<ResourceAddress>,
+    pub currency: ResourceAddress,
+    pub price: Decimal,
+    pub nfgid: NonFungibleGlobalId,
+    pub outpost_account: ComponentAddress,
+    pub start_time: Option<Instant>,
+}
+
+/// The shape of a listing as emitted in version 3 and 4 events - once listings could opt into every registered marketplace.
+/// This must never change.
+#[derive(ScryptoSbor, Clone, Debug, PartialEq, Eq)]
+pub struct ListingV3 {
+    pub secondary_seller_permissions: Vec<ResourceAddress>,
+    pub currency: ResourceAddress,
+    pub price: Decimal,
+    pub nfgid: NonFungibleGlobalId,
+    pub outpost_account: ComponentAddress,
+    pub start_time: Option<Instant>,
+    pub registered_marketplaces: bool,
+}
+
+impl From<Listing> for ListingV3 {
+    fn from(listing: Listing) -> Self {
+        ListingV3 {
+            secondary_seller_permissions: listing.secondary_seller_permissions,
+            currency: listing.currency,
+            price: listing.price,
+            nfgid: listing.nfgid,
+            outpost_account: listing.outpost_account,
+            start_time: listing.start_time,
+            registered_marketplaces: listing.registered_marketplaces,
+        }
+    }
+}
+
+/// How the payment for a purchase was split. Outposts pass this alongside the purchased listing(s) so that
+/// purchase events carry everything an indexer needs to record the sale.
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct Settlement {
+    /// The marketplace or private buyer badge used to purchase
+    pub permission: ResourceAddress,
+    /// The account the NFTs were purchased for - Royalty NFTs are deposited to it directly
+    pub recipient: ComponentAddress,
+    pub marketplace_fee: Decimal,
+    pub royalty_amount: Decimal,
+    /// The OpenHub protocol fee - zero for private trades
+    pub protocol_fee: Decimal,
+    /// The amount sent to the seller after marketplace fees, royalties and the protocol fee
+    pub seller_proceeds: Decimal,
+}
+
+/// The number of lowest-priced public listings reported per collection and currency for the running floor.
+pub const FLOOR_DEPTH: usize = 10;
+
+/// The most public listings kept per collection and currency to take the floor from. Every list, update, cancel and purchase
+/// reads and rewrites the whole book, so it is capped to keep that cost and the book's substate size fixed however many
+/// listings a collection has.
+pub const FLOOR_BOOK_CAPACITY: usize = 50;
+
+/// Rolling market statistics for a collection in a single currency, kept up to date from the events of every Outpost.
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct CollectionStats {
+    pub total_volume: Decimal,
+    pub sale_count: u64,
+    pub last_sale_price: Option<Decimal>,
+    pub last_sale_time: Option<Instant>,
+    pub active_listings: u64,
+    /// The cheapest `FLOOR_DEPTH` public listings, sorted by price. Listings only open to private deals are left out. Once
+    /// more public listings exist than the floor book holds, only those known to be cheaper than every listing left out of
+    /// the book are reported, so this can hold fewer than `FLOOR_DEPTH` listings until the left out listings are gone.
+    pub lowest_listings: Vec<(Decimal, NonFungibleGlobalId)>,
+}
+
+/// The cheapest public listings of a collection in a currency, which the running floor is taken from.
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct FloorBook {
+    /// At most `FLOOR_BOOK_CAPACITY` listings, sorted by price
+    pub listings: Vec<(Decimal, NonFungibleGlobalId)>,
+    /// The number of public listings that didn't fit in the book
+    pub overflow: u64,
+    /// No listing left out of the book is priced below this. It only resets once every left out listing is gone.
+    pub overflow_floor: Option<Decimal>,
+}
+
+impl FloorBook {
+    /// The cheapest listings that are certain to be the floor - those priced no higher than any listing left out of the book.
+    fn known_floor(&self) -> Vec<(Decimal, NonFungibleGlobalId)> {
+        self.listings
+            .iter()
+            .filter(|(price, _)| self.overflow_floor.map_or(true, |floor| *price <= floor))
+            .take(FLOOR_DEPTH)
+            .cloned()
+            .collect()
+    }
+
+    fn leave_out(&mut self, price: Decimal) {
+        self.overflow += 1;
+        self.overflow_floor = Some(self.overflow_floor.map_or(price, |floor| floor.min(price)));
+    }
+}
+
+/// Shared by the listing created, updated and canceled events.
+#[derive(ScryptoSbor, Clone)]
+pub enum ListingEventPayload {
+    V1 {
+        listing: ListingV1,
+        outpost_account: ComponentAddress,
+        nft_id: NonFungibleGlobalId,
+    },
+    V2 {
+        listing: ListingV2,
+        outpost_account: ComponentAddress,
+        nft_id: NonFungibleGlobalId,
+    },
+    V3 {
+        listing: ListingV3,
+        outpost_account: ComponentAddress,
+        nft_id: NonFungibleGlobalId,
+    },
+}
+
+#[derive(ScryptoSbor, Clone)]
+pub enum ListingPurchasedPayload {
+    V1 {
+        listing: ListingV1,
+        outpost_account: ComponentAddress,
+        nft_id: NonFungibleGlobalId,
+    },
+    V2 {
+        listing: ListingV2,
+        outpost_account: ComponentAddress,
+        nft_id: NonFungibleGlobalId,
+        permission: ResourceAddress,
+        recipient: Option<ComponentAddress>,
+        marketplace_fee: Decimal,
+        royalty_amount: Decimal,
+        seller_proceeds: Decimal,
+        timestamp: Instant,
+    },
+    V3 {
+        listing: ListingV3,
+        outpost_account: ComponentAddress,
+        nft_id: NonFungibleGlobalId,
+        permission: ResourceAddress,
+        recipient: Option<ComponentAddress>,
+        marketplace_fee: Decimal,
+        royalty_amount: Decimal,
+        seller_proceeds: Decimal,
+        timestamp: Instant,
+    },
+    V4 {
+        listing: ListingV3,
+        outpost_account: ComponentAddress,
+        nft_id: NonFungibleGlobalId,
+        permission: ResourceAddress,
+        recipient: Option<ComponentAddress>,
+        marketplace_fee: Decimal,
+        royalty_amount: Decimal,
+        protocol_fee: Decimal,
+        seller_proceeds: Decimal,
+        timestamp: Instant,
+    },
+}
+
+#[derive(ScryptoSbor, Clone)]
+pub enum OutpostCreatedPayload {
+    V1 {
+        outpost_component: ComponentAddress,
+        outpost_account: ComponentAddress,
+    },
+}
+
+#[derive(ScryptoSbor, Clone)]
+pub enum SameOwnerTransferPayload {
+    V1 {
+        outpost_component: ComponentAddress,
+        nft_ids: Vec<NonFungibleGlobalId>,
+        recipient: ComponentAddress,
+    },
+}
+
+#[derive(ScryptoSbor, Clone)]
+pub enum DappTransferPayload {
+    V1 {
+        outpost_component: ComponentAddress,
+        nft_ids: Vec<NonFungibleGlobalId>,
+        dapp: ComponentAddress,
+        method: String,
+    },
+}
+
+#[derive(ScryptoSbor, Clone)]
+pub enum OutpostKeyRecoveredPayload {
+    V1 {
+        outpost_component: ComponentAddress,
+        outpost_account: ComponentAddress,
+        new_key: NonFungibleGlobalId,
+    },
+}
+
+/// Emitted whenever sale or rental proceeds are paid out to an Outpost's linked account through the account locker.
+#[derive(ScryptoSbor, Clone)]
+pub enum RevenueClaimedPayload {
+    V1 {
+        outpost_component: ComponentAddress,
+        recipient: ComponentAddress,
+        currency: ResourceAddress,
+        amount: Decimal,
+    },
+}
+
+/// Shared by the buyer blocked and unblocked events.
+#[derive(ScryptoSbor, Clone)]
+pub enum BlocklistEventPayload {
+    V1 {
+        outpost_component: ComponentAddress,
+        buyer: BlockedBuyer,
+    },
+}
+
+/// Shared by the rental listed and canceled events.
+#[derive(ScryptoSbor, Clone)]
+pub enum RentalEventPayload {
+    V1 {
+        rental_listing: RentalListing,
+        outpost_account: ComponentAddress,
+        nft_id: NonFungibleGlobalId,
+    },
+}
+
+#[derive(ScryptoSbor, Clone)]
+pub enum NftRentedPayload {
+    V1 {
+        rental_listing: RentalListing,
+        outpost_account: ComponentAddress,
+        nft_id: NonFungibleGlobalId,
+        renter: ComponentAddress,
+        expires_at: Instant,
+        protocol_fee: Decimal,
+    },
+}
+
+/// Shared by the loan offered, accepted, repaid and claimed events.
+#[derive(ScryptoSbor, Clone)]
+pub enum LoanEventPayload {
+    V1 {
+        outpost_account: ComponentAddress,
+        loan_id: u64,
+        loan: Loan,
+    },
+}
+
+/// Emitted when a collection the hub has flagged with a warning is listed, so marketplaces can label or hide the listing.
+#[derive(ScryptoSbor, Clone)]
+pub enum FlaggedCollectionListedPayload {
+    V1 {
+        outpost_account: ComponentAddress,
+        collection: ResourceAddress,
+        flag: CollectionFlag,
+    },
+}
+
+#[derive(ScryptoSbor, Clone)]
+pub enum OutpostRelinkedPayload {
+    V1 {
+        outpost_component: ComponentAddress,
+        old_account: ComponentAddress,
+        new_account: ComponentAddress,
+    },
+}
+
+#[derive(ScryptoSbor, Clone)]
+pub enum OutpostUpgradedPayload {
+    V1 {
+        outpost_account: ComponentAddress,
+        old_outpost: ComponentAddress,
+        new_outpost: ComponentAddress,
+        version: u32,
+    },
+}
+
+impl VersionedPayload for ListingEventPayload {
+    fn schema_version(&self) -> u8 {
+        match self {
+            ListingEventPayload::V1 { .. } => 1,
+            ListingEventPayload::V2 { .. } => 2,
+            ListingEventPayload::V3 { .. } => 3,
+        }
+    }
+}
+
+impl VersionedPayload for ListingPurchasedPayload {
+    fn schema_version(&self) -> u8 {
+        match self {
+            ListingPurchasedPayload::V1 { .. } => 1,
+            ListingPurchasedPayload::V2 { .. } => 2,
+            ListingPurchasedPayload::V3 { .. } => 3,
+            ListingPurchasedPayload::V4 { .. } => 4,
+        }
+    }
+}
+
+impl VersionedPayload for OutpostCreatedPayload {
+    fn schema_version(&self) -> u8 {
+        match self {
+            OutpostCreatedPayload::V1 { .. } => 1,
+        }
+    }
+}
+
+impl VersionedPayload for SameOwnerTransferPayload {
+    fn schema_version(&self) -> u8 {
+        match self {
+            SameOwnerTransferPayload::V1 { .. } => 1,
+        }
+    }
+}
+
+impl VersionedPayload for DappTransferPayload {
+    fn schema_version(&self) -> u8 {
+        match self {
+            DappTransferPayload::V1 { .. } => 1,
+        }
+    }
+}
+
+impl VersionedPayload for OutpostKeyRecoveredPayload {
+    fn schema_version(&self) -> u8 {
+        match self {
+            OutpostKeyRecoveredPayload::V1 { .. } => 1,
+        }
+    }
+}
+
+impl VersionedPayload for RevenueClaimedPayload {
+    fn schema_version(&self) -> u8 {
+        match self {
+            RevenueClaimedPayload::V1 { .. } => 1,
+        }
+    }
+}
+
+impl VersionedPayload for BlocklistEventPayload {
+    fn schema_version(&self) -> u8 {
+        match self {
+            BlocklistEventPayload::V1 { .. } => 1,
+        }
+    }
+}
+
+impl VersionedPayload for RentalEventPayload {
+    fn schema_version(&self) -> u8 {
+        match self {
+            RentalEventPayload::V1 { .. } => 1,
+        }
+    }
+}
+
+impl VersionedPayload for NftRentedPayload {
+    fn schema_version(&self) -> u8 {
+        match self {
+            NftRentedPayload::V1 { .. } => 1,
+        }
+    }
+}
+
+impl VersionedPayload for LoanEventPayload {
+    fn schema_version(&self) -> u8 {
+        match self {
+            LoanEventPayload::V1 { .. } => 1,
+        }
+    }
+}
+
+impl VersionedPayload for FlaggedCollectionListedPayload {
+    fn schema_version(&self) -> u8 {
+        match self {
+            FlaggedCollectionListedPayload::V1 { .. } => 1,
+        }
+    }
+}
+
+impl VersionedPayload for OutpostRelinkedPayload {
+    fn schema_version(&self) -> u8 {
+        match self {
+            OutpostRelinkedPayload::V1 { .. } => 1,
+        }
+    }
+}
+
+impl VersionedPayload for OutpostUpgradedPayload {
+    fn schema_version(&self) -> u8 {
+        match self {
+            OutpostUpgradedPayload::V1 { .. } => 1,
+        }
+    }
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct ListingCreated {
+    pub schema_version: u8,
+    pub payload: ListingEventPayload,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct ListingUpdated {
+    pub schema_version: u8,
+    pub payload: ListingEventPayload,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct ListingCanceled {
+    pub schema_version: u8,
+    pub payload: ListingEventPayload,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct ListingPurchased {
+    pub schema_version: u8,
+    pub payload: ListingPurchasedPayload,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct OutpostCreated {
+    pub schema_version: u8,
+    pub payload: OutpostCreatedPayload,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct SameOwnerTransfer {
+    pub schema_version: u8,
+    pub payload: SameOwnerTransferPayload,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct DappTransfer {
+    pub schema_version: u8,
+    pub payload: DappTransferPayload,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct OutpostKeyRecovered {
+    pub schema_version: u8,
+    pub payload: OutpostKeyRecoveredPayload,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct RevenueClaimed {
+    pub schema_version: u8,
+    pub payload: RevenueClaimedPayload,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct BuyerBlocked {
+    pub schema_version: u8,
+    pub payload: BlocklistEventPayload,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct BuyerUnblocked {
+    pub schema_version: u8,
+    pub payload: BlocklistEventPayload,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct RentalListed {
+    pub schema_version: u8,
+    pub payload: RentalEventPayload,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct RentalCanceled {
+    pub schema_version: u8,
+    pub payload: RentalEventPayload,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct NftRented {
+    pub schema_version: u8,
+    pub payload: NftRentedPayload,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct LoanOffered {
+    pub schema_version: u8,
+    pub payload: LoanEventPayload,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct LoanAccepted {
+    pub schema_version: u8,
+    pub payload: LoanEventPayload,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+pub struct LoanRepaid {
+    pub schema_version: u8,
+    pub payload: LoanEventPayload,
+}
 
 #[derive(ScryptoSbor, ScryptoEvent)]
-struct ListingCreated {
-    listing: Listing,
-    outpost_account: ComponentAddress,
-    nft_id: NonFungibleGlobalId,
+pub struct LoanClaimed {
+    pub schema_version: u8,
+    pub payload: LoanEventPayload,
 }
 
 #[derive(ScryptoSbor, ScryptoEvent)]
-struct ListingUpdated {
-    listing: Listing,
-    outpost_account: ComponentAddress,
-    nft_id: NonFungibleGlobalId,
+pub struct FlaggedCollectionListed {
+    pub schema_version: u8,
+    pub payload: FlaggedCollectionListedPayload,
 }
 
 #[derive(ScryptoSbor, ScryptoEvent)]
-struct ListingCanceled {
-    listing: Listing,
-    outpost_account: ComponentAddress,
-    nft_id: NonFungibleGlobalId,
+pub struct OutpostRelinked {
+    pub schema_version: u8,
+    pub payload: OutpostRelinkedPayload,
 }
 
 #[derive(ScryptoSbor, ScryptoEvent)]
-struct ListingPurchased {
-    listing: Listing,
-    outpost_account: ComponentAddress,
-    nft_id: NonFungibleGlobalId,
+pub struct OutpostUpgraded {
+    pub schema_version: u8,
+    pub payload: OutpostUpgradedPayload,
 }
 
 #[blueprint]
-#[events(ListingCreated, ListingUpdated, ListingCanceled, ListingPurchased)]
+#[events(
+    ListingCreated,
+    ListingUpdated,
+    ListingCanceled,
+    ListingPurchased,
+    OutpostCreated,
+    SameOwnerTransfer,
+    DappTransfer,
+    OutpostKeyRecovered,
+    RevenueClaimed,
+    BuyerBlocked,
+    BuyerUnblocked,
+    RentalListed,
+    RentalCanceled,
+    NftRented,
+    LoanOffered,
+    LoanAccepted,
+    LoanRepaid,
+    LoanClaimed,
+    FlaggedCollectionListed,
+    OutpostRelinked,
+    OutpostUpgraded
+)]
 mod event {
 
+    enable_method_auth! {
+    roles {
+        admin => updatable_by: [];
+    },
+    methods {
+        listing_event => PUBLIC;
+        update_listing_event => PUBLIC;
+        cancel_listing_event => PUBLIC;
+        purchase_listing_event => PUBLIC;
+        multi_listing_event => PUBLIC;
+        multi_purchase_event => PUBLIC;
+        outpost_created_event => PUBLIC;
+        same_owner_transfer_event => PUBLIC;
+        dapp_transfer_event => PUBLIC;
+        key_recovered_event => PUBLIC;
+        revenue_claimed_event => PUBLIC;
+        buyer_blocked_event => PUBLIC;
+        buyer_unblocked_event => PUBLIC;
+        rental_listed_event => PUBLIC;
+        rental_canceled_event => PUBLIC;
+        nft_rented_event => PUBLIC;
+        loan_offered_event => PUBLIC;
+        loan_accepted_event => PUBLIC;
+        loan_repaid_event => PUBLIC;
+        loan_claimed_event => PUBLIC;
+        flagged_collection_listed_event => PUBLIC;
+        outpost_relinked_event => PUBLIC;
+        outpost_upgraded_event => PUBLIC;
+        get_collection_stats => PUBLIC;
+        get_floor_price => PUBLIC;
+        set_price_oracle => restrict_to: [admin];
+    }
+    }
+
     struct Event {
         emitter_badge_auth: ResourceAddress,
+        /// Market statistics keyed by (collection, currency)
+        collection_stats: KeyValueStore<(ResourceAddress, ResourceAddress), CollectionStats>,
+        /// An optional price oracle that every sale is forwarded to
+        price_oracle: Option<Global<PriceOracle>>,
+        /// The cheapest public active listings keyed by (collection, currency), which the floor is taken from
+        floor_books: KeyValueStore<(ResourceAddress, ResourceAddress), FloorBook>,
+        /// Every public active listing the floor books know about, and whether it is in its book or left out of it
+        floor_entries: KeyValueStore<NonFungibleGlobalId, bool>,
     }
 
     impl Event {
-        pub fn create_event_listener(emitter_badge_auth: ResourceAddress) -> Global<Event> {
+        pub fn create_event_listener(
+            emitter_badge_auth: ResourceAddress,
+            admin: ResourceAddress,
+        ) -> Global<Event> {
             let (event_address_reservation, _event_component_address) =
                 Runtime::allocate_component_address(Event::blueprint_id());
 
-            Self { emitter_badge_auth }
-                .instantiate()
-                .prepare_to_globalize(OwnerRole::None)
-                .with_address(event_address_reservation)
-                .globalize()
+            Self {
+                emitter_badge_auth,
+                collection_stats: KeyValueStore::new(),
+                price_oracle: None,
+                floor_books: KeyValueStore::new(),
+                floor_entries: KeyValueStore::new(),
+            }
+            .instantiate()
+            .prepare_to_globalize(OwnerRole::None)
+            .roles(roles!(
+                admin => rule!(require(admin));
+            ))
+            .with_address(event_address_reservation)
+            .globalize()
         }
 
         pub fn listing_event(
-            &self,
+            &mut self,
             listing: Listing,
             nft_id: NonFungibleGlobalId,
             emitter_badge: Proof,
         ) {
             emitter_badge.check(self.emitter_badge_auth);
+            self.record_listing(&listing);
+
+            let payload = Self::listing_payload(listing, nft_id);
+
             Runtime::emit_event(ListingCreated {
-                listing: listing.clone(),
-                outpost_account: listing.outpost_account,
-                nft_id,
+                schema_version: payload.schema_version(),
+                payload,
             });
         }
 
         pub fn update_listing_event(
-            &self,
+            &mut self,
             listing: Listing,
             nft_id: NonFungibleGlobalId,
             emitter_badge: Proof,
         ) {
             emitter_badge.check(self.emitter_badge_auth);
+            self.record_price_change(&listing);
+
+            let payload = Self::listing_payload(listing, nft_id);
+
             Runtime::emit_event(ListingUpdated {
-                listing: listing.clone(),
-                outpost_account: listing.outpost_account,
-                nft_id,
+                schema_version: payload.schema_version(),
+                payload,
             });
         }
 
         pub fn cancel_listing_event(
-            &self,
+            &mut self,
             listing: Listing,
             nft_id: NonFungibleGlobalId,
             emitter_badge: Proof,
         ) {
             emitter_badge.check(self.emitter_badge_auth);
+            self.record_delisting(&listing);
+
+            let payload = Self::listing_payload(listing, nft_id);
+
             Runtime::emit_event(ListingCanceled {
-                listing: listing.clone(),
-                outpost_account: listing.outpost_account,
-                nft_id,
+                schema_version: payload.schema_version(),
+                payload,
             });
         }
 
         pub fn purchase_listing_event(
-            &self,
+            &mut self,
             listing: Listing,
             nft_id: NonFungibleGlobalId,
+            settlement: Settlement,
             emitter_badge: Proof,
         ) {
             emitter_badge.check(self.emitter_badge_auth);
+            self.record_sale(&listing);
+            self.report_sale(&listing, settlement.recipient);
 
-            Runtime::emit_event(ListingPurchased {
-                listing: listing.clone(),
+            let payload = ListingPurchasedPayload::V4 {
                 outpost_account: listing.outpost_account,
+                listing: listing.into(),
                 nft_id,
+                permission: settlement.permission,
+                recipient: Some(settlement.recipient),
+                marketplace_fee: settlement.marketplace_fee,
+                royalty_amount: settlement.royalty_amount,
+                protocol_fee: settlement.protocol_fee,
+                seller_proceeds: settlement.seller_proceeds,
+                timestamp: Clock::current_time_rounded_to_seconds(),
+            };
+
+            Runtime::emit_event(ListingPurchased {
+                schema_version: payload.schema_version(),
+                payload,
             });
         }
 
-        pub fn multi_listing_event(&self, listings: Vec<Listing>, emitter_badge: Proof) {
+        pub fn multi_listing_event(&mut self, listings: Vec<Listing>, emitter_badge: Proof) {
             emitter_badge.check(self.emitter_badge_auth);
 
-            // Use zip to iterate over both vectors simultaneously
             for listing in listings {
+                self.record_listing(&listing);
+                let nft_id = listing.nfgid.clone();
+
+                let payload = Self::listing_payload(listing, nft_id);
+
                 Runtime::emit_event(ListingCreated {
-                    listing: listing.clone(),
-                    outpost_account: listing.outpost_account,
-                    nft_id: listing.nfgid, // Wrap single NFT ID in a vector to match event structure
+                    schema_version: payload.schema_version(),
+                    payload,
                 });
             }
         }
 
-        pub fn multi_purchase_event(&self, listings: Vec<Listing>, emitter_badge: Proof) {
+        /// Bulk purchases are settled as a single payment, so the settlement totals are split across the purchased
+        /// listings pro-rata by price. The last listing takes any rounding remainder so the events add up to the totals.
+        pub fn multi_purchase_event(
+            &mut self,
+            listings: Vec<Listing>,
+            settlement: Settlement,
+            emitter_badge: Proof,
+        ) {
             emitter_badge.check(self.emitter_badge_auth);
 
-            // Use zip to iterate over both vectors simultaneously
-            for listing in listings {
-                Runtime::emit_event(ListingPurchased {
-                    listing: listing.clone(),
+            let timestamp = Clock::current_time_rounded_to_seconds();
+
+            let total_price = listings.iter().fold(dec!(0), |acc, listing| {
+                acc.checked_add(listing.price).unwrap()
+            });
+
+            let mut remaining_fee = settlement.marketplace_fee;
+            let mut remaining_royalty = settlement.royalty_amount;
+            let mut remaining_protocol_fee = settlement.protocol_fee;
+            let mut remaining_proceeds = settlement.seller_proceeds;
+
+            let listing_count = listings.len();
+
+            for (index, listing) in listings.into_iter().enumerate() {
+                self.record_sale(&listing);
+                self.report_sale(&listing, settlement.recipient);
+
+                let (marketplace_fee, royalty_amount, protocol_fee, seller_proceeds) =
+                    if index + 1 == listing_count {
+                        (
+                            remaining_fee,
+                            remaining_royalty,
+                            remaining_protocol_fee,
+                            remaining_proceeds,
+                        )
+                    } else {
+                        let share = listing.price.checked_div(total_price).unwrap();
+                        (
+                            Self::pro_rata(settlement.marketplace_fee, share),
+                            Self::pro_rata(settlement.royalty_amount, share),
+                            Self::pro_rata(settlement.protocol_fee, share),
+                            Self::pro_rata(settlement.seller_proceeds, share),
+                        )
+                    };
+
+                remaining_fee = remaining_fee.checked_sub(marketplace_fee).unwrap();
+                remaining_royalty = remaining_royalty.checked_sub(royalty_amount).unwrap();
+                remaining_protocol_fee = remaining_protocol_fee.checked_sub(protocol_fee).unwrap();
+                remaining_proceeds = remaining_proceeds.checked_sub(seller_proceeds).unwrap();
+
+                let payload = ListingPurchasedPayload::V4 {
                     outpost_account: listing.outpost_account,
-                    nft_id: listing.nfgid, // Wrap single NFT ID in a vector to match event structure
+                    nft_id: listing.nfgid.clone(),
+                    listing: listing.into(),
+                    permission: settlement.permission,
+                    recipient: Some(settlement.recipient),
+                    marketplace_fee,
+                    royalty_amount,
+                    protocol_fee,
+                    seller_proceeds,
+                    timestamp,
+                };
+
+                Runtime::emit_event(ListingPurchased {
+                    schema_version: payload.schema_version(),
+                    payload,
                 });
             }
         }
+
+        pub fn outpost_created_event(
+            &self,
+            outpost_component: ComponentAddress,
+            outpost_account: ComponentAddress,
+            emitter_badge: Proof,
+        ) {
+            emitter_badge.check(self.emitter_badge_auth);
+
+            let payload = OutpostCreatedPayload::V1 {
+                outpost_component,
+                outpost_account,
+            };
+
+            Runtime::emit_event(OutpostCreated {
+                schema_version: payload.schema_version(),
+                payload,
+            });
+        }
+
+        pub fn same_owner_transfer_event(
+            &self,
+            outpost_component: ComponentAddress,
+            nft_ids: Vec<NonFungibleGlobalId>,
+            recipient: ComponentAddress,
+            emitter_badge: Proof,
+        ) {
+            emitter_badge.check(self.emitter_badge_auth);
+
+            let payload = SameOwnerTransferPayload::V1 {
+                outpost_component,
+                nft_ids,
+                recipient,
+            };
+
+            Runtime::emit_event(SameOwnerTransfer {
+                schema_version: payload.schema_version(),
+                payload,
+            });
+        }
+
+        pub fn dapp_transfer_event(
+            &self,
+            outpost_component: ComponentAddress,
+            nft_ids: Vec<NonFungibleGlobalId>,
+            dapp: ComponentAddress,
+            method: String,
+            emitter_badge: Proof,
+        ) {
+            emitter_badge.check(self.emitter_badge_auth);
+
+            let payload = DappTransferPayload::V1 {
+                outpost_component,
+                nft_ids,
+                dapp,
+                method,
+            };
+
+            Runtime::emit_event(DappTransfer {
+                schema_version: payload.schema_version(),
+                payload,
+            });
+        }
+
+        pub fn key_recovered_event(
+            &self,
+            outpost_component: ComponentAddress,
+            outpost_account: ComponentAddress,
+            new_key: NonFungibleGlobalId,
+            emitter_badge: Proof,
+        ) {
+            emitter_badge.check(self.emitter_badge_auth);
+
+            let payload = OutpostKeyRecoveredPayload::V1 {
+                outpost_component,
+                outpost_account,
+                new_key,
+            };
+
+            Runtime::emit_event(OutpostKeyRecovered {
+                schema_version: payload.schema_version(),
+                payload,
+            });
+        }
+
+        pub fn revenue_claimed_event(
+            &self,
+            outpost_component: ComponentAddress,
+            recipient: ComponentAddress,
+            currency: ResourceAddress,
+            amount: Decimal,
+            emitter_badge: Proof,
+        ) {
+            emitter_badge.check(self.emitter_badge_auth);
+
+            let payload = RevenueClaimedPayload::V1 {
+                outpost_component,
+                recipient,
+                currency,
+                amount,
+            };
+
+            Runtime::emit_event(RevenueClaimed {
+                schema_version: payload.schema_version(),
+                payload,
+            });
+        }
+
+        pub fn buyer_blocked_event(
+            &self,
+            outpost_component: ComponentAddress,
+            buyer: BlockedBuyer,
+            emitter_badge: Proof,
+        ) {
+            emitter_badge.check(self.emitter_badge_auth);
+
+            let payload = BlocklistEventPayload::V1 {
+                outpost_component,
+                buyer,
+            };
+
+            Runtime::emit_event(BuyerBlocked {
+                schema_version: payload.schema_version(),
+                payload,
+            });
+        }
+
+        pub fn buyer_unblocked_event(
+            &self,
+            outpost_component: ComponentAddress,
+            buyer: BlockedBuyer,
+            emitter_badge: Proof,
+        ) {
+            emitter_badge.check(self.emitter_badge_auth);
+
+            let payload = BlocklistEventPayload::V1 {
+                outpost_component,
+                buyer,
+            };
+
+            Runtime::emit_event(BuyerUnblocked {
+                schema_version: payload.schema_version(),
+                payload,
+            });
+        }
+
+        pub fn rental_listed_event(
+            &self,
+            rental_listing: RentalListing,
+            nft_id: NonFungibleGlobalId,
+            emitter_badge: Proof,
+        ) {
+            emitter_badge.check(self.emitter_badge_auth);
+
+            let payload = RentalEventPayload::V1 {
+                outpost_account: rental_listing.outpost_account,
+                rental_listing,
+                nft_id,
+            };
+
+            Runtime::emit_event(RentalListed {
+                schema_version: payload.schema_version(),
+                payload,
+            });
+        }
+
+        pub fn rental_canceled_event(
+            &self,
+            rental_listing: RentalListing,
+            nft_id: NonFungibleGlobalId,
+            emitter_badge: Proof,
+        ) {
+            emitter_badge.check(self.emitter_badge_auth);
+
+            let payload = RentalEventPayload::V1 {
+                outpost_account: rental_listing.outpost_account,
+                rental_listing,
+                nft_id,
+            };
+
+            Runtime::emit_event(RentalCanceled {
+                schema_version: payload.schema_version(),
+                payload,
+            });
+        }
+
+        pub fn nft_rented_event(
+            &self,
+            rental_listing: RentalListing,
+            nft_id: NonFungibleGlobalId,
+            renter: ComponentAddress,
+            expires_at: Instant,
+            protocol_fee: Decimal,
+            emitter_badge: Proof,
+        ) {
+            emitter_badge.check(self.emitter_badge_auth);
+
+            let payload = NftRentedPayload::V1 {
+                outpost_account: rental_listing.outpost_account,
+                rental_listing,
+                nft_id,
+                renter,
+                expires_at,
+                protocol_fee,
+            };
+
+            Runtime::emit_event(NftRented {
+                schema_version: payload.schema_version(),
+                payload,
+            });
+        }
+
+        pub fn loan_offered_event(
+            &self,
+            outpost_account: ComponentAddress,
+            loan_id: u64,
+            loan: Loan,
+            emitter_badge: Proof,
+        ) {
+            emitter_badge.check(self.emitter_badge_auth);
+
+            let payload = LoanEventPayload::V1 {
+                outpost_account,
+                loan_id,
+                loan,
+            };
+
+            Runtime::emit_event(LoanOffered {
+                schema_version: payload.schema_version(),
+                payload,
+            });
+        }
+
+        pub fn loan_accepted_event(
+            &self,
+            outpost_account: ComponentAddress,
+            loan_id: u64,
+            loan: Loan,
+            emitter_badge: Proof,
+        ) {
+            emitter_badge.check(self.emitter_badge_auth);
+
+            let payload = LoanEventPayload::V1 {
+                outpost_account,
+                loan_id,
+                loan,
+            };
+
+            Runtime::emit_event(LoanAccepted {
+                schema_version: payload.schema_version(),
+                payload,
+            });
+        }
+
+        pub fn loan_repaid_event(
+            &self,
+            outpost_account: ComponentAddress,
+            loan_id: u64,
+            loan: Loan,
+            emitter_badge: Proof,
+        ) {
+            emitter_badge.check(self.emitter_badge_auth);
+
+            let payload = LoanEventPayload::V1 {
+                outpost_account,
+                loan_id,
+                loan,
+            };
+
+            Runtime::emit_event(LoanRepaid {
+                schema_version: payload.schema_version(),
+                payload,
+            });
+        }
+
+        pub fn loan_claimed_event(
+            &self,
+            outpost_account: ComponentAddress,
+            loan_id: u64,
+            loan: Loan,
+            emitter_badge: Proof,
+        ) {
+            emitter_badge.check(self.emitter_badge_auth);
+
+            let payload = LoanEventPayload::V1 {
+                outpost_account,
+                loan_id,
+                loan,
+            };
+
+            Runtime::emit_event(LoanClaimed {
+                schema_version: payload.schema_version(),
+                payload,
+            });
+        }
+
+        pub fn flagged_collection_listed_event(
+            &self,
+            outpost_account: ComponentAddress,
+            collection: ResourceAddress,
+            flag: CollectionFlag,
+            emitter_badge: Proof,
+        ) {
+            emitter_badge.check(self.emitter_badge_auth);
+
+            let payload = FlaggedCollectionListedPayload::V1 {
+                outpost_account,
+                collection,
+                flag,
+            };
+
+            Runtime::emit_event(FlaggedCollectionListed {
+                schema_version: payload.schema_version(),
+                payload,
+            });
+        }
+
+        pub fn outpost_relinked_event(
+            &self,
+            outpost_component: ComponentAddress,
+            old_account: ComponentAddress,
+            new_account: ComponentAddress,
+            emitter_badge: Proof,
+        ) {
+            emitter_badge.check(self.emitter_badge_auth);
+
+            let payload = OutpostRelinkedPayload::V1 {
+                outpost_component,
+                old_account,
+                new_account,
+            };
+
+            Runtime::emit_event(OutpostRelinked {
+                schema_version: payload.schema_version(),
+                payload,
+            });
+        }
+
+        pub fn outpost_upgraded_event(
+            &self,
+            outpost_account: ComponentAddress,
+            old_outpost: ComponentAddress,
+            new_outpost: ComponentAddress,
+            version: u32,
+            emitter_badge: Proof,
+        ) {
+            emitter_badge.check(self.emitter_badge_auth);
+
+            let payload = OutpostUpgradedPayload::V1 {
+                outpost_account,
+                old_outpost,
+                new_outpost,
+                version,
+            };
+
+            Runtime::emit_event(OutpostUpgraded {
+                schema_version: payload.schema_version(),
+                payload,
+            });
+        }
+
+        pub fn get_collection_stats(
+            &self,
+            collection: ResourceAddress,
+            currency: ResourceAddress,
+        ) -> Option<CollectionStats> {
+            self.collection_stats
+                .get(&(collection, currency))
+                .map(|stats| stats.clone())
+        }
+
+        /// The lowest price of the public active listings for a collection in a currency, if there are any and it is known -
+        /// see `CollectionStats::lowest_listings`.
+        pub fn get_floor_price(
+            &self,
+            collection: ResourceAddress,
+            currency: ResourceAddress,
+        ) -> Option<Decimal> {
+            self.collection_stats
+                .get(&(collection, currency))
+                .and_then(|stats| stats.lowest_listings.first().map(|(price, _)| *price))
+        }
+
+        /// Links the price oracle that sales are forwarded to. Every purchase waits on the oracle, so it must accept this
+        /// component as its reporter - otherwise every sale would revert.
+        pub fn set_price_oracle(&mut self, price_oracle: Option<Global<PriceOracle>>) {
+            if let Some(price_oracle) = price_oracle {
+                let event_component =
+                    ComponentAddress::new_or_panic(Runtime::global_address().into());
+
+                assert!(
+                    price_oracle.reporter() == event_component,
+                    "[set_price_oracle] The price oracle must accept sales from this event component"
+                );
+            }
+
+            self.price_oracle = price_oracle;
+        }
+
+        // market statistics
+
+        fn report_sale(&self, listing: &Listing, recipient: ComponentAddress) {
+            if let Some(price_oracle) = self.price_oracle {
+                price_oracle.record_sale(
+                    listing.nfgid.clone(),
+                    listing.currency,
+                    listing.price,
+                    listing.outpost_account,
+                    recipient,
+                );
+            }
+        }
+
+        fn stats_for(&self, listing: &Listing) -> CollectionStats {
+            self.collection_stats
+                .get(&(listing.nfgid.resource_address(), listing.currency))
+                .map(|stats| stats.clone())
+                .unwrap_or(CollectionStats {
+                    total_volume: dec!(0),
+                    sale_count: 0,
+                    last_sale_price: None,
+                    last_sale_time: None,
+                    active_listings: 0,
+                    lowest_listings: vec![],
+                })
+        }
+
+        fn save_stats(&mut self, listing: &Listing, stats: CollectionStats) {
+            self.collection_stats
+                .insert((listing.nfgid.resource_address(), listing.currency), stats);
+        }
+
+        fn record_listing(&mut self, listing: &Listing) {
+            let mut stats = self.stats_for(listing);
+
+            stats.active_listings += 1;
+            self.track_floor(&mut stats, listing);
+
+            self.save_stats(listing, stats);
+        }
+
+        fn record_price_change(&mut self, listing: &Listing) {
+            let mut stats = self.stats_for(listing);
+
+            self.untrack_floor(&mut stats, listing);
+            self.track_floor(&mut stats, listing);
+
+            self.save_stats(listing, stats);
+        }
+
+        fn record_delisting(&mut self, listing: &Listing) {
+            let mut stats = self.stats_for(listing);
+
+            stats.active_listings = stats.active_listings.saturating_sub(1);
+            self.untrack_floor(&mut stats, listing);
+
+            self.save_stats(listing, stats);
+        }
+
+        fn record_sale(&mut self, listing: &Listing) {
+            let mut stats = self.stats_for(listing);
+
+            stats.total_volume = stats.total_volume.checked_add(listing.price).unwrap();
+            stats.sale_count += 1;
+            stats.last_sale_price = Some(listing.price);
+            stats.last_sale_time = Some(Clock::current_time_rounded_to_seconds());
+            stats.active_listings = stats.active_listings.saturating_sub(1);
+            self.untrack_floor(&mut stats, listing);
+
+            self.save_stats(listing, stats);
+        }
+
+        fn floor_book(&self, listing: &Listing) -> FloorBook {
+            self.floor_books
+                .get(&(listing.nfgid.resource_address(), listing.currency))
+                .map(|book| book.clone())
+                .unwrap_or(FloorBook {
+                    listings: vec![],
+                    overflow: 0,
+                    overflow_floor: None,
+                })
+        }
+
+        fn save_floor_book(&mut self, stats: &mut CollectionStats, listing: &Listing, book: FloorBook) {
+            stats.lowest_listings = book.known_floor();
+
+            self.floor_books
+                .insert((listing.nfgid.resource_address(), listing.currency), book);
+        }
+
+        fn track_floor(&mut self, stats: &mut CollectionStats, listing: &Listing) {
+            // a listing is only ever counted once, even if it is announced again without being removed first
+            if self.floor_entries.get(&listing.nfgid).is_some() {
+                self.untrack_floor(stats, listing);
+            }
+
+            if !listing.is_public() {
+                return;
+            }
+
+            let mut book = self.floor_book(listing);
+
+            let position = book
+                .listings
+                .iter()
+                .position(|(price, _)| listing.price < *price)
+                .unwrap_or(book.listings.len());
+
+            if position >= FLOOR_BOOK_CAPACITY {
+                book.leave_out(listing.price);
+                self.floor_entries.insert(listing.nfgid.clone(), false);
+            } else {
+                book.listings
+                    .insert(position, (listing.price, listing.nfgid.clone()));
+                self.floor_entries.insert(listing.nfgid.clone(), true);
+
+                if book.listings.len() > FLOOR_BOOK_CAPACITY {
+                    let (price, evicted) = book.listings.pop().unwrap();
+
+                    book.leave_out(price);
+                    self.floor_entries.insert(evicted, false);
+                }
+            }
+
+            self.save_floor_book(stats, listing, book);
+        }
+
+        fn untrack_floor(&mut self, stats: &mut CollectionStats, listing: &Listing) {
+            let in_book = match self.floor_entries.remove(&listing.nfgid) {
+                Some(in_book) => in_book,
+                None => return,
+            };
+
+            let mut book = self.floor_book(listing);
+
+            if in_book {
+                book.listings
+                    .retain(|(_, tracked)| *tracked != listing.nfgid);
+            } else {
+                book.overflow = book.overflow.saturating_sub(1);
+
+                if book.overflow == 0 {
+                    book.overflow_floor = None;
+                }
+            }
+
+            self.save_floor_book(stats, listing, book);
+        }
+
+        fn listing_payload(listing: Listing, nft_id: NonFungibleGlobalId) -> ListingEventPayload {
+            ListingEventPayload::V3 {
+                outpost_account: listing.outpost_account,
+                listing: listing.into(),
+                nft_id,
+            }
+        }
+
+        fn pro_rata(amount: Decimal, share: Decimal) -> Decimal {
+            amount
+                .checked_mul(share)
+                .unwrap()
+                .checked_round(18, RoundingMode::ToZero)
+                .unwrap()
+        }
     }
 }
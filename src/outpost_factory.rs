@@ -1,6 +1,10 @@
 use crate::outpost_account::opentrader::OpenTrader;
+use crate::outpost_account::{BlockedBuyer, Listing, RentalListing};
 use crate::outpost_event::event;
 use crate::outpost_event::event::Event;
+use crate::price_oracle::price_oracle::PriceOracle;
+use crate::price_oracle::{DEFAULT_RESALE_COOLDOWN_SECONDS, DEFAULT_WINDOW_SECONDS};
+use scrypto::engine::scrypto_env::ScryptoVmV1Api;
 use scrypto::prelude::*;
 
 // This blueprint creates all the open trader accounts. It creates emitter badges that are used to authenticate event emitters from each trader acccount and allows
@@ -21,35 +25,229 @@ struct EmitterKey {
     name: String,
 }
 
+/// The highest protocol fee the hub admin can ever set, as a rate of the sale price.
+pub const PROTOCOL_FEE_CAP: Decimal = dec!(0.05);
+
+/// Increases to the protocol fee only take effect after this delay, giving traders time to react. Decreases apply immediately.
+pub const PROTOCOL_FEE_TIMELOCK_SECONDS: i64 = 7 * 24 * 60 * 60;
+
+/// A marketplace in the OpenHub directory. Registration is curated by the hub admin, and the fee cap is enforced by every
+/// Outpost on purchases made with the marketplace's permission badge.
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct MarketplaceRegistration {
+    pub component: ComponentAddress,
+    pub fee_cap: Decimal,
+    pub dapp_definition: ComponentAddress,
+}
+
+#[derive(ScryptoSbor, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FlagReason {
+    /// A copy of another collection passing itself off as the original
+    Counterfeit,
+    /// A collection whose admin badges or minting rights are known to be in the wrong hands
+    Compromised,
+}
+
+#[derive(ScryptoSbor, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FlagSeverity {
+    /// Listing is still allowed, but Outposts emit a warning so marketplaces can label or hide the listing
+    Warn,
+    /// Outposts refuse to list the collection
+    Block,
+}
+
+/// An entry in the OpenHub moderation registry.
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct CollectionFlag {
+    pub reason: FlagReason,
+    pub severity: FlagSeverity,
+    pub flagged_at: Instant,
+    /// When the collection's creator appealed the flag, if they have. The flag stays in force until the hub admin or a
+    /// moderator resolves the appeal.
+    pub appealed_at: Option<Instant>,
+    /// When the appeal was rejected, if it was. A rejected flag can't be appealed again unless the collection is re-flagged.
+    pub appeal_rejected_at: Option<Instant>,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+struct CollectionFlagged {
+    collection: ResourceAddress,
+    flag: CollectionFlag,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+struct CollectionFlagAppealed {
+    collection: ResourceAddress,
+    flag: CollectionFlag,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+struct CollectionFlagAppealRejected {
+    collection: ResourceAddress,
+    flag: CollectionFlag,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+struct CollectionFlagCleared {
+    collection: ResourceAddress,
+    /// Whether the flag was cleared after the collection's creator appealed it
+    appealed: bool,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+struct MarketplaceRegistered {
+    permission: ResourceAddress,
+    registration: MarketplaceRegistration,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+struct MarketplaceDeregistered {
+    permission: ResourceAddress,
+}
+
 #[derive(ScryptoSbor, ScryptoEvent)]
-struct OutpostCreated {
-    outpost_component: ComponentAddress,
-    outpost_account: ComponentAddress,
+struct ProtocolFeeScheduled {
+    fee: Decimal,
+    effective_from: Instant,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+struct ProtocolFeeUpdated {
+    fee: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+struct ProtocolFeesWithdrawn {
+    currency: ResourceAddress,
+    amount: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+struct ProtocolPauseUpdated {
+    paused: bool,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+struct CollectionPauseUpdated {
+    collection: ResourceAddress,
+    paused: bool,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+struct OutpostPackageUpdated {
+    package: PackageAddress,
+    version: u32,
 }
 
 #[blueprint]
-#[events(OutpostCreated)]
+#[events(
+    ProtocolPauseUpdated,
+    CollectionPauseUpdated,
+    OutpostPackageUpdated,
+    MarketplaceRegistered,
+    MarketplaceDeregistered,
+    ProtocolFeeScheduled,
+    ProtocolFeeUpdated,
+    ProtocolFeesWithdrawn,
+    CollectionFlagged,
+    CollectionFlagAppealed,
+    CollectionFlagAppealRejected,
+    CollectionFlagCleared
+)]
 mod openhub {
 
+    enable_method_auth! {
+    roles {
+        admin => updatable_by: [];
+        moderator => updatable_by: [admin];
+    },
+    methods {
+        create_outpost => PUBLIC;
+        relink_outpost => PUBLIC;
+        set_outpost_package => restrict_to: [admin];
+        upgrade_outpost => PUBLIC;
+        get_outpost_version => PUBLIC;
+        latest_outpost_version => PUBLIC;
+        get_outpost => PUBLIC;
+        get_outposts => PUBLIC;
+        get_outpost_owner => PUBLIC;
+        is_registered_outpost => PUBLIC;
+        set_protocol_pause => restrict_to: [admin];
+        set_collection_pause => restrict_to: [admin];
+        flag_collection => restrict_to: [admin, moderator];
+        clear_collection_flag => restrict_to: [admin, moderator];
+        appeal_collection_flag => PUBLIC;
+        resolve_appeal => restrict_to: [admin, moderator];
+        get_collection_flag => PUBLIC;
+        is_collection_blocked => PUBLIC;
+        get_flagged_collections => PUBLIC;
+        register_marketplace => restrict_to: [admin];
+        deregister_marketplace => restrict_to: [admin];
+        get_marketplace => PUBLIC;
+        get_registered_marketplaces => PUBLIC;
+        set_protocol_fee => restrict_to: [admin];
+        get_protocol_fee => PUBLIC;
+        get_pending_protocol_fee => PUBLIC;
+        deposit_protocol_fee => PUBLIC;
+        withdraw_protocol_fees => restrict_to: [admin];
+        is_trading_paused => PUBLIC;
+        fetch_virt_badge => PUBLIC;
+        fetch_royal_nft_depositer_badge => PUBLIC;
+        get_price_oracle => PUBLIC;
+        get_event_component => PUBLIC;
+    }
+    }
+
     struct OpenHub {
         /// The badge that is stored and locked in a trader account to authenticate event emitters
         emitter_trader_badge: NonFungibleResourceManager,
+        /// The hub's own emitter badge, used to emit the Outpost relink and upgrade events through the event component
+        emitter_badge: NonFungibleVault,
         /// The personal user badge that a user holds and uses to authenticate methods on their trading account
         outpost_account_badge: NonFungibleResourceManager,
         /// The badge that is used to allow trader accounts to hold and trade Royalty NFTs
         royal_nft_depositer_badge: FungibleResourceManager,
         /// Event emitter component
         event_manager: Global<event::Event>,
+        /// The price oracle the event component reports every sale to
+        price_oracle: Global<PriceOracle>,
         /// Hub Component Address
         component_address: ComponentAddress,
         /// AccountLocker for all traders
         account_locker: Global<AccountLocker>,
-        /// Created accounts
-        registered_accounts: KeyValueStore<ComponentAddress, ComponentAddress>,
+        /// The Outposts created by each account, oldest first. An account can have any number of Outposts, each with its own key.
+        registered_accounts: KeyValueStore<ComponentAddress, Vec<ComponentAddress>>,
+        /// Reverse lookup of every Outpost created by this hub to its linked account and current key
+        registered_outposts: KeyValueStore<ComponentAddress, (ComponentAddress, NonFungibleGlobalId)>,
         // Transient Tokens
         transient_token_manager: FungibleResourceManager,
         // package admin
         admin: ResourceAddress,
+        /// Emergency pause of listing and purchasing across every Outpost created by this hub
+        paused: bool,
+        /// Collections that can't be listed or purchased on any Outpost while the rest of the protocol keeps trading
+        paused_collections: KeyValueStore<ResourceAddress, ()>,
+        /// The package new Outposts are instantiated from. It must contain an OpenTrader blueprint with the same create_trader
+        /// and upgrade interface as this one.
+        outpost_package: PackageAddress,
+        /// Incremented every time the Outpost package is changed
+        outpost_version: u32,
+        /// The version of the package each registered Outpost was instantiated from
+        outpost_versions: KeyValueStore<ComponentAddress, u32>,
+        /// Directory of marketplaces keyed by their permission badge resource
+        registered_marketplaces: KeyValueStore<ResourceAddress, MarketplaceRegistration>,
+        /// The permission resources in registered_marketplaces, so the directory can be listed
+        marketplace_permissions: Vec<ResourceAddress>,
+        /// The protocol fee rate charged on the sale price of every purchase through a marketplace badge
+        protocol_fee: Decimal,
+        /// An increase to the protocol fee waiting out its timelock, and when it takes effect
+        pending_protocol_fee: Option<(Decimal, Instant)>,
+        /// Protocol fees collected from Outposts, per currency
+        treasury: KeyValueStore<ResourceAddress, Vault>,
+        /// Moderation registry of collections flagged as counterfeit or compromised
+        flagged_collections: KeyValueStore<ResourceAddress, CollectionFlag>,
+        /// The resources in flagged_collections, so marketplaces can fetch the whole registry
+        flagged_index: Vec<ResourceAddress>,
     }
 
     impl OpenHub {
@@ -82,7 +280,8 @@ mod openhub {
 
             let admin_rule = rule!(require(open_hub_admin.resource_address()));
 
-            let emitter_trader_badge = ResourceBuilder::new_ruid_non_fungible::<EmitterKey>(
+            // the hub keeps the first emitter badge so it can emit its own Outpost events through the event component
+            let hub_emitter_badge = ResourceBuilder::new_ruid_non_fungible::<EmitterKey>(
                 OwnerRole::None,
             )
             .metadata(metadata!(
@@ -102,7 +301,11 @@ mod openhub {
                 minter => global_caller_badge_rule.clone();
                 minter_updater => admin_rule.clone();
             })
-            .create_with_no_initial_supply();
+            .mint_initial_supply([EmitterKey {
+                name: "hub emitter".to_string(),
+            }]);
+
+            let emitter_trader_badge = hub_emitter_badge.resource_manager();
 
             let outpost_account_badge =
                 ResourceBuilder::new_ruid_non_fungible::<TraderKey>(OwnerRole::None)
@@ -179,7 +382,22 @@ mod openhub {
                 })
                 .create_with_no_initial_supply();
 
-            let event_manager = Event::create_event_listener(emitter_trader_badge.address());
+            let event_manager = Event::create_event_listener(
+                emitter_trader_badge.address(),
+                open_hub_admin.resource_address(),
+            );
+
+            // every sale through an Outpost of this hub feeds the price oracle
+            let price_oracle = PriceOracle::start_price_oracle(
+                event_manager.address(),
+                open_hub_admin.resource_address(),
+                DEFAULT_WINDOW_SECONDS,
+                DEFAULT_RESALE_COOLDOWN_SECONDS,
+            );
+
+            open_hub_admin.authorize_with_all(|| {
+                event_manager.set_price_oracle(Some(price_oracle));
+            });
 
             let locker_badge_rule = rule!(require(emitter_trader_badge.address()));
 
@@ -194,17 +412,36 @@ mod openhub {
 
             (Self {
                 emitter_trader_badge,
+                emitter_badge: NonFungibleVault::with_bucket(hub_emitter_badge),
                 outpost_account_badge,
                 royal_nft_depositer_badge,
                 event_manager,
+                price_oracle,
                 component_address,
                 account_locker: locker,
                 registered_accounts: KeyValueStore::new(),
+                registered_outposts: KeyValueStore::new(),
                 transient_token_manager,
-                admin: open_hub_admin.resource_address()
+                admin: open_hub_admin.resource_address(),
+                paused: false,
+                paused_collections: KeyValueStore::new(),
+                outpost_package: Runtime::package_address(),
+                outpost_version: 1,
+                outpost_versions: KeyValueStore::new(),
+                registered_marketplaces: KeyValueStore::new(),
+                marketplace_permissions: vec![],
+                protocol_fee: dec!(0),
+                pending_protocol_fee: None,
+                treasury: KeyValueStore::new(),
+                flagged_collections: KeyValueStore::new(),
+                flagged_index: vec![],
             }
             .instantiate()
             .prepare_to_globalize(OwnerRole::None)
+            .roles(roles!(
+                admin => admin_rule.clone();
+                moderator => rule!(deny_all);
+            ))
             .metadata(metadata! (
                 roles {
                     metadata_setter => admin_rule.clone();
@@ -223,41 +460,12 @@ mod openhub {
             .globalize(), open_hub_admin)
         }
 
-        /// Creates a new open trader account with a emitter badge, personal key, and a badge to hold and trade Royalty NFTs
+        /// Creates a new open trader account with a emitter badge, personal key, and a badge to hold and trade Royalty NFTs.
+        /// An account can create as many Outposts as it likes - e.g. to keep separate strategies apart - each with its own key.
         pub fn create_outpost(&self, my_account: Global<Account>) -> (NonFungibleGlobalId, Bucket) {
-            {
-                // Getting the owner role of the account.
-                let owner_role = my_account.get_owner_role();
-
-                // Assert against it.
-                Runtime::assert_access_rule(owner_role.rule);
-
-                // Assertion passed - the caller is the owner of the account.
-            }
-
-            if self
-                .registered_accounts
-                .get(&my_account.address())
-                .is_some()
-            {
-                panic!("Account already has created an OT Trading Hub - check your wallet for your hub key.");
-            }
-
-            let dapp_def_account =
-                Blueprint::<Account>::create_advanced(OwnerRole::Updatable(rule!(allow_all)), None); // will reset owner role after dapp def metadata has been set
-            dapp_def_account.set_metadata("account_type", String::from("dapp definition"));
-            dapp_def_account.set_metadata("name", "Outpost".to_string());
-            dapp_def_account.set_metadata(
-                "description",
-                "An extension of your account for managing your NFTs across Radix".to_string(),
-            );
-            dapp_def_account.set_metadata("info_url", Url::of("https://www.outpost.trade/"));
-            dapp_def_account.set_metadata(
-                "icon_url",
-                Url::of("https://www.outpost.trade/img/outpost_symbol.png"),
-            );
+            Self::assert_account_owner(&my_account);
 
-            let dapp_def_address = GlobalAddress::from(dapp_def_account.address());
+            let dapp_def_account = Self::create_dapp_definition();
 
             let emitter_badge = self
                 .emitter_trader_badge
@@ -286,43 +494,521 @@ mod openhub {
             let transient_token = self.transient_token_manager.mint(1);
 
             // Instatiation of a trading account via the outpost_account blueprint, passing in badges that will be locked in the accounts.
-            let new_hub_component = OpenTrader::create_trader(
+            let new_hub_component = self.deploy_outpost(
                 nfgid.clone(),
                 my_account,
                 emitter_badge.into(),
                 depositer_permission_badge.into(),
-                self.event_manager,
-                dapp_def_address,
-                self.account_locker.clone(),
+                &dapp_def_account,
                 transient_token,
             );
 
             let hub_component_address = new_hub_component.address();
 
-            dapp_def_account.set_metadata(
-                "claimed_entities",
-                vec![GlobalAddress::from(hub_component_address.clone())],
-            );
-            dapp_def_account.set_owner_role(rule!(require(self.admin)));
-
             self.outpost_account_badge.update_non_fungible_data(
                 nfgid.clone().local_id(),
                 "hub",
                 Some(hub_component_address.clone()),
             );
 
-            Runtime::emit_event(OutpostCreated {
-                outpost_component: hub_component_address.clone(),
-                outpost_account: my_account.address(),
-            });
+            self.link_outpost(my_account.address(), hub_component_address);
 
-            self.registered_accounts
-                .insert(my_account.clone().address(), hub_component_address);
+            self.registered_outposts.insert(
+                hub_component_address,
+                (my_account.address(), nfgid.clone()),
+            );
+
+            self.outpost_versions
+                .insert(hub_component_address, self.outpost_version);
 
             // return the personal trading account badge (and the nfgid of the account for testing purposes)
             (nfgid, personal_trading_account_badge.into())
         }
 
+        /// Re-links an Outpost to a different account, e.g. when a user moves to a new wallet. The owners of both the current
+        /// and the new account must sign. Sales revenue, returned NFTs and loan repayments go to the new account from now on, while
+        /// the Outpost stays under the control of its existing key.
+        pub fn relink_outpost(
+            &self,
+            outpost_address: ComponentAddress,
+            old_account: Global<Account>,
+            new_account: Global<Account>,
+        ) {
+            Self::assert_account_owner(&old_account);
+            Self::assert_account_owner(&new_account);
+
+            assert!(
+                old_account.address() != new_account.address(),
+                "[relink] The Outpost is already linked to this account"
+            );
+
+            self.assert_linked(old_account.address(), outpost_address, "relink");
+
+            let outpost: Global<OpenTrader> = Global::from(outpost_address);
+
+            outpost.relink_account(new_account);
+
+            let (_, key) = self
+                .registered_outposts
+                .get(&outpost_address)
+                .map(|entry| entry.clone())
+                .unwrap();
+
+            self.unlink_outpost(old_account.address(), outpost_address);
+            self.link_outpost(new_account.address(), outpost_address);
+
+            self.registered_outposts
+                .insert(outpost_address, (new_account.address(), key));
+
+            self.event_manager.outpost_relinked_event(
+                outpost_address,
+                old_account.address(),
+                new_account.address(),
+                self.emitter_proof(),
+            );
+        }
+
+        /// Points new Outposts, and upgrades of existing ones, at a new package version. The package must contain an OpenTrader
+        /// blueprint with the same create_trader, export_for_upgrade and import_upgrade interface.
+        pub fn set_outpost_package(&mut self, package: PackageAddress) {
+            self.outpost_package = package;
+            self.outpost_version += 1;
+
+            Runtime::emit_event(OutpostPackageUpdated {
+                package,
+                version: self.outpost_version,
+            });
+        }
+
+        /// Moves a user's Outpost to the latest package version. The old Outpost hands over its badges, escrowed NFTs, listings and
+        /// blocklist and is permanently disabled, while a new Outpost controlled by the same key recreates them. The owner of the
+        /// linked account must prove ownership.
+        pub fn upgrade_outpost(
+            &mut self,
+            my_account: Global<Account>,
+            old_address: ComponentAddress,
+        ) -> ComponentAddress {
+            Self::assert_account_owner(&my_account);
+
+            let key = self.assert_linked(my_account.address(), old_address, "upgrade");
+
+            let current_version = self
+                .outpost_versions
+                .get(&old_address)
+                .map(|version| *version)
+                .unwrap_or(1);
+
+            assert!(
+                current_version < self.outpost_version,
+                "[upgrade] Outpost is already on the latest version"
+            );
+
+            let old_outpost: Global<OpenTrader> = Global::from(old_address);
+
+            let (
+                emitter_badge,
+                depositer_badge,
+                transient_token,
+                nfts,
+                listings,
+                rentals,
+                blocked_buyers,
+            ): (
+                Bucket,
+                Bucket,
+                Bucket,
+                Vec<Bucket>,
+                Vec<Listing>,
+                Vec<RentalListing>,
+                Vec<BlockedBuyer>,
+            ) = old_outpost.export_for_upgrade();
+
+            let dapp_def_account = Self::create_dapp_definition();
+
+            let new_outpost = self.deploy_outpost(
+                key.clone(),
+                my_account,
+                emitter_badge,
+                depositer_badge,
+                &dapp_def_account,
+                transient_token.as_fungible(),
+            );
+
+            new_outpost.import_upgrade(nfts, listings, rentals, blocked_buyers);
+
+            let new_address = new_outpost.address();
+
+            self.outpost_account_badge.update_non_fungible_data(
+                key.local_id(),
+                "hub",
+                Some(new_address),
+            );
+
+            self.unlink_outpost(my_account.address(), old_address);
+            self.link_outpost(my_account.address(), new_address);
+
+            self.registered_outposts.remove(&old_address);
+            self.registered_outposts
+                .insert(new_address, (my_account.address(), key));
+
+            self.outpost_versions.remove(&old_address);
+            self.outpost_versions
+                .insert(new_address, self.outpost_version);
+
+            self.event_manager.outpost_upgraded_event(
+                my_account.address(),
+                old_address,
+                new_address,
+                self.outpost_version,
+                self.emitter_proof(),
+            );
+
+            new_address
+        }
+
+        /// The package version a registered Outpost was instantiated from.
+        pub fn get_outpost_version(&self, outpost: ComponentAddress) -> Option<u32> {
+            self.outpost_versions
+                .get(&outpost)
+                .map(|version| *version)
+        }
+
+        /// The package new Outposts are instantiated from, and its version.
+        pub fn latest_outpost_version(&self) -> (PackageAddress, u32) {
+            (self.outpost_package, self.outpost_version)
+        }
+
+        /// Resolves an account to the first Outpost linked to it through this hub.
+        pub fn get_outpost(&self, account: ComponentAddress) -> Option<ComponentAddress> {
+            self.registered_accounts
+                .get(&account)
+                .and_then(|outposts| outposts.first().cloned())
+        }
+
+        /// Every Outpost linked to an account, oldest first.
+        pub fn get_outposts(&self, account: ComponentAddress) -> Vec<ComponentAddress> {
+            self.registered_accounts
+                .get(&account)
+                .map(|outposts| outposts.clone())
+                .unwrap_or_default()
+        }
+
+        /// Resolves an Outpost to its linked account and the id of the key that currently controls it.
+        pub fn get_outpost_owner(
+            &self,
+            outpost: ComponentAddress,
+        ) -> Option<(ComponentAddress, NonFungibleGlobalId)> {
+            self.registered_outposts
+                .get(&outpost)
+                .map(|owner| owner.clone())
+        }
+
+        /// Checks that a component is a genuine Outpost created by this hub. Marketplaces should check this
+        /// before trusting a component passed to them as an Outpost.
+        pub fn is_registered_outpost(&self, component: ComponentAddress) -> bool {
+            self.registered_outposts.get(&component).is_some()
+        }
+
+        /// Pauses or resumes listing and purchasing on every Outpost. Cancelling listings and withdrawing NFTs or revenue is
+        /// never paused, so users can always exit.
+        pub fn set_protocol_pause(&mut self, paused: bool) {
+            self.paused = paused;
+
+            Runtime::emit_event(ProtocolPauseUpdated { paused });
+        }
+
+        /// Pauses or resumes listing and purchasing of a single collection on every Outpost.
+        pub fn set_collection_pause(
+            &mut self,
+            collection: ResourceAddress,
+            paused: bool,
+        ) {
+            if paused {
+                self.paused_collections.insert(collection, ());
+            } else {
+                self.paused_collections.remove(&collection);
+            }
+
+            Runtime::emit_event(CollectionPauseUpdated { collection, paused });
+        }
+
+        /// Flags a collection in the moderation registry, replacing any existing flag and pending appeal. Besides the hub admin,
+        /// anyone the admin assigns the moderator role can flag and clear collections and resolve appeals.
+        pub fn flag_collection(
+            &mut self,
+            collection: ResourceAddress,
+            reason: FlagReason,
+            severity: FlagSeverity,
+        ) {
+            let flag = CollectionFlag {
+                reason,
+                severity,
+                flagged_at: Clock::current_time_rounded_to_seconds(),
+                appealed_at: None,
+                appeal_rejected_at: None,
+            };
+
+            if self.flagged_collections.get(&collection).is_none() {
+                self.flagged_index.push(collection);
+            }
+
+            self.flagged_collections.insert(collection, flag.clone());
+
+            Runtime::emit_event(CollectionFlagged { collection, flag });
+        }
+
+        /// Removes a collection from the moderation registry, whether or not its creator has appealed.
+        pub fn clear_collection_flag(&mut self, collection: ResourceAddress) {
+            let flag = self
+                .flagged_collections
+                .get(&collection)
+                .map(|flag| flag.clone())
+                .expect("[collection_flag] Collection is not flagged");
+
+            self.remove_collection_flag(collection, flag);
+        }
+
+        /// The creator of a flagged collection can appeal the flag by proving they hold its owner role, e.g. with the creator
+        /// badge of a Royalty NFT collection. The appeal is only recorded - a counterfeit's creator holds its owner role too, so
+        /// the flag stays in force until the hub admin or a moderator resolves the appeal.
+        pub fn appeal_collection_flag(&mut self, collection: ResourceAddress, creator_proof: Proof) {
+            let owner_role = ResourceManager::from_address(collection).get_owner_role();
+
+            creator_proof.authorize(|| Runtime::assert_access_rule(owner_role.rule));
+
+            let mut flag = self
+                .flagged_collections
+                .get(&collection)
+                .map(|flag| flag.clone())
+                .expect("[collection_flag] Collection is not flagged");
+
+            assert!(
+                flag.appealed_at.is_none(),
+                "[collection_flag] This flag has already been appealed"
+            );
+
+            flag.appealed_at = Some(Clock::current_time_rounded_to_seconds());
+
+            self.flagged_collections.insert(collection, flag.clone());
+
+            Runtime::emit_event(CollectionFlagAppealed { collection, flag });
+        }
+
+        /// Resolves a creator's appeal. Upholding the appeal clears the flag, rejecting it keeps the flag in force and stops
+        /// the creator from appealing it again.
+        pub fn resolve_appeal(&mut self, collection: ResourceAddress, uphold: bool) {
+            let mut flag = self
+                .flagged_collections
+                .get(&collection)
+                .map(|flag| flag.clone())
+                .expect("[collection_flag] Collection is not flagged");
+
+            assert!(
+                flag.appealed_at.is_some(),
+                "[collection_flag] This flag has not been appealed"
+            );
+
+            assert!(
+                flag.appeal_rejected_at.is_none(),
+                "[collection_flag] This appeal has already been rejected"
+            );
+
+            if uphold {
+                self.remove_collection_flag(collection, flag);
+            } else {
+                flag.appeal_rejected_at = Some(Clock::current_time_rounded_to_seconds());
+
+                self.flagged_collections.insert(collection, flag.clone());
+
+                Runtime::emit_event(CollectionFlagAppealRejected { collection, flag });
+            }
+        }
+
+        pub fn get_collection_flag(&self, collection: ResourceAddress) -> Option<CollectionFlag> {
+            self.flagged_collections
+                .get(&collection)
+                .map(|flag| flag.clone())
+        }
+
+        /// Checked by Outposts before every listing.
+        pub fn is_collection_blocked(&self, collection: ResourceAddress) -> bool {
+            self.flagged_collections
+                .get(&collection)
+                .map_or(false, |flag| flag.severity == FlagSeverity::Block)
+        }
+
+        pub fn get_flagged_collections(&self) -> Vec<(ResourceAddress, CollectionFlag)> {
+            self.flagged_index
+                .iter()
+                .map(|collection| {
+                    (
+                        *collection,
+                        self.flagged_collections
+                            .get(collection)
+                            .map(|flag| flag.clone())
+                            .unwrap(),
+                    )
+                })
+                .collect()
+        }
+
+        /// Adds a marketplace to the directory, or updates its registration. The fee currently set on the permission badge
+        /// must be within the cap the marketplace commits to.
+        pub fn register_marketplace(
+            &mut self,
+            permission: ResourceAddress,
+            component: ComponentAddress,
+            fee_cap: Decimal,
+            dapp_definition: ComponentAddress,
+        ) {
+            assert!(
+                fee_cap >= dec!(0) && fee_cap <= dec!(1),
+                "[register_marketplace] Fee cap must be between 0 and 1"
+            );
+
+            let current_fee: Option<Decimal> = ResourceManager::from_address(permission)
+                .get_metadata("marketplace_fee")
+                .unwrap();
+
+            assert!(
+                current_fee.unwrap_or(dec!(0)) <= fee_cap,
+                "[register_marketplace] Marketplace fee is above the fee cap"
+            );
+
+            let registration = MarketplaceRegistration {
+                component,
+                fee_cap,
+                dapp_definition,
+            };
+
+            if self.registered_marketplaces.get(&permission).is_none() {
+                self.marketplace_permissions.push(permission);
+            }
+
+            self.registered_marketplaces
+                .insert(permission, registration.clone());
+
+            Runtime::emit_event(MarketplaceRegistered {
+                permission,
+                registration,
+            });
+        }
+
+        pub fn deregister_marketplace(&mut self, permission: ResourceAddress) {
+            assert!(
+                self.registered_marketplaces.get(&permission).is_some(),
+                "[deregister_marketplace] Marketplace is not registered"
+            );
+
+            self.registered_marketplaces.remove(&permission);
+            self.marketplace_permissions
+                .retain(|registered| *registered != permission);
+
+            Runtime::emit_event(MarketplaceDeregistered { permission });
+        }
+
+        pub fn get_marketplace(&self, permission: ResourceAddress) -> Option<MarketplaceRegistration> {
+            self.registered_marketplaces
+                .get(&permission)
+                .map(|registration| registration.clone())
+        }
+
+        /// The full marketplace directory, in order of registration.
+        pub fn get_registered_marketplaces(&self) -> Vec<(ResourceAddress, MarketplaceRegistration)> {
+            self.marketplace_permissions
+                .iter()
+                .map(|permission| {
+                    (
+                        *permission,
+                        self.registered_marketplaces
+                            .get(permission)
+                            .map(|registration| registration.clone())
+                            .unwrap(),
+                    )
+                })
+                .collect()
+        }
+
+        /// Sets the protocol fee, up to `PROTOCOL_FEE_CAP`. A lower fee applies immediately, while a higher fee is scheduled
+        /// and only applies once `PROTOCOL_FEE_TIMELOCK_SECONDS` have passed. Setting a new fee replaces any pending change.
+        pub fn set_protocol_fee(&mut self, fee: Decimal) {
+            assert!(
+                fee >= dec!(0) && fee <= PROTOCOL_FEE_CAP,
+                "[protocol_fee] Protocol fee must be between 0 and {}",
+                PROTOCOL_FEE_CAP
+            );
+
+            // settle a pending increase that has already passed its timelock before comparing against the current fee
+            self.protocol_fee = self.get_protocol_fee();
+
+            if fee <= self.protocol_fee {
+                self.protocol_fee = fee;
+                self.pending_protocol_fee = None;
+
+                Runtime::emit_event(ProtocolFeeUpdated { fee });
+            } else {
+                let effective_from = Clock::current_time_rounded_to_seconds()
+                    .add_seconds(PROTOCOL_FEE_TIMELOCK_SECONDS)
+                    .unwrap();
+
+                self.pending_protocol_fee = Some((fee, effective_from));
+
+                Runtime::emit_event(ProtocolFeeScheduled {
+                    fee,
+                    effective_from,
+                });
+            }
+        }
+
+        /// The protocol fee rate currently charged, taking into account a scheduled increase whose timelock has passed.
+        pub fn get_protocol_fee(&self) -> Decimal {
+            match self.pending_protocol_fee {
+                Some((fee, effective_from))
+                    if Clock::current_time_rounded_to_seconds() >= effective_from =>
+                {
+                    fee
+                }
+                _ => self.protocol_fee,
+            }
+        }
+
+        pub fn get_pending_protocol_fee(&self) -> Option<(Decimal, Instant)> {
+            self.pending_protocol_fee
+        }
+
+        /// Outposts send the protocol fee they take from each purchase here.
+        pub fn deposit_protocol_fee(&mut self, fee: Bucket) {
+            let currency = fee.resource_address();
+
+            let vault_exists = self.treasury.get(&currency).is_some();
+
+            if vault_exists {
+                self.treasury.get_mut(&currency).unwrap().put(fee);
+            } else {
+                self.treasury.insert(currency, Vault::with_bucket(fee));
+            }
+        }
+
+        pub fn withdraw_protocol_fees(&mut self, currency: ResourceAddress) -> Bucket {
+            let fees = self
+                .treasury
+                .get_mut(&currency)
+                .expect("[withdraw_protocol_fees] No fees collected in this currency")
+                .take_all();
+
+            Runtime::emit_event(ProtocolFeesWithdrawn {
+                currency,
+                amount: fees.amount(),
+            });
+
+            fees
+        }
+
+        /// Checked by Outposts before every listing and purchase.
+        pub fn is_trading_paused(&self, collection: ResourceAddress) -> bool {
+            self.paused || self.paused_collections.get(&collection).is_some()
+        }
+
         pub fn fetch_virt_badge(&mut self) -> ResourceAddress {
             self.emitter_trader_badge.address()
         }
@@ -330,5 +1016,140 @@ mod openhub {
         pub fn fetch_royal_nft_depositer_badge(&mut self) -> ResourceAddress {
             self.royal_nft_depositer_badge.address()
         }
+
+        /// The price oracle recording the sales of every Outpost of this hub.
+        pub fn get_price_oracle(&self) -> ComponentAddress {
+            self.price_oracle.address()
+        }
+
+        /// The central event component every Outpost of this hub emits through, which also keeps the collection statistics.
+        pub fn get_event_component(&self) -> ComponentAddress {
+            self.event_manager.address()
+        }
+
+        // utility methods
+
+        fn assert_account_owner(account: &Global<Account>) {
+            // Getting the owner role of the account.
+            let owner_role = account.get_owner_role();
+
+            // Assert against it - passing means the caller is the owner of the account.
+            Runtime::assert_access_rule(owner_role.rule);
+        }
+
+        /// Panics unless the Outpost is registered and linked to the account, returning the key that currently controls it.
+        fn assert_linked(
+            &self,
+            account: ComponentAddress,
+            outpost: ComponentAddress,
+            method: &str,
+        ) -> NonFungibleGlobalId {
+            let (linked_account, key) = self
+                .registered_outposts
+                .get(&outpost)
+                .map(|owner| owner.clone())
+                .unwrap_or_else(|| panic!("[{}] Outpost is not registered", method));
+
+            assert!(
+                linked_account == account,
+                "[{}] Outpost is not linked to this account",
+                method
+            );
+
+            key
+        }
+
+        fn link_outpost(&self, account: ComponentAddress, outpost: ComponentAddress) {
+            let mut outposts = self.get_outposts(account);
+
+            outposts.push(outpost);
+
+            self.registered_accounts.insert(account, outposts);
+        }
+
+        fn unlink_outpost(&self, account: ComponentAddress, outpost: ComponentAddress) {
+            let mut outposts = self.get_outposts(account);
+
+            outposts.retain(|linked| *linked != outpost);
+
+            self.registered_accounts.insert(account, outposts);
+        }
+
+        /// A proof of the hub's emitter badge, which the event component checks before emitting.
+        fn emitter_proof(&self) -> Proof {
+            self.emitter_badge
+                .create_proof_of_non_fungibles(&self.emitter_badge.non_fungible_local_ids(1))
+                .into()
+        }
+
+        fn remove_collection_flag(&mut self, collection: ResourceAddress, flag: CollectionFlag) {
+            self.flagged_collections.remove(&collection);
+            self.flagged_index.retain(|flagged| *flagged != collection);
+
+            Runtime::emit_event(CollectionFlagCleared {
+                collection,
+                appealed: flag.appealed_at.is_some(),
+            });
+        }
+
+        /// Each Outpost has its own dapp definition account which claims the Outpost component once it exists.
+        fn create_dapp_definition() -> Global<Account> {
+            let dapp_def_account =
+                Blueprint::<Account>::create_advanced(OwnerRole::Updatable(rule!(allow_all)), None); // will reset owner role after dapp def metadata has been set
+            dapp_def_account.set_metadata("account_type", String::from("dapp definition"));
+            dapp_def_account.set_metadata("name", "Outpost".to_string());
+            dapp_def_account.set_metadata(
+                "description",
+                "An extension of your account for managing your NFTs across Radix".to_string(),
+            );
+            dapp_def_account.set_metadata("info_url", Url::of("https://www.outpost.trade/"));
+            dapp_def_account.set_metadata(
+                "icon_url",
+                Url::of("https://www.outpost.trade/img/outpost_symbol.png"),
+            );
+
+            dapp_def_account
+        }
+
+        /// Instantiates an OpenTrader from the current Outpost package, claims it on its dapp definition and hands the dapp
+        /// definition over to the hub admin.
+        fn deploy_outpost(
+            &self,
+            auth_key: NonFungibleGlobalId,
+            my_account: Global<Account>,
+            emitter_badge: Bucket,
+            depositer_badge: Bucket,
+            dapp_def_account: &Global<Account>,
+            transient_token: FungibleBucket,
+        ) -> Global<OpenTrader> {
+            let dapp_def_address = GlobalAddress::from(dapp_def_account.address());
+
+            let output = ScryptoVmV1Api::blueprint_call(
+                self.outpost_package,
+                "OpenTrader",
+                "create_trader",
+                scrypto_args!(
+                    auth_key,
+                    my_account,
+                    emitter_badge,
+                    depositer_badge,
+                    self.event_manager,
+                    dapp_def_address,
+                    self.account_locker.clone(),
+                    transient_token,
+                    self.component_address
+                ),
+            );
+
+            let outpost: Global<OpenTrader> = scrypto_decode(&output).unwrap();
+
+            dapp_def_account.set_metadata(
+                "claimed_entities",
+                vec![GlobalAddress::from(outpost.address())],
+            );
+            dapp_def_account.set_owner_role(rule!(require(self.admin)));
+
+            outpost
+        }
     }
 }
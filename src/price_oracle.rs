@@ -0,0 +1,356 @@
+use crate::outpost_account::opentrader::OpenTrader;
+use scrypto::prelude::*;
+
+/// This blueprint records the sale prices of NFTs traded through Outposts and exposes a time-weighted average price and
+/// median price per collection and currency, so that lending, rental and insurance components can price NFTs from real trades.
+/// Sales are reported by the central event component only - it is the one place every Outpost purchase passes through.
+///
+/// To make the oracle harder to manipulate, sales between accounts with the same owner are excluded as wash trades, as are
+/// resales of the same NFT within a cooldown period (which would otherwise allow an NFT to be ping-ponged between accounts).
+/// The owner check only catches a sale to the seller's own account or between accounts secured by the same owner rule - a
+/// virtual account is owned by its own key, so two virtual accounts never match and the resale cooldown is what limits
+/// wash trading between them.
+///
+/// Only the latest observations of each collection and currency are kept (100 by default). A collection that trades more
+/// often than that within the window is priced from a shorter window - the one its kept observations cover.
+///
+/// Every Outpost purchase waits on the report, so the reporter is fixed at instantiation and can't be changed - the event
+/// component checks it before linking an oracle, and a linked oracle can't start rejecting its reports later on.
+
+/// The window used by twap and median when none is provided, for the oracle every OpenHub starts with
+pub const DEFAULT_WINDOW_SECONDS: i64 = 7 * 24 * 60 * 60;
+
+/// The resale cooldown of the oracle every OpenHub starts with
+pub const DEFAULT_RESALE_COOLDOWN_SECONDS: i64 = 24 * 60 * 60;
+
+/// The number of observations kept per collection and currency by a new oracle
+pub const DEFAULT_MAX_OBSERVATIONS: usize = 100;
+
+#[derive(ScryptoSbor, Clone, Debug)]
+pub struct Observation {
+    pub price: Decimal,
+    pub timestamp: Instant,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+struct SaleObserved {
+    collection: ResourceAddress,
+    currency: ResourceAddress,
+    nft_id: NonFungibleGlobalId,
+    price: Decimal,
+}
+
+#[derive(ScryptoSbor, ScryptoEvent)]
+struct SaleExcluded {
+    collection: ResourceAddress,
+    currency: ResourceAddress,
+    nft_id: NonFungibleGlobalId,
+    price: Decimal,
+    reason: String,
+}
+
+#[blueprint]
+#[events(SaleObserved, SaleExcluded)]
+mod price_oracle {
+
+    enable_method_auth! {
+    roles {
+        admin => updatable_by: [];
+        reporter => updatable_by: [];
+    },
+    methods {
+        record_sale => restrict_to: [reporter];
+        set_default_window => restrict_to: [admin];
+        set_resale_cooldown => restrict_to: [admin];
+        set_max_observations => restrict_to: [admin];
+        twap => PUBLIC;
+        median => PUBLIC;
+        observations => PUBLIC;
+        reporter => PUBLIC;
+    }
+    }
+
+    struct PriceOracle {
+        /// The event component that reports sales
+        reporter: ComponentAddress,
+        /// Recorded sales keyed by (collection, currency), oldest first
+        observations: KeyValueStore<(ResourceAddress, ResourceAddress), Vec<Observation>>,
+        /// The last time each NFT was sold, used to enforce the resale cooldown
+        last_sale: KeyValueStore<NonFungibleGlobalId, Instant>,
+        /// The window used by twap and median when none is provided
+        default_window_seconds: i64,
+        /// Sales of the same NFT within this many seconds of its previous sale are excluded
+        resale_cooldown_seconds: i64,
+        /// The maximum number of observations kept per collection and currency - the oldest are dropped first, even if they
+        /// are still within the window
+        max_observations: usize,
+    }
+
+    impl PriceOracle {
+        /// Instantiates a price oracle that only accepts sales reported by the given event component.
+        pub fn start_price_oracle(
+            event_component: ComponentAddress,
+            admin: ResourceAddress,
+            default_window_seconds: i64,
+            resale_cooldown_seconds: i64,
+        ) -> Global<PriceOracle> {
+            assert!(
+                default_window_seconds > 0,
+                "[price_oracle] Window must be greater than zero"
+            );
+
+            assert!(
+                resale_cooldown_seconds >= 0,
+                "[price_oracle] Resale cooldown can't be negative"
+            );
+
+            Self {
+                reporter: event_component,
+                observations: KeyValueStore::new(),
+                last_sale: KeyValueStore::new(),
+                default_window_seconds,
+                resale_cooldown_seconds,
+                max_observations: DEFAULT_MAX_OBSERVATIONS,
+            }
+            .instantiate()
+            .prepare_to_globalize(OwnerRole::None)
+            .roles(roles!(
+                admin => rule!(require(admin));
+                reporter => rule!(require(global_caller(event_component)));
+            ))
+            .globalize()
+        }
+
+        /// Records a sale reported by the event component. The seller is the Outpost the NFT was sold from - its linked
+        /// account is compared against the account the NFT was bought for to exclude wash trades.
+        pub fn record_sale(
+            &mut self,
+            nft_id: NonFungibleGlobalId,
+            currency: ResourceAddress,
+            price: Decimal,
+            outpost_component: ComponentAddress,
+            recipient: ComponentAddress,
+        ) {
+            let collection = nft_id.resource_address();
+            let now = Clock::current_time_rounded_to_seconds();
+
+            if let Some(reason) =
+                self.exclusion_reason(&nft_id, outpost_component, recipient, now)
+            {
+                Runtime::emit_event(SaleExcluded {
+                    collection,
+                    currency,
+                    nft_id,
+                    price,
+                    reason,
+                });
+                return;
+            }
+
+            self.last_sale.insert(nft_id.clone(), now);
+
+            let mut observations = self
+                .observations
+                .get(&(collection, currency))
+                .map(|observations| observations.clone())
+                .unwrap_or_default();
+
+            observations.push(Observation {
+                price,
+                timestamp: now,
+            });
+
+            if observations.len() > self.max_observations {
+                let excess = observations.len() - self.max_observations;
+                observations.drain(0..excess);
+            }
+
+            self.observations
+                .insert((collection, currency), observations);
+
+            Runtime::emit_event(SaleObserved {
+                collection,
+                currency,
+                nft_id,
+                price,
+            });
+        }
+
+        /// The time-weighted average sale price over the window. Each price is weighted by how long it stood as the
+        /// latest sale within the window - including the last sale before the window started, which sets the price at its start.
+        pub fn twap(
+            &self,
+            collection: ResourceAddress,
+            currency: ResourceAddress,
+            window_seconds: Option<i64>,
+        ) -> Option<Decimal> {
+            let observations = self.observations.get(&(collection, currency))?;
+
+            let now = Clock::current_time_rounded_to_seconds();
+            let window_start = now.seconds_since_unix_epoch
+                - window_seconds.unwrap_or(self.default_window_seconds);
+
+            let mut weighted_sum = dec!(0);
+            let mut total_weight: i64 = 0;
+
+            for (index, observation) in observations.iter().enumerate() {
+                let end = observations
+                    .get(index + 1)
+                    .map_or(now.seconds_since_unix_epoch, |next| {
+                        next.timestamp.seconds_since_unix_epoch
+                    });
+
+                let start = observation.timestamp.seconds_since_unix_epoch.max(window_start);
+
+                if end <= start {
+                    continue;
+                }
+
+                let weight = end - start;
+
+                weighted_sum = weighted_sum
+                    .checked_add(observation.price.checked_mul(weight).unwrap())
+                    .unwrap();
+                total_weight += weight;
+            }
+
+            if total_weight == 0 {
+                // every observation falls in the current second - fall back to the latest price
+                return observations
+                    .last()
+                    .filter(|observation| observation.timestamp.seconds_since_unix_epoch >= window_start)
+                    .map(|observation| observation.price);
+            }
+
+            weighted_sum.checked_div(total_weight)
+        }
+
+        /// The median sale price of the sales within the window.
+        pub fn median(
+            &self,
+            collection: ResourceAddress,
+            currency: ResourceAddress,
+            window_seconds: Option<i64>,
+        ) -> Option<Decimal> {
+            let mut prices: Vec<Decimal> = self
+                .observations_in_window(collection, currency, window_seconds)
+                .into_iter()
+                .map(|observation| observation.price)
+                .collect();
+
+            if prices.is_empty() {
+                return None;
+            }
+
+            prices.sort();
+
+            let middle = prices.len() / 2;
+
+            if prices.len() % 2 == 0 {
+                prices[middle - 1]
+                    .checked_add(prices[middle])
+                    .unwrap()
+                    .checked_div(2)
+            } else {
+                Some(prices[middle])
+            }
+        }
+
+        /// The event component this oracle accepts sales from.
+        pub fn reporter(&self) -> ComponentAddress {
+            self.reporter
+        }
+
+        pub fn observations(
+            &self,
+            collection: ResourceAddress,
+            currency: ResourceAddress,
+            window_seconds: Option<i64>,
+        ) -> Vec<Observation> {
+            self.observations_in_window(collection, currency, window_seconds)
+        }
+
+        pub fn set_default_window(&mut self, window_seconds: i64) {
+            assert!(
+                window_seconds > 0,
+                "[price_oracle] Window must be greater than zero"
+            );
+            self.default_window_seconds = window_seconds;
+        }
+
+        pub fn set_resale_cooldown(&mut self, cooldown_seconds: i64) {
+            assert!(
+                cooldown_seconds >= 0,
+                "[price_oracle] Resale cooldown can't be negative"
+            );
+            self.resale_cooldown_seconds = cooldown_seconds;
+        }
+
+        /// A busy collection can fill every kept observation within the window, so raise this for collections that trade more
+        /// often than this many times per window.
+        pub fn set_max_observations(&mut self, max_observations: usize) {
+            assert!(
+                max_observations > 0,
+                "[price_oracle] Must keep at least one observation"
+            );
+            self.max_observations = max_observations;
+        }
+
+        // utility methods
+
+        fn observations_in_window(
+            &self,
+            collection: ResourceAddress,
+            currency: ResourceAddress,
+            window_seconds: Option<i64>,
+        ) -> Vec<Observation> {
+            let window_start = Clock::current_time_rounded_to_seconds().seconds_since_unix_epoch
+                - window_seconds.unwrap_or(self.default_window_seconds);
+
+            self.observations
+                .get(&(collection, currency))
+                .map(|observations| {
+                    observations
+                        .iter()
+                        .filter(|observation| {
+                            observation.timestamp.seconds_since_unix_epoch >= window_start
+                        })
+                        .cloned()
+                        .collect()
+                })
+                .unwrap_or_default()
+        }
+
+        fn exclusion_reason(
+            &self,
+            nft_id: &NonFungibleGlobalId,
+            outpost_component: ComponentAddress,
+            recipient: ComponentAddress,
+            now: Instant,
+        ) -> Option<String> {
+            if let Some(last_sale) = self.last_sale.get(nft_id) {
+                if now.seconds_since_unix_epoch - last_sale.seconds_since_unix_epoch
+                    < self.resale_cooldown_seconds
+                {
+                    return Some("Resold within the cooldown period".to_string());
+                }
+            }
+
+            let outpost: Global<OpenTrader> = Global::from(outpost_component);
+            let seller = outpost.linked_account();
+
+            if seller == recipient {
+                return Some("Seller and buyer are the same account".to_string());
+            }
+
+            let seller_account: Global<Account> = Global::from(seller);
+            let recipient_account: Global<Account> = Global::from(recipient);
+
+            if seller_account.get_owner_role().rule == recipient_account.get_owner_role().rule {
+                return Some("Seller and buyer accounts have the same owner".to_string());
+            }
+
+            None
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use scrypto_test::prelude::*;
+mod common;
+mod creator_manifests;
+mod marketplace_manifests;
+mod misc_manifests;
+mod scenario_manifests;
+mod trader_manifests;
+use common::*;
+use creator_manifests::*;
+use marketplace_manifests::*;
+use misc_manifests::*;
+use scenario_manifests::*;
+use trader_manifests::*;
+
+#[test]
+fn blocked_marketplace_and_recipient_cannot_purchase() {
+    let mut fixture = setup_trading();
+
+    let nft_address = mint_nfts(&mut fixture, 1).nft_address;
+
+    let TradingFixture {
+        mut test_runner,
+        user,
+        trader_key_resource,
+        trader_key_local,
+        trader_component,
+        marketplace_component,
+        marketplace_key,
+        ..
+    } = fixture;
+
+    let buyer = make_user(&mut test_runner, Some("buyer"));
+
+    list(
+        &mut test_runner,
+        &user,
+        trader_component.clone(),
+        trader_key_resource.clone(),
+        trader_key_local.clone(),
+        nft_address.clone(),
+        NonFungibleLocalId::integer(0),
+        dec!(100),
+        None,
+        vec![marketplace_key.clone()],
+    );
+
+    let orders = vec![(
+        trader_component.clone(),
+        create_global_id(nft_address.clone(), 0),
+        dec!(100),
+    )];
+
+    // a blocked marketplace can't purchase, even though the listing permits it
+    block_buyer(
+        &mut test_runner,
+        &user,
+        trader_component,
+        trader_key_resource,
+        trader_key_local.clone(),
+        BlockedBuyer::Permission(marketplace_key),
+        true,
+    );
+
+    assert!(is_buyer_blocked(
+        &mut test_runner,
+        &user,
+        trader_component,
+        BlockedBuyer::Permission(marketplace_key)
+    ));
+
+    try_purchase_multi_listing(
+        &mut test_runner,
+        &buyer,
+        marketplace_component,
+        orders.clone(),
+        dec!(100),
+    )
+    .expect_commit_failure();
+
+    block_buyer(
+        &mut test_runner,
+        &user,
+        trader_component,
+        trader_key_resource,
+        trader_key_local.clone(),
+        BlockedBuyer::Permission(marketplace_key),
+        false,
+    );
+
+    // the recipient is checked on non-royal purchases as well
+    block_buyer(
+        &mut test_runner,
+        &user,
+        trader_component,
+        trader_key_resource,
+        trader_key_local.clone(),
+        BlockedBuyer::Recipient(buyer.account),
+        true,
+    );
+
+    try_purchase_multi_listing(
+        &mut test_runner,
+        &buyer,
+        marketplace_component,
+        orders.clone(),
+        dec!(100),
+    )
+    .expect_commit_failure();
+
+    block_buyer(
+        &mut test_runner,
+        &user,
+        trader_component,
+        trader_key_resource,
+        trader_key_local,
+        BlockedBuyer::Recipient(buyer.account),
+        false,
+    );
+
+    assert!(!is_buyer_blocked(
+        &mut test_runner,
+        &user,
+        trader_component,
+        BlockedBuyer::Recipient(buyer.account)
+    ));
+
+    try_purchase_multi_listing(
+        &mut test_runner,
+        &buyer,
+        marketplace_component,
+        orders,
+        dec!(100),
+    )
+    .expect_commit_success();
+
+    assert_eq!(
+        test_runner.get_component_balance(buyer.account, nft_address),
+        dec!(1)
+    );
+}
@@ -0,0 +1,110 @@
+use outpost::outpost_event::{ListingPurchased, ListingPurchasedPayload};
+use scrypto_test::prelude::*;
+mod common;
+mod creator_manifests;
+mod marketplace_manifests;
+mod misc_manifests;
+mod scenario_manifests;
+mod trader_manifests;
+use common::*;
+use creator_manifests::*;
+use marketplace_manifests::*;
+use misc_manifests::*;
+use scenario_manifests::*;
+use trader_manifests::*;
+
+fn purchased_events(
+    test_runner: &DefaultLedgerSimulator,
+    receipt: &TransactionReceipt,
+) -> Vec<ListingPurchased> {
+    receipt
+        .expect_commit_success()
+        .application_events
+        .iter()
+        .filter(|(event_type, _)| test_runner.event_name(event_type) == "ListingPurchased")
+        .map(|(_, data)| scrypto_decode::<ListingPurchased>(data).unwrap())
+        .collect()
+}
+
+#[test]
+fn bulk_settlement_is_split_by_listing_price() {
+    let mut fixture = setup_trading();
+
+    let nft_address = mint_royalty_nfts(&mut fixture, 3).nft_address;
+
+    let TradingFixture {
+        mut test_runner,
+        user,
+        trader_key_resource,
+        trader_key_local,
+        trader_component,
+        marketplace_component,
+        marketplace_key,
+        ..
+    } = fixture;
+
+    let buyer = make_user(&mut test_runner, Some("buyer"));
+
+    let cheap = create_global_id(nft_address.clone(), 0);
+    let expensive = create_global_id(nft_address.clone(), 1);
+
+    royal_multi_list(
+        &mut test_runner,
+        &user,
+        vec![(cheap.clone(), dec!(100)), (expensive.clone(), dec!(300))],
+        trader_component.clone(),
+        trader_key_resource,
+        trader_key_local,
+        nft_address.clone(),
+        NonFungibleLocalId::integer(0),
+        None,
+        vec![marketplace_key.clone()],
+    );
+
+    let transient_token_address =
+        get_transient_token_address(&mut test_runner, &user, trader_component.clone());
+
+    let receipt = try_purchase_multi_royalty_nft(
+        &mut test_runner,
+        &buyer,
+        vec![
+            (trader_component, cheap.clone(), dec!(100)),
+            (trader_component, expensive.clone(), dec!(300)),
+        ],
+        dec!(400),
+        marketplace_component,
+        trader_component,
+        None,
+        transient_token_address,
+    );
+
+    let events = purchased_events(&test_runner, &receipt);
+
+    assert_eq!(events.len(), 2);
+
+    // 10% royalty and a 2% marketplace fee on each listing's own price, no protocol fee is configured
+    let expected = [
+        (cheap, dec!(10), dec!(2), dec!(88)),
+        (expensive, dec!(30), dec!(6), dec!(264)),
+    ];
+
+    for (event, (expected_id, royalty, fee, proceeds)) in events.into_iter().zip(expected) {
+        match event.payload {
+            ListingPurchasedPayload::V4 {
+                nft_id,
+                marketplace_fee,
+                royalty_amount,
+                protocol_fee,
+                seller_proceeds,
+                ..
+            } => {
+                assert_eq!(nft_id, expected_id);
+                assert_eq!(royalty_amount, royalty);
+                assert_eq!(marketplace_fee, fee);
+                assert_eq!(protocol_fee, dec!(0));
+                assert_eq!(seller_proceeds, proceeds);
+            }
+            _ => panic!("Expected a version 4 payload"),
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use scrypto::runtime::Clock;
+use scrypto_test::prelude::*;
+mod common;
+mod creator_manifests;
+mod marketplace_manifests;
+mod misc_manifests;
+mod scenario_manifests;
+mod trader_manifests;
+use common::*;
+use creator_manifests::*;
+use marketplace_manifests::*;
+use misc_manifests::*;
+use scenario_manifests::*;
+use trader_manifests::*;
+
+#[test]
+fn list_then_close_outpost() {
+    let (
+        TradingFixture {
+            mut test_runner,
+            user,
+            trader_key_resource,
+            trader_key_local,
+            trader_component,
+            ..
+        },
+        TestCollection { nft_address, .. },
+    ) = setup_listed_royalty_nft();
+
+    close_outpost(
+        &mut test_runner,
+        &user,
+        trader_component,
+        trader_key_resource,
+        trader_key_local,
+        None,
+    );
+
+    let balance = test_runner.get_component_balance(user.account, nft_address);
+
+    assert_eq!(balance, dec!(3));
+}
+
+#[test]
+fn close_outpost_and_migrate_listings() {
+    let (
+        TradingFixture {
+            mut test_runner,
+            user,
+            open_hub_component,
+            trader_key_resource,
+            trader_key_local,
+            trader_component,
+            marketplace_component,
+            ..
+        },
+        TestCollection { nft_address, .. },
+    ) = setup_listed_royalty_nft();
+
+    let other_user = make_user(&mut test_runner, Some("user2"));
+
+    let (_new_key_resource, _new_key_local, new_trader_component) =
+        create_outpost(&mut test_runner, &user, open_hub_component);
+
+    let (_other_key_resource, _other_key_local, other_trader_component) =
+        create_outpost(&mut test_runner, &other_user, open_hub_component);
+
+    // an Outpost registered to another account can't receive the listings
+    try_close_outpost(
+        &mut test_runner,
+        &user,
+        trader_component,
+        trader_key_resource,
+        trader_key_local.clone(),
+        Some(other_trader_component),
+    )
+    .expect_commit_failure();
+
+    // nor can a component that isn't an Outpost
+    try_close_outpost(
+        &mut test_runner,
+        &user,
+        trader_component,
+        trader_key_resource,
+        trader_key_local.clone(),
+        Some(marketplace_component),
+    )
+    .expect_commit_failure();
+
+    close_outpost(
+        &mut test_runner,
+        &user,
+        trader_component,
+        trader_key_resource,
+        trader_key_local,
+        Some(new_trader_component),
+    );
+
+    assert_eq!(
+        test_runner.get_component_balance(user.account, nft_address),
+        dec!(2)
+    );
+
+    // the listing was recreated on the new Outpost and can be purchased from it
+    let transient_token_address =
+        get_transient_token_address(&mut test_runner, &user, new_trader_component);
+
+    purchase_royalty_nft(
+        &mut test_runner,
+        &user,
+        marketplace_component,
+        new_trader_component,
+        create_global_id(nft_address.clone(), 0),
+        dec!(100),
+        None,
+        transient_token_address,
+    );
+
+    assert_eq!(
+        test_runner.get_component_balance(user.account, nft_address),
+        dec!(3)
+    );
+}
@@ -0,0 +1,349 @@
+use scrypto_test::prelude::*;
+mod common;
+mod creator_manifests;
+mod marketplace_manifests;
+mod misc_manifests;
+mod scenario_manifests;
+mod trader_manifests;
+use common::*;
+use creator_manifests::*;
+use marketplace_manifests::*;
+use misc_manifests::*;
+use scenario_manifests::*;
+use trader_manifests::*;
+
+#[test]
+fn blocked_collections_cannot_be_listed_until_cleared() {
+    let mut fixture = setup_trading();
+
+    let TestCollection {
+        creator_key,
+        nft_address,
+        ..
+    } = mint_royalty_nfts(&mut fixture, 3);
+
+    let TradingFixture {
+        mut test_runner,
+        user,
+        open_hub_component,
+        hub_admin,
+        trader_key_resource,
+        trader_key_local,
+        trader_component,
+        marketplace_key,
+        ..
+    } = fixture;
+
+    flag_collection(
+        &mut test_runner,
+        &user,
+        open_hub_component,
+        hub_admin,
+        nft_address,
+        FlagReason::Counterfeit,
+        FlagSeverity::Block,
+    );
+
+    assert!(is_collection_blocked(
+        &mut test_runner,
+        &user,
+        open_hub_component,
+        nft_address
+    ));
+
+    // listing a blocked collection must fail
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(
+            user.account,
+            "create_proof_of_non_fungibles",
+            manifest_args!(trader_key_resource, indexset![trader_key_local.clone()]),
+        )
+        .call_method(
+            user.account,
+            "withdraw_non_fungibles",
+            manifest_args!(nft_address, indexset![NonFungibleLocalId::integer(0)]),
+        )
+        .take_all_from_worktop(nft_address, "listing")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(
+                trader_component,
+                "royal_list",
+                manifest_args!(
+                    lookup.bucket("listing"),
+                    dec!(100),
+                    XRD,
+                    vec![marketplace_key.clone()],
+                    None::<Instant>,
+                    false,
+                ),
+            )
+        })
+        .call_method(
+            user.account,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+
+    let receipt = test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    );
+
+    receipt.expect_commit_failure();
+
+    // rental listings are refused as well
+    let rental_badge = test_runner.create_fungible_resource(dec!(1), 0, user.account);
+
+    try_list_rental(
+        &mut test_runner,
+        &user,
+        trader_component,
+        trader_key_resource,
+        trader_key_local.clone(),
+        nft_address,
+        NonFungibleLocalId::integer(1),
+        dec!(10),
+        60,
+        vec![rental_badge],
+    )
+    .expect_commit_failure();
+
+    // only the collection's owner role can appeal
+    appeal_collection_flag(
+        &mut test_runner,
+        &user,
+        open_hub_component,
+        nft_address,
+        hub_admin,
+    )
+    .expect_commit_failure();
+
+    appeal_collection_flag(
+        &mut test_runner,
+        &user,
+        open_hub_component,
+        nft_address,
+        creator_key,
+    )
+    .expect_commit_success();
+
+    appeal_collection_flag(
+        &mut test_runner,
+        &user,
+        open_hub_component,
+        nft_address,
+        creator_key,
+    )
+    .expect_commit_failure();
+
+    // an appeal is only a request - the flag stays until it is reviewed
+    assert!(is_collection_blocked(
+        &mut test_runner,
+        &user,
+        open_hub_component,
+        nft_address
+    ));
+
+    try_clear_collection_flag(
+        &mut test_runner,
+        &user,
+        open_hub_component,
+        creator_key,
+        nft_address,
+    )
+    .expect_commit_failure();
+
+    let moderator_badge = test_runner.create_fungible_resource(dec!(1), 0, user.account);
+
+    try_clear_collection_flag(
+        &mut test_runner,
+        &user,
+        open_hub_component,
+        moderator_badge,
+        nft_address,
+    )
+    .expect_commit_failure();
+
+    set_hub_moderator(
+        &mut test_runner,
+        &user,
+        open_hub_component,
+        hub_admin,
+        moderator_badge,
+    );
+
+    try_clear_collection_flag(
+        &mut test_runner,
+        &user,
+        open_hub_component,
+        moderator_badge,
+        nft_address,
+    )
+    .expect_commit_success();
+
+    assert!(!is_collection_blocked(
+        &mut test_runner,
+        &user,
+        open_hub_component,
+        nft_address
+    ));
+
+    list_royalty_nft(
+        &mut test_runner,
+        &user,
+        trader_component.clone(),
+        trader_key_resource.clone(),
+        trader_key_local.clone(),
+        nft_address.clone(),
+        NonFungibleLocalId::integer(0),
+        dec!(100),
+        None,
+        vec![marketplace_key.clone()],
+    );
+}
+
+#[test]
+fn appeals_are_resolved_by_moderators() {
+    let mut fixture = setup_trading();
+
+    let TestCollection {
+        creator_key,
+        nft_address,
+        ..
+    } = mint_royalty_nfts(&mut fixture, 1);
+
+    let TradingFixture {
+        mut test_runner,
+        user,
+        open_hub_component,
+        hub_admin,
+        ..
+    } = fixture;
+
+    flag_collection(
+        &mut test_runner,
+        &user,
+        open_hub_component,
+        hub_admin,
+        nft_address,
+        FlagReason::Counterfeit,
+        FlagSeverity::Block,
+    );
+
+    // there's nothing to resolve until the creator appeals
+    try_resolve_appeal(
+        &mut test_runner,
+        &user,
+        open_hub_component,
+        hub_admin,
+        nft_address,
+        true,
+    )
+    .expect_commit_failure();
+
+    appeal_collection_flag(
+        &mut test_runner,
+        &user,
+        open_hub_component,
+        nft_address,
+        creator_key,
+    )
+    .expect_commit_success();
+
+    // the creator can't resolve their own appeal
+    try_resolve_appeal(
+        &mut test_runner,
+        &user,
+        open_hub_component,
+        creator_key,
+        nft_address,
+        true,
+    )
+    .expect_commit_failure();
+
+    // a rejected appeal keeps the flag and can't be made again
+    try_resolve_appeal(
+        &mut test_runner,
+        &user,
+        open_hub_component,
+        hub_admin,
+        nft_address,
+        false,
+    )
+    .expect_commit_success();
+
+    assert!(is_collection_blocked(
+        &mut test_runner,
+        &user,
+        open_hub_component,
+        nft_address
+    ));
+
+    appeal_collection_flag(
+        &mut test_runner,
+        &user,
+        open_hub_component,
+        nft_address,
+        creator_key,
+    )
+    .expect_commit_failure();
+
+    try_resolve_appeal(
+        &mut test_runner,
+        &user,
+        open_hub_component,
+        hub_admin,
+        nft_address,
+        true,
+    )
+    .expect_commit_failure();
+
+    // re-flagging starts a fresh flag that can be appealed again, and an upheld appeal clears it
+    flag_collection(
+        &mut test_runner,
+        &user,
+        open_hub_component,
+        hub_admin,
+        nft_address,
+        FlagReason::Counterfeit,
+        FlagSeverity::Block,
+    );
+
+    appeal_collection_flag(
+        &mut test_runner,
+        &user,
+        open_hub_component,
+        nft_address,
+        creator_key,
+    )
+    .expect_commit_success();
+
+    let moderator_badge = test_runner.create_fungible_resource(dec!(1), 0, user.account);
+
+    set_hub_moderator(
+        &mut test_runner,
+        &user,
+        open_hub_component,
+        hub_admin,
+        moderator_badge,
+    );
+
+    try_resolve_appeal(
+        &mut test_runner,
+        &user,
+        open_hub_component,
+        moderator_badge,
+        nft_address,
+        true,
+    )
+    .expect_commit_success();
+
+    assert!(!is_collection_blocked(
+        &mut test_runner,
+        &user,
+        open_hub_component,
+        nft_address
+    ));
+}
@@ -0,0 +1,202 @@
+use outpost::outpost_event::{FLOOR_BOOK_CAPACITY, FLOOR_DEPTH};
+use scrypto_test::prelude::*;
+mod common;
+mod creator_manifests;
+mod marketplace_manifests;
+mod misc_manifests;
+mod scenario_manifests;
+mod trader_manifests;
+use common::*;
+use creator_manifests::*;
+use marketplace_manifests::*;
+use misc_manifests::*;
+use scenario_manifests::*;
+use trader_manifests::*;
+
+#[test]
+fn floor_skips_private_listings_and_refills_after_sales() {
+    let public_listings = FLOOR_DEPTH as u64 + 1;
+
+    let mut fixture = setup_trading();
+
+    let nft_address = mint_nfts(&mut fixture, public_listings + 1).nft_address;
+
+    let TradingFixture {
+        mut test_runner,
+        user,
+        open_hub_component,
+        trader_key_resource,
+        trader_key_local,
+        trader_component,
+        marketplace_component,
+        marketplace_key,
+        ..
+    } = fixture;
+
+    let buyer = make_user(&mut test_runner, Some("buyer"));
+
+    let event_component = get_event_component(&mut test_runner, &user, open_hub_component);
+
+    // a private deal badge carries no marketplace fee metadata
+    let private_badge = test_runner.create_fungible_resource(dec!(1), 0, buyer.account);
+
+    list(
+        &mut test_runner,
+        &user,
+        trader_component.clone(),
+        trader_key_resource.clone(),
+        trader_key_local.clone(),
+        nft_address.clone(),
+        NonFungibleLocalId::integer(0),
+        dec!(50),
+        None,
+        vec![private_badge],
+    );
+
+    assert_eq!(
+        get_floor_price(&mut test_runner, &user, event_component, nft_address, XRD),
+        None
+    );
+
+    // one more public listing than the floor reports, priced 100, 101, ...
+    for id in 1..=public_listings {
+        list(
+            &mut test_runner,
+            &user,
+            trader_component.clone(),
+            trader_key_resource.clone(),
+            trader_key_local.clone(),
+            nft_address.clone(),
+            NonFungibleLocalId::integer(id),
+            dec!(99) + Decimal::from(id),
+            None,
+            vec![marketplace_key.clone()],
+        );
+    }
+
+    assert_eq!(
+        get_floor_price(&mut test_runner, &user, event_component, nft_address, XRD),
+        Some(dec!(100))
+    );
+
+    // buy every listing the floor reported, leaving only the most expensive public one
+    let orders: Vec<(ComponentAddress, NonFungibleGlobalId, Decimal)> = (1..public_listings)
+        .map(|id| {
+            (
+                trader_component.clone(),
+                create_global_id(nft_address.clone(), id),
+                dec!(99) + Decimal::from(id),
+            )
+        })
+        .collect();
+
+    let total = orders
+        .iter()
+        .fold(dec!(0), |acc, (_, _, price)| acc + *price);
+
+    purchase_multi_listing(
+        &mut test_runner,
+        &buyer,
+        marketplace_component,
+        orders,
+        total,
+        false,
+    );
+
+    assert_eq!(
+        get_floor_price(&mut test_runner, &user, event_component, nft_address, XRD),
+        Some(dec!(99) + Decimal::from(public_listings))
+    );
+}
+
+#[test]
+fn floor_book_is_capped() {
+    let capacity = FLOOR_BOOK_CAPACITY as u64;
+
+    let mut fixture = setup_trading();
+
+    let nft_address = mint_nfts(&mut fixture, capacity + 2).nft_address;
+
+    let TradingFixture {
+        mut test_runner,
+        user,
+        open_hub_component,
+        trader_key_resource,
+        trader_key_local,
+        trader_component,
+        marketplace_component,
+        marketplace_key,
+        ..
+    } = fixture;
+
+    let buyer = make_user(&mut test_runner, Some("buyer"));
+
+    let event_component = get_event_component(&mut test_runner, &user, open_hub_component);
+
+    // fill the book, priced 100, 101, ...
+    for id in 0..capacity {
+        list(
+            &mut test_runner,
+            &user,
+            trader_component,
+            trader_key_resource,
+            trader_key_local.clone(),
+            nft_address,
+            NonFungibleLocalId::integer(id),
+            dec!(100) + Decimal::from(id),
+            None,
+            vec![marketplace_key],
+        );
+    }
+
+    // a cheaper listing still takes the floor, pushing the most expensive one out of the book
+    list(
+        &mut test_runner,
+        &user,
+        trader_component,
+        trader_key_resource,
+        trader_key_local.clone(),
+        nft_address,
+        NonFungibleLocalId::integer(capacity),
+        dec!(90),
+        None,
+        vec![marketplace_key],
+    );
+
+    assert_eq!(
+        get_floor_price(&mut test_runner, &user, event_component, nft_address, XRD),
+        Some(dec!(90))
+    );
+
+    // a listing above everything in the full book is left out, without changing the floor
+    list(
+        &mut test_runner,
+        &user,
+        trader_component,
+        trader_key_resource,
+        trader_key_local,
+        nft_address,
+        NonFungibleLocalId::integer(capacity + 1),
+        dec!(1000),
+        None,
+        vec![marketplace_key],
+    );
+
+    purchase_multi_listing(
+        &mut test_runner,
+        &buyer,
+        marketplace_component,
+        vec![(
+            trader_component,
+            create_global_id(nft_address, capacity),
+            dec!(90),
+        )],
+        dec!(90),
+        false,
+    );
+
+    assert_eq!(
+        get_floor_price(&mut test_runner, &user, event_component, nft_address, XRD),
+        Some(dec!(100))
+    );
+}
@@ -12,6 +12,10 @@ use scrypto_test::{
 };
 // use transaction::prelude::*;
 
+use crate::creator_manifests::*;
+use crate::misc_manifests::*;
+use crate::trader_manifests::*;
+
 #[derive(ScryptoSbor, ManifestSbor, NonFungibleData)]
 pub struct NfData {}
 
@@ -115,6 +119,197 @@ pub fn get_component_nflids(
     nflids
 }
 
+/// A hub with an Outpost, the event listener and a marketplace charging a 2% fee - the starting point of most trading
+/// tests. Destructure the fields a test needs.
+pub struct TradingFixture {
+    pub test_runner: DefaultLedgerSimulator,
+    pub user: User,
+    pub package: PackageAddress,
+    pub open_hub_component: ComponentAddress,
+    pub hub_admin: ResourceAddress,
+    pub depositer_badge: ResourceAddress,
+    pub trader_key_resource: ResourceAddress,
+    pub trader_key_local: NonFungibleLocalId,
+    pub trader_component: ComponentAddress,
+    pub marketplace_component: ComponentAddress,
+    pub marketplace_key: ResourceAddress,
+    pub mint_factory: ComponentAddress,
+}
+
+/// A collection created for a test, with every minted NFT held by the fixture's user.
+pub struct TestCollection {
+    pub component: ComponentAddress,
+    pub creator_key: ResourceAddress,
+    pub nft_address: ResourceAddress,
+}
+
+pub fn setup_trading() -> TradingFixture {
+    let (mut test_runner, user, package) = setup_for_test();
+
+    let (open_hub_component, hub_admin) =
+        instantiate_open_hub_with_admin(&mut test_runner, &user, package);
+
+    let virtual_badge = fetch_virt_badge(&mut test_runner, &user, open_hub_component);
+
+    let depositer_badge = fetch_depositer_badge(&mut test_runner, &user, open_hub_component);
+
+    let (trader_key_resource, trader_key_local, trader_component) =
+        create_outpost(&mut test_runner, &user, open_hub_component);
+
+    create_event_listener(&mut test_runner, &user, package, virtual_badge);
+
+    let (marketplace_component, marketplace_key) =
+        create_marketplace(&mut test_runner, &user, package, dec!(0.02));
+
+    let mint_factory = create_mint_factory(&mut test_runner, &user, package);
+
+    TradingFixture {
+        test_runner,
+        user,
+        package,
+        open_hub_component,
+        hub_admin,
+        depositer_badge,
+        trader_key_resource,
+        trader_key_local,
+        trader_component,
+        marketplace_component,
+        marketplace_key,
+        mint_factory,
+    }
+}
+
+/// Creates a Royalty NFT collection with the default royalty config and buys `amount` NFTs from its mint through the
+/// fixture's marketplace.
+pub fn mint_royalty_nfts(fixture: &mut TradingFixture, amount: u64) -> TestCollection {
+    let royalty_config = defaults_royalty_config(fixture.depositer_badge);
+
+    mint_royalty_nfts_with_config(fixture, royalty_config, amount)
+}
+
+/// As `mint_royalty_nfts`, for tests that need to change the default royalty config first.
+pub fn mint_royalty_nfts_with_config(
+    fixture: &mut TradingFixture,
+    royalty_config: RoyalConfig,
+    amount: u64,
+) -> TestCollection {
+    let test_runner = &mut fixture.test_runner;
+    let user = &fixture.user;
+
+    let (component, creator_key) =
+        create_royalty_nft(test_runner, user, fixture.mint_factory, royalty_config);
+
+    enable_mint_reveal(
+        test_runner,
+        user,
+        component,
+        creator_key,
+        fixture.marketplace_key,
+        dec!(100.2389272937),
+        1000u64,
+        Instant {
+            seconds_since_unix_epoch: 0,
+        },
+    );
+
+    let minting_transient = get_transient_address(test_runner, user, component);
+
+    let nft_address = nft_address(test_runner, user, component);
+
+    purchase_preview_mint_via_marketplace(
+        test_runner,
+        user,
+        fixture.marketplace_component,
+        nft_address,
+        dec!(100.2389272937),
+        amount,
+        minting_transient,
+        component,
+    );
+
+    TestCollection {
+        component,
+        creator_key,
+        nft_address,
+    }
+}
+
+/// Creates a collection without royalties and mints NFTs 0 to `amount - 1` directly.
+pub fn mint_nfts(fixture: &mut TradingFixture, amount: u64) -> TestCollection {
+    let test_runner = &mut fixture.test_runner;
+    let user = &fixture.user;
+
+    let (component, creator_key) = create_custom_variant_nft(
+        test_runner,
+        user,
+        fixture.mint_factory,
+        blank_config(),
+        fixture.depositer_badge,
+        false,
+    );
+
+    for id in 0..amount {
+        direct_mint(test_runner, user, component, id, creator_key);
+    }
+
+    let nft_address = nft_address(test_runner, user, component);
+
+    TestCollection {
+        component,
+        creator_key,
+        nft_address,
+    }
+}
+
+/// A Royalty NFT collection with the default royalty config and nothing minted, for tests that only exercise the
+/// creator's side of the config.
+pub fn setup_royalty_nft() -> (DefaultLedgerSimulator, User, TestCollection) {
+    let (mut test_runner, user, package) = setup_for_test();
+
+    let open_hub_component = instantiate_open_hub(&mut test_runner, &user, package);
+
+    let depositer_badge = fetch_depositer_badge(&mut test_runner, &user, open_hub_component);
+
+    let mint_factory = create_mint_factory(&mut test_runner, &user, package);
+
+    let royalty_config = defaults_royalty_config(depositer_badge);
+
+    let (component, creator_key) =
+        create_royalty_nft(&mut test_runner, &user, mint_factory, royalty_config);
+
+    let nft_address = nft_address(&mut test_runner, &user, component);
+
+    let collection = TestCollection {
+        component,
+        creator_key,
+        nft_address,
+    };
+
+    (test_runner, user, collection)
+}
+
+/// Three Royalty NFTs, the first of which is listed at 100 XRD for the fixture's marketplace.
+pub fn setup_listed_royalty_nft() -> (TradingFixture, TestCollection) {
+    let mut fixture = setup_trading();
+
+    let collection = mint_royalty_nfts(&mut fixture, 3);
+
+    list_royalty_nft(
+        &mut fixture.test_runner,
+        &fixture.user,
+        fixture.trader_component,
+        fixture.trader_key_resource,
+        fixture.trader_key_local.clone(),
+        collection.nft_address,
+        NonFungibleLocalId::integer(0),
+        dec!(100),
+        None,
+        vec![fixture.marketplace_key],
+    );
+
+    (fixture, collection)
+}
+
 use std::ops::Range;
 
 /// Converts a vector of u64 into a vector of NonFungibleLocalId
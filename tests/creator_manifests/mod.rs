@@ -4,6 +4,59 @@ use scrypto_test::prelude::*;
 
 use crate::common::*;
 
+#[derive(ScryptoSbor, ManifestEncode, ManifestDecode, Clone, PartialEq, Eq, Debug)]
+pub struct RoyaltyBand {
+    pub from_price: Decimal,
+    pub royalty_percent: Decimal,
+}
+
+#[derive(ScryptoSbor, ManifestEncode, ManifestDecode, Clone, PartialEq, Eq, Debug)]
+pub struct FlatRoyalty {
+    pub amount: Decimal,
+    pub greater_of_percent: bool,
+}
+
+#[derive(ScryptoSbor, ManifestEncode, ManifestDecode, Clone, Debug)]
+pub struct RoyaltySplit {
+    pub beneficiary: ComponentAddress,
+    pub basis_points: u16,
+}
+
+#[derive(ScryptoSbor, ManifestEncode, ManifestDecode, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SplitPayout {
+    Locker,
+    Accrue,
+}
+
+#[derive(ScryptoSbor, ManifestEncode, ManifestDecode, Clone, PartialEq, Eq, Debug)]
+pub enum RoyaltyChange {
+    RoyaltyPercent(Decimal),
+    LimitBuyers,
+    LimitDapps,
+    RemovePermittedCurrency(ResourceAddress),
+    ChangeDelay(i64),
+    RoyaltyBands(ResourceAddress, Vec<RoyaltyBand>),
+    RemoveRoyaltyBands(ResourceAddress),
+    FlatRoyalty(ResourceAddress, FlatRoyalty),
+    RemoveFlatRoyalty(ResourceAddress),
+    CurrencyRoyaltyPercent(ResourceAddress, Decimal),
+    RemoveCurrencyRoyaltyPercent(ResourceAddress),
+    NftRoyaltyPercent(NonFungibleLocalId, Decimal),
+    RemoveNftRoyaltyPercent(NonFungibleLocalId),
+    MinimumRoyalties,
+    MinimumRoyaltyAmount(ResourceAddress, Decimal),
+    LimitCurrencies,
+    RemovePermissionedBuyer(ResourceAddress),
+    RemovePermissionedDapp(ComponentAddress),
+}
+
+#[derive(ScryptoSbor, ManifestEncode, ManifestDecode, Clone, Debug)]
+pub struct PendingRoyaltyChange {
+    pub id: u64,
+    pub change: RoyaltyChange,
+    pub effective_from: Instant,
+}
+
 pub fn enable_mint_reveal(
     test_runner: &mut DefaultLedgerSimulator,
     user: &User,
@@ -961,6 +1014,15 @@ pub fn lock_royalty_configuration(
     component: ComponentAddress,
     creator_key: ResourceAddress,
 ) {
+    try_lock_royalty_configuration(test_runner, user, component, creator_key).expect_commit(true);
+}
+
+pub fn try_lock_royalty_configuration(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    component: ComponentAddress,
+    creator_key: ResourceAddress,
+) -> TransactionReceipt {
     let creator_local_id: NonFungibleLocalId =
         NonFungibleLocalId::string("creator_key".to_string()).unwrap();
 
@@ -974,6 +1036,31 @@ pub fn lock_royalty_configuration(
         .call_method(component, "lock_royalty_configuration", manifest_args!())
         .build();
 
+    test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    )
+}
+
+pub fn remove_royalty_config(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    component: ComponentAddress,
+    creator_key: ResourceAddress,
+) {
+    let creator_local_id: NonFungibleLocalId =
+        NonFungibleLocalId::string("creator_key".to_string()).unwrap();
+
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(
+            user.account,
+            "create_proof_of_non_fungibles",
+            manifest_args!(creator_key, vec![creator_local_id]),
+        )
+        .call_method(component, "remove_royalty_config", manifest_args!())
+        .build();
+
     let receipt = test_runner.execute_manifest(
         manifest,
         vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
@@ -1026,3 +1113,356 @@ pub fn transfer_royal_nft_to_component(
 
     receipt.expect_commit(true);
 }
+
+pub fn set_royalty_bands(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    component: ComponentAddress,
+    creator_key: ResourceAddress,
+    currency: ResourceAddress,
+    bands: Vec<RoyaltyBand>,
+) -> TransactionReceipt {
+    let creator_local_id: NonFungibleLocalId =
+        NonFungibleLocalId::string("creator_key".to_string()).unwrap();
+
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(
+            user.account,
+            "create_proof_of_non_fungibles",
+            manifest_args!(creator_key, vec![creator_local_id]),
+        )
+        .call_method(
+            component,
+            "set_royalty_bands",
+            manifest_args!(currency, bands),
+        )
+        .build();
+
+    test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    )
+}
+
+pub fn get_royalty_bands(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    component: ComponentAddress,
+    currency: ResourceAddress,
+) -> Option<Vec<RoyaltyBand>> {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(component, "get_royalty_bands", manifest_args!(currency))
+        .build();
+
+    let receipt = test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    );
+
+    receipt.expect_commit(true).output(1)
+}
+
+pub fn set_flat_royalty(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    component: ComponentAddress,
+    creator_key: ResourceAddress,
+    currency: ResourceAddress,
+    flat_royalty: FlatRoyalty,
+) -> TransactionReceipt {
+    let creator_local_id: NonFungibleLocalId =
+        NonFungibleLocalId::string("creator_key".to_string()).unwrap();
+
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(
+            user.account,
+            "create_proof_of_non_fungibles",
+            manifest_args!(creator_key, vec![creator_local_id]),
+        )
+        .call_method(
+            component,
+            "set_flat_royalty",
+            manifest_args!(currency, flat_royalty),
+        )
+        .build();
+
+    test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    )
+}
+
+pub fn get_flat_royalty(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    component: ComponentAddress,
+    currency: ResourceAddress,
+) -> Option<FlatRoyalty> {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(component, "get_flat_royalty", manifest_args!(currency))
+        .build();
+
+    let receipt = test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    );
+
+    receipt.expect_commit(true).output(1)
+}
+
+pub fn set_royalty_splits(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    component: ComponentAddress,
+    creator_key: ResourceAddress,
+    splits: Vec<RoyaltySplit>,
+    payout: SplitPayout,
+) -> TransactionReceipt {
+    let creator_local_id: NonFungibleLocalId =
+        NonFungibleLocalId::string("creator_key".to_string()).unwrap();
+
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(
+            user.account,
+            "create_proof_of_non_fungibles",
+            manifest_args!(creator_key, vec![creator_local_id]),
+        )
+        .call_method(
+            component,
+            "set_royalty_splits",
+            manifest_args!(splits, payout),
+        )
+        .build();
+
+    test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    )
+}
+
+pub fn get_royalty_splits(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    component: ComponentAddress,
+) -> (Vec<RoyaltySplit>, SplitPayout) {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(component, "get_royalty_splits", manifest_args!())
+        .build();
+
+    let receipt = test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    );
+
+    receipt.expect_commit(true).output(1)
+}
+
+pub fn set_currency_royalty_percent(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    component: ComponentAddress,
+    creator_key: ResourceAddress,
+    currency: ResourceAddress,
+    royalty_percent: Decimal,
+) -> TransactionReceipt {
+    let creator_local_id: NonFungibleLocalId =
+        NonFungibleLocalId::string("creator_key".to_string()).unwrap();
+
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(
+            user.account,
+            "create_proof_of_non_fungibles",
+            manifest_args!(creator_key, vec![creator_local_id]),
+        )
+        .call_method(
+            component,
+            "set_currency_royalty_percent",
+            manifest_args!(currency, royalty_percent),
+        )
+        .build();
+
+    test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    )
+}
+
+pub fn get_currency_royalty_percent(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    component: ComponentAddress,
+    currency: ResourceAddress,
+) -> Decimal {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(
+            component,
+            "get_currency_royalty_percent",
+            manifest_args!(currency),
+        )
+        .build();
+
+    let receipt = test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    );
+
+    receipt.expect_commit(true).output(1)
+}
+
+pub fn set_nft_royalty_percent(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    component: ComponentAddress,
+    creator_key: ResourceAddress,
+    local_id: NonFungibleLocalId,
+    royalty_percent: Decimal,
+) -> TransactionReceipt {
+    let creator_local_id: NonFungibleLocalId =
+        NonFungibleLocalId::string("creator_key".to_string()).unwrap();
+
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(
+            user.account,
+            "create_proof_of_non_fungibles",
+            manifest_args!(creator_key, vec![creator_local_id]),
+        )
+        .call_method(
+            component,
+            "set_nft_royalty_percent",
+            manifest_args!(local_id, royalty_percent),
+        )
+        .build();
+
+    test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    )
+}
+
+pub fn get_nft_royalty_percent(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    component: ComponentAddress,
+    local_id: NonFungibleLocalId,
+) -> Option<Decimal> {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(
+            component,
+            "get_nft_royalty_percent",
+            manifest_args!(local_id),
+        )
+        .build();
+
+    let receipt = test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    );
+
+    receipt.expect_commit(true).output(1)
+}
+
+pub fn set_royalty_change_delay(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    component: ComponentAddress,
+    creator_key: ResourceAddress,
+    delay_seconds: i64,
+) -> TransactionReceipt {
+    let creator_local_id: NonFungibleLocalId =
+        NonFungibleLocalId::string("creator_key".to_string()).unwrap();
+
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(
+            user.account,
+            "create_proof_of_non_fungibles",
+            manifest_args!(creator_key, vec![creator_local_id]),
+        )
+        .call_method(
+            component,
+            "set_royalty_change_delay",
+            manifest_args!(delay_seconds),
+        )
+        .build();
+
+    test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    )
+}
+
+pub fn try_cancel_pending_royalty_change(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    component: ComponentAddress,
+    creator_key: ResourceAddress,
+    id: u64,
+) -> TransactionReceipt {
+    let creator_local_id: NonFungibleLocalId =
+        NonFungibleLocalId::string("creator_key".to_string()).unwrap();
+
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(
+            user.account,
+            "create_proof_of_non_fungibles",
+            manifest_args!(creator_key, vec![creator_local_id]),
+        )
+        .call_method(
+            component,
+            "cancel_pending_royalty_change",
+            manifest_args!(id),
+        )
+        .build();
+
+    test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    )
+}
+
+pub fn apply_pending_royalty_changes(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    component: ComponentAddress,
+) {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(component, "apply_pending_royalty_changes", manifest_args!())
+        .build();
+
+    let receipt = test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    );
+
+    receipt.expect_commit(true);
+}
+
+pub fn get_pending_royalty_changes(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    component: ComponentAddress,
+) -> Vec<PendingRoyaltyChange> {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(component, "get_pending_royalty_changes", manifest_args!())
+        .build();
+
+    let receipt = test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    );
+
+    receipt.expect_commit(true).output(1)
+}
@@ -0,0 +1,285 @@
+use outpost::outpost_event::*;
+use scrypto_test::prelude::*;
+
+// A copy of the listing shape that was embedded in events before schema versioning was introduced.
+// If ListingV1 ever drifts from this, previously emitted version 1 events will no longer decode.
+#[derive(ScryptoSbor)]
+struct FrozenListingV1 {
+    secondary_seller_permissions: Vec<ResourceAddress>,
+    currency: ResourceAddress,
+    price: Decimal,
+    nfgid: NonFungibleGlobalId,
+    outpost_account: ComponentAddress,
+}
+
+fn test_nft_id() -> NonFungibleGlobalId {
+    NonFungibleGlobalId::new(SECP256K1_SIGNATURE_RESOURCE, NonFungibleLocalId::integer(1))
+}
+
+#[test]
+fn frozen_v1_listing_decodes_as_listing_v1() {
+    let frozen = FrozenListingV1 {
+        secondary_seller_permissions: vec![XRD],
+        currency: XRD,
+        price: dec!(100),
+        nfgid: test_nft_id(),
+        outpost_account: FAUCET,
+    };
+
+    let encoded = scrypto_encode(&frozen).unwrap();
+
+    let decoded: ListingV1 = scrypto_decode(&encoded).unwrap();
+
+    assert_eq!(decoded.secondary_seller_permissions, vec![XRD]);
+    assert_eq!(decoded.currency, XRD);
+    assert_eq!(decoded.price, dec!(100));
+    assert_eq!(decoded.nfgid, test_nft_id());
+    assert_eq!(decoded.outpost_account, FAUCET);
+}
+
+#[test]
+fn v1_listing_events_still_decode() {
+    let listing = ListingV1 {
+        secondary_seller_permissions: vec![XRD],
+        currency: XRD,
+        price: dec!(100),
+        nfgid: test_nft_id(),
+        outpost_account: FAUCET,
+    };
+
+    let event = ListingCreated {
+        schema_version: 1,
+        payload: ListingEventPayload::V1 {
+            listing: listing.clone(),
+            outpost_account: FAUCET,
+            nft_id: test_nft_id(),
+        },
+    };
+
+    let decoded: ListingCreated = scrypto_decode(&scrypto_encode(&event).unwrap()).unwrap();
+
+    assert_eq!(decoded.schema_version, 1);
+
+    match decoded.payload {
+        ListingEventPayload::V1 {
+            listing: decoded_listing,
+            outpost_account,
+            nft_id,
+        } => {
+            assert_eq!(decoded_listing, listing);
+            assert_eq!(outpost_account, FAUCET);
+            assert_eq!(nft_id, test_nft_id());
+        }
+        _ => panic!("Expected a version 1 payload"),
+    }
+
+    let purchase = ListingPurchased {
+        schema_version: 1,
+        payload: ListingPurchasedPayload::V1 {
+            listing: listing.clone(),
+            outpost_account: FAUCET,
+            nft_id: test_nft_id(),
+        },
+    };
+
+    let decoded: ListingPurchased = scrypto_decode(&scrypto_encode(&purchase).unwrap()).unwrap();
+
+    assert!(matches!(decoded.payload, ListingPurchasedPayload::V1 { .. }));
+}
+
+#[test]
+fn v2_listing_decodes_as_listing_v2() {
+    let listing = ListingV2 {
+        secondary_seller_permissions: vec![XRD],
+        currency: XRD,
+        price: dec!(100),
+        nfgid: test_nft_id(),
+        outpost_account: FAUCET,
+        start_time: Some(Instant::new(1_700_000_000)),
+    };
+
+    let purchase = ListingPurchased {
+        schema_version: 2,
+        payload: ListingPurchasedPayload::V2 {
+            listing: listing.clone(),
+            outpost_account: FAUCET,
+            nft_id: test_nft_id(),
+            permission: XRD,
+            recipient: None,
+            marketplace_fee: dec!(2),
+            royalty_amount: dec!(5),
+            seller_proceeds: dec!(93),
+            timestamp: Instant::new(1_700_000_100),
+        },
+    };
+
+    let decoded: ListingPurchased = scrypto_decode(&scrypto_encode(&purchase).unwrap()).unwrap();
+
+    match decoded.payload {
+        ListingPurchasedPayload::V2 {
+            listing: decoded_listing,
+            ..
+        } => assert_eq!(decoded_listing, listing),
+        _ => panic!("Expected a version 2 payload"),
+    }
+}
+
+#[test]
+fn current_listing_events_round_trip() {
+    let listing = ListingV3 {
+        secondary_seller_permissions: vec![XRD],
+        currency: XRD,
+        price: dec!(100),
+        nfgid: test_nft_id(),
+        outpost_account: FAUCET,
+        start_time: Some(Instant::new(1_700_000_000)),
+        registered_marketplaces: true,
+    };
+
+    let payload = ListingPurchasedPayload::V4 {
+        listing,
+        outpost_account: FAUCET,
+        nft_id: test_nft_id(),
+        permission: XRD,
+        recipient: None,
+        marketplace_fee: dec!(2),
+        royalty_amount: dec!(5),
+        protocol_fee: dec!(1),
+        seller_proceeds: dec!(92),
+        timestamp: Instant::new(1_700_000_100),
+    };
+
+    // the schema version always follows the payload variant
+    assert_eq!(payload.schema_version(), 4);
+
+    let event = ListingPurchased {
+        schema_version: payload.schema_version(),
+        payload,
+    };
+
+    let decoded: ListingPurchased = scrypto_decode(&scrypto_encode(&event).unwrap()).unwrap();
+
+    assert_eq!(decoded.schema_version, 4);
+
+    match decoded.payload {
+        ListingPurchasedPayload::V4 {
+            listing,
+            protocol_fee,
+            seller_proceeds,
+            ..
+        } => {
+            assert_eq!(listing.start_time, Some(Instant::new(1_700_000_000)));
+            assert!(listing.registered_marketplaces);
+            assert_eq!(protocol_fee, dec!(1));
+            assert_eq!(seller_proceeds, dec!(92));
+        }
+        _ => panic!("Expected a version 4 payload"),
+    }
+
+    let payload = ListingEventPayload::V3 {
+        listing: ListingV3 {
+            secondary_seller_permissions: vec![XRD],
+            currency: XRD,
+            price: dec!(100),
+            nfgid: test_nft_id(),
+            outpost_account: FAUCET,
+            start_time: None,
+            registered_marketplaces: false,
+        },
+        outpost_account: FAUCET,
+        nft_id: test_nft_id(),
+    };
+
+    assert_eq!(payload.schema_version(), 3);
+}
+
+// A copy of the bare listing event structs emitted by the Outpost and the event component before schema versioning was
+// introduced, so the bytes below are exactly what those events carried.
+#[derive(ScryptoSbor)]
+struct BaselineListingCreated {
+    listing: FrozenListingV1,
+    outpost_account: ComponentAddress,
+    nft_id: NonFungibleGlobalId,
+}
+
+#[test]
+fn unversioned_events_decode_only_as_unversioned_events() {
+    let baseline = BaselineListingCreated {
+        listing: FrozenListingV1 {
+            secondary_seller_permissions: vec![XRD],
+            currency: XRD,
+            price: dec!(100),
+            nfgid: test_nft_id(),
+            outpost_account: FAUCET,
+        },
+        outpost_account: FAUCET,
+        nft_id: test_nft_id(),
+    };
+
+    let encoded = scrypto_encode(&baseline).unwrap();
+
+    // versioning is a breaking change - old events don't decode as the versioned events
+    assert!(scrypto_decode::<ListingCreated>(&encoded).is_err());
+    assert!(scrypto_decode::<ListingPurchased>(&encoded).is_err());
+
+    let decoded: UnversionedListingEvent = scrypto_decode(&encoded).unwrap();
+
+    assert_eq!(decoded.listing.price, dec!(100));
+    assert_eq!(decoded.outpost_account, FAUCET);
+    assert_eq!(decoded.nft_id, test_nft_id());
+}
+
+// No Outpost flow rekeys an Outpost, so key recovery events are only checked for their schema here.
+#[test]
+fn key_recovered_events_round_trip() {
+    let event = OutpostKeyRecovered {
+        schema_version: 1,
+        payload: OutpostKeyRecoveredPayload::V1 {
+            outpost_component: FAUCET,
+            outpost_account: FAUCET,
+            new_key: test_nft_id(),
+        },
+    };
+
+    let decoded: OutpostKeyRecovered = scrypto_decode(&scrypto_encode(&event).unwrap()).unwrap();
+
+    match decoded.payload {
+        OutpostKeyRecoveredPayload::V1 {
+            outpost_account,
+            new_key,
+            ..
+        } => {
+            assert_eq!(outpost_account, FAUCET);
+            assert_eq!(new_key, test_nft_id());
+        }
+    }
+}
+
+// Revenue claims are emitted whenever sale or rental proceeds are paid out to an Outpost's linked account.
+#[test]
+fn revenue_claimed_events_round_trip() {
+    let event = RevenueClaimed {
+        schema_version: 1,
+        payload: RevenueClaimedPayload::V1 {
+            outpost_component: FAUCET,
+            recipient: FAUCET,
+            currency: XRD,
+            amount: dec!(250),
+        },
+    };
+
+    let decoded: RevenueClaimed = scrypto_decode(&scrypto_encode(&event).unwrap()).unwrap();
+
+    match decoded.payload {
+        RevenueClaimedPayload::V1 {
+            recipient,
+            currency,
+            amount,
+            ..
+        } => {
+            assert_eq!(recipient, FAUCET);
+            assert_eq!(currency, XRD);
+            assert_eq!(amount, dec!(250));
+        }
+    }
+}
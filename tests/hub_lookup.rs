@@ -0,0 +1,121 @@
+use scrypto::runtime::Clock;
+use scrypto_test::prelude::*;
+mod common;
+mod creator_manifests;
+mod marketplace_manifests;
+mod misc_manifests;
+mod scenario_manifests;
+mod trader_manifests;
+use common::*;
+use creator_manifests::*;
+use marketplace_manifests::*;
+use misc_manifests::*;
+use scenario_manifests::*;
+use trader_manifests::*;
+
+#[test]
+fn resolve_account_to_outpost() {
+    let (mut test_runner, user, package) = setup_for_test();
+
+    let open_hub_component = instantiate_open_hub(&mut test_runner, &user, package);
+
+    let (_trader_key_resource, _trader_key_local, trader_component) =
+        create_outpost(&mut test_runner, &user, open_hub_component);
+
+    let outpost = get_outpost(&mut test_runner, &user, open_hub_component, user.account);
+
+    assert_eq!(outpost, Some(trader_component));
+
+    assert!(is_registered_outpost(
+        &mut test_runner,
+        &user,
+        open_hub_component,
+        trader_component
+    ));
+
+    assert!(!is_registered_outpost(
+        &mut test_runner,
+        &user,
+        open_hub_component,
+        open_hub_component
+    ));
+}
+
+#[test]
+fn multiple_outposts_and_relink() {
+    let (mut test_runner, user, package) = setup_for_test();
+
+    let new_user = make_user(&mut test_runner, Some("user2"));
+
+    let open_hub_component = instantiate_open_hub(&mut test_runner, &user, package);
+
+    let (_first_key_resource, _first_key_local, first_outpost) =
+        create_outpost(&mut test_runner, &user, open_hub_component);
+
+    let (key_resource, second_key_local, second_outpost) =
+        create_outpost(&mut test_runner, &user, open_hub_component);
+
+    assert_eq!(
+        get_outposts(&mut test_runner, &user, open_hub_component, user.account),
+        vec![first_outpost, second_outpost]
+    );
+
+    relink_outpost(
+        &mut test_runner,
+        &user,
+        &new_user,
+        open_hub_component,
+        second_outpost,
+    );
+
+    assert_eq!(
+        get_outposts(&mut test_runner, &user, open_hub_component, user.account),
+        vec![first_outpost]
+    );
+
+    assert_eq!(
+        get_outposts(&mut test_runner, &user, open_hub_component, new_user.account),
+        vec![second_outpost]
+    );
+
+    let (linked_account, key) =
+        get_outpost_owner(&mut test_runner, &user, open_hub_component, second_outpost).unwrap();
+
+    assert_eq!(linked_account, new_user.account);
+
+    // the Outpost keeps its key, which stays in the account it was issued to
+    assert_eq!(key, NonFungibleGlobalId::new(key_resource, second_key_local));
+
+    assert_eq!(
+        test_runner.get_component_balance(new_user.account, key_resource),
+        dec!(0)
+    );
+}
+
+#[test]
+fn marketplace_directory() {
+    let (mut test_runner, user, package) = setup_for_test();
+
+    let (open_hub_component, hub_admin) =
+        instantiate_open_hub_with_admin(&mut test_runner, &user, package);
+
+    let (marketplace_component, marketplace_key) =
+        create_marketplace(&mut test_runner, &user, package, dec!(0.02));
+
+    assert!(get_registered_marketplaces(&mut test_runner, &user, open_hub_component).is_empty());
+
+    register_marketplace(
+        &mut test_runner,
+        &user,
+        open_hub_component,
+        hub_admin,
+        marketplace_key,
+        marketplace_component,
+        dec!(0.05),
+    );
+
+    assert_eq!(
+        get_registered_marketplaces(&mut test_runner, &user, open_hub_component),
+        vec![marketplace_key]
+    );
+}
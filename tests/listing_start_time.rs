@@ -0,0 +1,86 @@
+use scrypto_test::prelude::*;
+mod common;
+mod creator_manifests;
+mod marketplace_manifests;
+mod misc_manifests;
+mod scenario_manifests;
+mod trader_manifests;
+use common::*;
+use creator_manifests::*;
+use marketplace_manifests::*;
+use misc_manifests::*;
+use scenario_manifests::*;
+use trader_manifests::*;
+
+const ONE_DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+#[test]
+fn purchase_only_after_start_time() {
+    let mut fixture = setup_trading();
+
+    let nft_address = mint_royalty_nfts(&mut fixture, 3).nft_address;
+
+    let TradingFixture {
+        mut test_runner,
+        user,
+        trader_key_resource,
+        trader_key_local,
+        trader_component,
+        marketplace_component,
+        marketplace_key,
+        ..
+    } = fixture;
+
+    let global_id = create_global_id(nft_address.clone(), 0);
+
+    // the listing only opens one day in
+    list_royalty_nft_from(
+        &mut test_runner,
+        &user,
+        trader_component.clone(),
+        trader_key_resource.clone(),
+        trader_key_local.clone(),
+        nft_address.clone(),
+        NonFungibleLocalId::integer(0),
+        dec!(100),
+        None,
+        vec![marketplace_key.clone()],
+        Some(Instant {
+            seconds_since_unix_epoch: ONE_DAY_MS / 1000,
+        }),
+        false,
+    );
+
+    let transient_token_address =
+        get_transient_token_address(&mut test_runner, &user, trader_component.clone());
+
+    try_purchase_royalty_nft(
+        &mut test_runner,
+        &user,
+        marketplace_component,
+        trader_component,
+        global_id.clone(),
+        dec!(100),
+        None,
+        transient_token_address,
+    )
+    .expect_commit_failure();
+
+    test_runner.advance_to_round_at_timestamp(Round::of(2), 2 * ONE_DAY_MS);
+
+    try_purchase_royalty_nft(
+        &mut test_runner,
+        &user,
+        marketplace_component,
+        trader_component,
+        global_id,
+        dec!(100),
+        None,
+        transient_token_address,
+    )
+    .expect_commit_success();
+
+    let balance = test_runner.get_component_balance(user.account, nft_address);
+
+    assert_eq!(balance, dec!(3));
+}
@@ -0,0 +1,201 @@
+use scrypto_test::prelude::*;
+mod common;
+mod creator_manifests;
+mod marketplace_manifests;
+mod misc_manifests;
+mod scenario_manifests;
+mod trader_manifests;
+use common::*;
+use creator_manifests::*;
+use marketplace_manifests::*;
+use misc_manifests::*;
+use scenario_manifests::*;
+use trader_manifests::*;
+
+const ONE_DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+struct LoanSetup {
+    test_runner: DefaultLedgerSimulator,
+    user: User,
+    lender: User,
+    trader_component: ComponentAddress,
+    trader_key_resource: ResourceAddress,
+    trader_key_local: NonFungibleLocalId,
+    royalty_nft_component: ComponentAddress,
+    creator_key: ResourceAddress,
+    nft_address: ResourceAddress,
+}
+
+/// Mints three Royalty NFTs to the user, lists the first on their Outpost and has the lender offer a loan of 50 XRD
+/// (repaying 60 XRD within a day) against it.
+fn setup_loan() -> LoanSetup {
+    let (
+        TradingFixture {
+            mut test_runner,
+            user,
+            trader_key_resource,
+            trader_key_local,
+            trader_component,
+            ..
+        },
+        TestCollection {
+            component: royalty_nft_component,
+            creator_key,
+            nft_address,
+        },
+    ) = setup_listed_royalty_nft();
+
+    let lender = make_user(&mut test_runner, Some("lender"));
+
+    offer_loan(
+        &mut test_runner,
+        &lender,
+        trader_component,
+        dec!(50),
+        dec!(60),
+        ONE_DAY_MS / 1000,
+        vec![(create_global_id(nft_address, 0), dec!(50))],
+    );
+
+    LoanSetup {
+        test_runner,
+        user,
+        lender,
+        trader_component,
+        trader_key_resource,
+        trader_key_local,
+        royalty_nft_component,
+        creator_key,
+        nft_address,
+    }
+}
+
+#[test]
+fn repaid_loan_returns_the_collateral() {
+    let LoanSetup {
+        mut test_runner,
+        user,
+        lender,
+        trader_component,
+        trader_key_resource,
+        trader_key_local,
+        nft_address,
+        ..
+    } = setup_loan();
+
+    let user_balance = test_runner.get_component_balance(user.account, XRD);
+
+    accept_loan(
+        &mut test_runner,
+        &user,
+        trader_component,
+        trader_key_resource,
+        trader_key_local.clone(),
+        1,
+    );
+
+    assert_eq!(
+        test_runner.get_component_balance(user.account, XRD) - user_balance,
+        dec!(50)
+    );
+
+    // the lender can't claim the collateral before the deadline
+    try_claim_loan(&mut test_runner, &lender, trader_component, 1, Some(dec!(50)))
+        .expect_commit_failure();
+
+    // the full repayment is required
+    try_repay_loan(
+        &mut test_runner,
+        &user,
+        trader_component,
+        trader_key_resource,
+        trader_key_local.clone(),
+        1,
+        dec!(50),
+    )
+    .expect_commit_failure();
+
+    try_repay_loan(
+        &mut test_runner,
+        &user,
+        trader_component,
+        trader_key_resource,
+        trader_key_local,
+        1,
+        dec!(60),
+    )
+    .expect_commit_success();
+
+    // the collateral is back in the user's account rather than stranded in the Outpost
+    assert_eq!(
+        test_runner.get_component_balance(user.account, nft_address),
+        dec!(3)
+    );
+
+    let lender_balance = test_runner.get_component_balance(lender.account, XRD);
+
+    try_claim_loan(&mut test_runner, &lender, trader_component, 1, None).expect_commit_success();
+
+    assert_eq!(
+        test_runner.get_component_balance(lender.account, XRD) - lender_balance,
+        dec!(60)
+    );
+}
+
+#[test]
+fn defaulted_loan_pays_royalty_on_the_collateral_value() {
+    let LoanSetup {
+        mut test_runner,
+        user,
+        lender,
+        trader_component,
+        trader_key_resource,
+        trader_key_local,
+        royalty_nft_component,
+        creator_key,
+        nft_address,
+    } = setup_loan();
+
+    accept_loan(
+        &mut test_runner,
+        &user,
+        trader_component,
+        trader_key_resource,
+        trader_key_local,
+        1,
+    );
+
+    // seizing collateral isn't a purchase, so it works even when the creator limits buyers
+    deny_all_buyers(&mut test_runner, &user, royalty_nft_component, creator_key);
+
+    test_runner.advance_to_round_at_timestamp(Round::of(2), 2 * ONE_DAY_MS);
+
+    // the royalty payment must cover the value of the seized Royalty NFT
+    try_claim_loan(&mut test_runner, &lender, trader_component, 1, Some(dec!(40)))
+        .expect_commit_failure();
+
+    let lender_balance = test_runner.get_component_balance(lender.account, XRD);
+    let royalties_before = test_runner.get_component_balance(royalty_nft_component, XRD);
+
+    try_claim_loan(&mut test_runner, &lender, trader_component, 1, Some(dec!(50)))
+        .expect_commit_success();
+
+    // 10% royalty on the 50 XRD the NFT was valued at, the rest of the payment is returned
+    assert_eq!(
+        test_runner.get_component_balance(royalty_nft_component, XRD) - royalties_before,
+        dec!(5)
+    );
+
+    assert_eq!(
+        lender_balance - test_runner.get_component_balance(lender.account, XRD),
+        dec!(5)
+    );
+
+    assert_eq!(
+        test_runner.get_component_balance(lender.account, nft_address),
+        dec!(1)
+    );
+
+    // the loan note has been burnt
+    try_claim_loan(&mut test_runner, &lender, trader_component, 1, None).expect_commit_failure();
+}
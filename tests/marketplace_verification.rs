@@ -0,0 +1,109 @@
+use scrypto_test::prelude::*;
+mod common;
+mod creator_manifests;
+mod marketplace_manifests;
+mod misc_manifests;
+mod scenario_manifests;
+mod trader_manifests;
+use common::*;
+use creator_manifests::*;
+use marketplace_manifests::*;
+use misc_manifests::*;
+use scenario_manifests::*;
+use trader_manifests::*;
+
+#[test]
+fn marketplace_only_buys_from_recognised_outposts() {
+    let mut fixture = setup_trading();
+
+    let nft_address = mint_nfts(&mut fixture, 1).nft_address;
+
+    let TradingFixture {
+        mut test_runner,
+        user,
+        package,
+        open_hub_component,
+        trader_key_resource,
+        trader_key_local,
+        trader_component,
+        ..
+    } = fixture;
+
+    let buyer = make_user(&mut test_runner, Some("buyer"));
+
+    // a marketplace whose configuration this test can change
+    let (marketplace_component, marketplace_key, marketplace_admin) =
+        create_marketplace_with_admin(&mut test_runner, &user, package, dec!(0.02));
+
+    list(
+        &mut test_runner,
+        &user,
+        trader_component.clone(),
+        trader_key_resource,
+        trader_key_local,
+        nft_address.clone(),
+        NonFungibleLocalId::integer(0),
+        dec!(100),
+        None,
+        vec![marketplace_key],
+    );
+
+    let nfgid = create_global_id(nft_address.clone(), 0);
+
+    // a component that isn't an Outpost is rejected before any payment is passed on
+    let dapp_component = create_generic_dapp(&mut test_runner, &user, package);
+
+    try_purchase_multi_listing(
+        &mut test_runner,
+        &buyer,
+        marketplace_component,
+        vec![(dapp_component, nfgid.clone(), dec!(100))],
+        dec!(100),
+    )
+    .expect_commit_failure();
+
+    // once the marketplace expects Outposts from another package, this Outpost is no longer recognised...
+    configure_marketplace(
+        &mut test_runner,
+        &user,
+        marketplace_component,
+        marketplace_admin,
+        "set_outpost_package",
+        FAUCET_PACKAGE,
+    );
+
+    let orders = vec![(trader_component, nfgid, dec!(100))];
+
+    try_purchase_multi_listing(
+        &mut test_runner,
+        &buyer,
+        marketplace_component,
+        orders.clone(),
+        dec!(100),
+    )
+    .expect_commit_failure();
+
+    // ...unless it is registered in the open hub the marketplace trusts
+    configure_marketplace(
+        &mut test_runner,
+        &user,
+        marketplace_component,
+        marketplace_admin,
+        "set_open_hub",
+        Some(open_hub_component),
+    );
+
+    try_purchase_multi_listing(
+        &mut test_runner,
+        &buyer,
+        marketplace_component,
+        orders,
+        dec!(100),
+    )
+    .expect_commit_success();
+
+    assert_eq!(
+        test_runner.get_component_balance(buyer.account, nft_address),
+        dec!(1)
+    );
+}
@@ -2,6 +2,7 @@ use scrypto::prelude::*;
 use scrypto_test::prelude::*;
 
 use crate::common::*;
+use outpost::outpost_factory::MarketplaceRegistration;
 
 #[derive(ScryptoSbor)]
 pub struct RoyaltyConfig {
@@ -163,7 +164,7 @@ pub fn create_event_listener(
             package,
             "Event",
             "create_event_listener",
-            manifest_args!(virt_badge_resource),
+            manifest_args!(virt_badge_resource, XRD),
         )
         .build();
 
@@ -184,13 +185,28 @@ pub fn create_marketplace(
     package: PackageAddress,
     fee: Decimal,
 ) -> (ComponentAddress, ResourceAddress) {
+    let (component, marketplace_key, _admin) =
+        create_marketplace_with_admin(test_runner, user, package, fee);
+
+    (component, marketplace_key)
+}
+
+/// Creates a marketplace along with the admin badge that manages it, held by the user.
+pub fn create_marketplace_with_admin(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    package: PackageAddress,
+    fee: Decimal,
+) -> (ComponentAddress, ResourceAddress, ResourceAddress) {
+    let admin = test_runner.create_fungible_resource(dec!(1), 0, user.account);
+
     let manifest = ManifestBuilder::new()
         .lock_fee_from_faucet()
         .call_function(
             package,
             "GenericMarketplace",
             "start_marketplace",
-            manifest_args!(fee, fee, user.account),
+            manifest_args!(fee, fee, user.account, admin),
         )
         .call_method(
             user.account,
@@ -227,7 +243,40 @@ pub fn create_marketplace(
 
     let marketplace_key: ResourceAddress = receipt.expect_commit(true).output(1);
 
-    (component, marketplace_key)
+    (component, marketplace_key, admin)
+}
+
+/// Calls `set_outpost_package` or `set_open_hub` on a marketplace with its admin badge.
+pub fn configure_marketplace<A: ManifestEncode>(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    marketplace_component: ComponentAddress,
+    admin: ResourceAddress,
+    method: &str,
+    value: A,
+) {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(user.account, admin, dec!(1))
+        .pop_from_auth_zone("admin_proof")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(
+                marketplace_component,
+                method,
+                manifest_args!(value, lookup.proof("admin_proof")),
+            )
+        })
+        .build();
+
+    let receipt = test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    );
+
+    if !receipt.is_commit_success() {
+        println!("{:?}", receipt);
+        panic!("TRANSACTION FAIL");
+    }
 }
 
 #[derive(ScryptoSbor, ManifestEncode, ManifestDecode)]
@@ -262,9 +311,21 @@ pub struct NFTRules {
     pub royalty_config_locked: bool,
 }
 
+#[derive(ScryptoSbor, ManifestEncode, ManifestDecode, Clone, Copy)]
+pub enum FlagReason {
+    Counterfeit,
+    Compromised,
+}
+
+#[derive(ScryptoSbor, ManifestEncode, ManifestDecode, Clone, Copy)]
+pub enum FlagSeverity {
+    Warn,
+    Block,
+}
+
 #[derive(ScryptoSbor, ManifestEncode, ManifestDecode)]
 pub struct RoyalConfig {
-    royaly_conig: RoyaltyConfigInput,
+    pub royaly_conig: RoyaltyConfigInput,
     metadata: NFTMetadata,
     minting_config: MintingConfig,
 }
@@ -702,3 +763,722 @@ pub fn withdraw_royalty_nft(
         panic!("TRANSACTION FAIL");
     }
 }
+
+pub fn get_outpost(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    hub_component: ComponentAddress,
+    account: ComponentAddress,
+) -> Option<ComponentAddress> {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(hub_component, "get_outpost", manifest_args!(account))
+        .build();
+
+    let receipt = test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    );
+
+    if !receipt.is_commit_success() {
+        println!("{:?}", receipt);
+        panic!("TRANSACTION FAIL");
+    }
+
+    receipt.expect_commit(true).output(1)
+}
+
+pub fn is_registered_outpost(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    hub_component: ComponentAddress,
+    component: ComponentAddress,
+) -> bool {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(
+            hub_component,
+            "is_registered_outpost",
+            manifest_args!(component),
+        )
+        .build();
+
+    let receipt = test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    );
+
+    if !receipt.is_commit_success() {
+        println!("{:?}", receipt);
+        panic!("TRANSACTION FAIL");
+    }
+
+    receipt.expect_commit(true).output(1)
+}
+
+pub fn instantiate_open_hub_with_admin(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    package: PackageAddress,
+) -> (ComponentAddress, ResourceAddress) {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_function(
+            package,
+            "OpenHub",
+            "start_open_hub",
+            manifest_args!(user.account),
+        )
+        .call_method(
+            user.account,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    );
+
+    if !receipt.is_commit_success() {
+        println!("{:?}", receipt);
+        panic!("TRANSACTION FAIL");
+    }
+
+    let commit = receipt.expect_commit_success();
+
+    // the admin badge is the first resource created by the hub
+    (
+        commit.new_component_addresses()[0],
+        commit.new_resource_addresses()[0],
+    )
+}
+
+pub fn set_protocol_pause(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    hub_component: ComponentAddress,
+    hub_admin: ResourceAddress,
+    paused: bool,
+) {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(user.account, hub_admin, dec!(1))
+        .call_method(
+            hub_component,
+            "set_protocol_pause",
+            manifest_args!(paused),
+        )
+        .build();
+
+    let receipt = test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    );
+
+    if !receipt.is_commit_success() {
+        println!("{:?}", receipt);
+        panic!("TRANSACTION FAIL");
+    }
+}
+
+pub fn set_collection_pause(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    hub_component: ComponentAddress,
+    hub_admin: ResourceAddress,
+    collection: ResourceAddress,
+    paused: bool,
+) {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(user.account, hub_admin, dec!(1))
+        .call_method(
+            hub_component,
+            "set_collection_pause",
+            manifest_args!(collection, paused),
+        )
+        .build();
+
+    let receipt = test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    );
+
+    if !receipt.is_commit_success() {
+        println!("{:?}", receipt);
+        panic!("TRANSACTION FAIL");
+    }
+}
+
+pub fn is_trading_paused(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    hub_component: ComponentAddress,
+    collection: ResourceAddress,
+) -> bool {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(
+            hub_component,
+            "is_trading_paused",
+            manifest_args!(collection),
+        )
+        .build();
+
+    let receipt = test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    );
+
+    if !receipt.is_commit_success() {
+        println!("{:?}", receipt);
+        panic!("TRANSACTION FAIL");
+    }
+
+    receipt.expect_commit(true).output(1)
+}
+
+pub fn set_outpost_package(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    hub_component: ComponentAddress,
+    hub_admin: ResourceAddress,
+    outpost_package: PackageAddress,
+) {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(user.account, hub_admin, dec!(1))
+        .call_method(
+            hub_component,
+            "set_outpost_package",
+            manifest_args!(outpost_package),
+        )
+        .build();
+
+    let receipt = test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    );
+
+    if !receipt.is_commit_success() {
+        println!("{:?}", receipt);
+        panic!("TRANSACTION FAIL");
+    }
+}
+
+pub fn try_upgrade_outpost(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    hub_component: ComponentAddress,
+    outpost: ComponentAddress,
+) -> TransactionReceipt {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(
+            hub_component,
+            "upgrade_outpost",
+            manifest_args!(user.account, outpost),
+        )
+        .build();
+
+    test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    )
+}
+
+pub fn upgrade_outpost(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    hub_component: ComponentAddress,
+    outpost: ComponentAddress,
+) -> ComponentAddress {
+    let receipt = try_upgrade_outpost(test_runner, user, hub_component, outpost);
+
+    if !receipt.is_commit_success() {
+        println!("{:?}", receipt);
+        panic!("TRANSACTION FAIL");
+    }
+
+    receipt.expect_commit(true).output(1)
+}
+
+pub fn get_outposts(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    hub_component: ComponentAddress,
+    account: ComponentAddress,
+) -> Vec<ComponentAddress> {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(hub_component, "get_outposts", manifest_args!(account))
+        .build();
+
+    let receipt = test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    );
+
+    if !receipt.is_commit_success() {
+        println!("{:?}", receipt);
+        panic!("TRANSACTION FAIL");
+    }
+
+    receipt.expect_commit(true).output(1)
+}
+
+pub fn get_outpost_owner(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    hub_component: ComponentAddress,
+    outpost: ComponentAddress,
+) -> Option<(ComponentAddress, NonFungibleGlobalId)> {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(hub_component, "get_outpost_owner", manifest_args!(outpost))
+        .build();
+
+    let receipt = test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    );
+
+    if !receipt.is_commit_success() {
+        println!("{:?}", receipt);
+        panic!("TRANSACTION FAIL");
+    }
+
+    receipt.expect_commit(true).output(1)
+}
+
+pub fn relink_outpost(
+    test_runner: &mut DefaultLedgerSimulator,
+    old_owner: &User,
+    new_owner: &User,
+    hub_component: ComponentAddress,
+    outpost: ComponentAddress,
+) -> TransactionReceipt {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(
+            hub_component,
+            "relink_outpost",
+            manifest_args!(outpost, old_owner.account, new_owner.account),
+        )
+        .build();
+
+    // both account owners have to sign
+    let receipt = test_runner.execute_manifest(
+        manifest,
+        vec![
+            NonFungibleGlobalId::from_public_key(&old_owner.pubkey),
+            NonFungibleGlobalId::from_public_key(&new_owner.pubkey),
+        ],
+    );
+
+    if !receipt.is_commit_success() {
+        println!("{:?}", receipt);
+        panic!("TRANSACTION FAIL");
+    }
+    receipt
+}
+
+pub fn register_marketplace(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    hub_component: ComponentAddress,
+    hub_admin: ResourceAddress,
+    permission: ResourceAddress,
+    marketplace_component: ComponentAddress,
+    fee_cap: Decimal,
+) {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(user.account, hub_admin, dec!(1))
+        .call_method(
+            hub_component,
+            "register_marketplace",
+            manifest_args!(permission, marketplace_component, fee_cap, user.account),
+        )
+        .build();
+
+    let receipt = test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    );
+
+    if !receipt.is_commit_success() {
+        println!("{:?}", receipt);
+        panic!("TRANSACTION FAIL");
+    }
+}
+
+pub fn get_registered_marketplaces(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    hub_component: ComponentAddress,
+) -> Vec<ResourceAddress> {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(
+            hub_component,
+            "get_registered_marketplaces",
+            manifest_args!(),
+        )
+        .build();
+
+    let receipt = test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    );
+
+    if !receipt.is_commit_success() {
+        println!("{:?}", receipt);
+        panic!("TRANSACTION FAIL");
+    }
+
+    let directory: Vec<(ResourceAddress, MarketplaceRegistration)> =
+        receipt.expect_commit(true).output(1);
+
+    directory
+        .into_iter()
+        .map(|(permission, _)| permission)
+        .collect()
+}
+
+pub fn set_protocol_fee(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    hub_component: ComponentAddress,
+    hub_admin: ResourceAddress,
+    fee: Decimal,
+) -> TransactionReceipt {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(user.account, hub_admin, dec!(1))
+        .call_method(
+            hub_component,
+            "set_protocol_fee",
+            manifest_args!(fee),
+        )
+        .build();
+
+    test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    )
+}
+
+pub fn get_protocol_fee(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    hub_component: ComponentAddress,
+) -> Decimal {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(hub_component, "get_protocol_fee", manifest_args!())
+        .build();
+
+    let receipt = test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    );
+
+    if !receipt.is_commit_success() {
+        println!("{:?}", receipt);
+        panic!("TRANSACTION FAIL");
+    }
+
+    receipt.expect_commit(true).output(1)
+}
+
+pub fn flag_collection(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    hub_component: ComponentAddress,
+    hub_admin: ResourceAddress,
+    collection: ResourceAddress,
+    reason: FlagReason,
+    severity: FlagSeverity,
+) {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(user.account, hub_admin, dec!(1))
+        .call_method(
+            hub_component,
+            "flag_collection",
+            manifest_args!(collection, reason, severity),
+        )
+        .build();
+
+    let receipt = test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    );
+
+    if !receipt.is_commit_success() {
+        println!("{:?}", receipt);
+        panic!("TRANSACTION FAIL");
+    }
+}
+
+pub fn try_clear_collection_flag(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    hub_component: ComponentAddress,
+    badge: ResourceAddress,
+    collection: ResourceAddress,
+) -> TransactionReceipt {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(user.account, badge, dec!(1))
+        .call_method(
+            hub_component,
+            "clear_collection_flag",
+            manifest_args!(collection),
+        )
+        .build();
+
+    test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    )
+}
+
+pub fn set_hub_moderator(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    hub_component: ComponentAddress,
+    hub_admin: ResourceAddress,
+    moderator_badge: ResourceAddress,
+) {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(user.account, hub_admin, dec!(1))
+        .set_role(
+            hub_component,
+            ModuleId::Main,
+            "moderator",
+            rule!(require(moderator_badge)),
+        )
+        .build();
+
+    let receipt = test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    );
+
+    if !receipt.is_commit_success() {
+        println!("{:?}", receipt);
+        panic!("TRANSACTION FAIL");
+    }
+}
+
+pub fn appeal_collection_flag(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    hub_component: ComponentAddress,
+    collection: ResourceAddress,
+    creator_badge: ResourceAddress,
+) -> TransactionReceipt {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(user.account, creator_badge, dec!(1))
+        .pop_from_auth_zone("creator_proof")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(
+                hub_component,
+                "appeal_collection_flag",
+                manifest_args!(collection, lookup.proof("creator_proof")),
+            )
+        })
+        .build();
+
+    test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    )
+}
+
+pub fn try_resolve_appeal(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    hub_component: ComponentAddress,
+    badge: ResourceAddress,
+    collection: ResourceAddress,
+    uphold: bool,
+) -> TransactionReceipt {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(user.account, badge, dec!(1))
+        .call_method(
+            hub_component,
+            "resolve_appeal",
+            manifest_args!(collection, uphold),
+        )
+        .build();
+
+    test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    )
+}
+
+pub fn is_collection_blocked(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    hub_component: ComponentAddress,
+    collection: ResourceAddress,
+) -> bool {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(
+            hub_component,
+            "is_collection_blocked",
+            manifest_args!(collection),
+        )
+        .build();
+
+    let receipt = test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    );
+
+    if !receipt.is_commit_success() {
+        println!("{:?}", receipt);
+        panic!("TRANSACTION FAIL");
+    }
+
+    receipt.expect_commit(true).output(1)
+}
+
+pub fn get_event_component(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    hub_component: ComponentAddress,
+) -> ComponentAddress {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(hub_component, "get_event_component", manifest_args!())
+        .build();
+
+    let receipt = test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    );
+
+    if !receipt.is_commit_success() {
+        println!("{:?}", receipt);
+        panic!("TRANSACTION FAIL");
+    }
+
+    receipt.expect_commit(true).output(1)
+}
+
+pub fn get_floor_price(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    event_component: ComponentAddress,
+    collection: ResourceAddress,
+    currency: ResourceAddress,
+) -> Option<Decimal> {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(
+            event_component,
+            "get_floor_price",
+            manifest_args!(collection, currency),
+        )
+        .build();
+
+    let receipt = test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    );
+
+    if !receipt.is_commit_success() {
+        println!("{:?}", receipt);
+        panic!("TRANSACTION FAIL");
+    }
+
+    receipt.expect_commit(true).output(1)
+}
+
+pub fn get_price_oracle(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    hub_component: ComponentAddress,
+) -> ComponentAddress {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(hub_component, "get_price_oracle", manifest_args!())
+        .build();
+
+    let receipt = test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    );
+
+    if !receipt.is_commit_success() {
+        println!("{:?}", receipt);
+        panic!("TRANSACTION FAIL");
+    }
+
+    receipt.expect_commit(true).output(1)
+}
+
+/// Calls `twap` or `median` on the price oracle.
+pub fn oracle_price(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    price_oracle: ComponentAddress,
+    method: &str,
+    collection: ResourceAddress,
+    currency: ResourceAddress,
+    window_seconds: Option<i64>,
+) -> Option<Decimal> {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(
+            price_oracle,
+            method,
+            manifest_args!(collection, currency, window_seconds),
+        )
+        .build();
+
+    let receipt = test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    );
+
+    if !receipt.is_commit_success() {
+        println!("{:?}", receipt);
+        panic!("TRANSACTION FAIL");
+    }
+
+    receipt.expect_commit(true).output(1)
+}
+
+pub fn try_set_resale_cooldown(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    price_oracle: ComponentAddress,
+    hub_admin: ResourceAddress,
+    cooldown_seconds: i64,
+) -> TransactionReceipt {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(user.account, hub_admin, dec!(1))
+        .call_method(
+            price_oracle,
+            "set_resale_cooldown",
+            manifest_args!(cooldown_seconds),
+        )
+        .build();
+
+    test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    )
+}
@@ -0,0 +1,253 @@
+use outpost::outpost_event::{
+    DappTransfer, DappTransferPayload, OutpostRelinked, OutpostRelinkedPayload, RentalEventPayload,
+    RentalListed, RevenueClaimed, RevenueClaimedPayload, SameOwnerTransfer,
+    SameOwnerTransferPayload,
+};
+use scrypto_test::prelude::*;
+mod common;
+mod creator_manifests;
+mod marketplace_manifests;
+mod misc_manifests;
+mod scenario_manifests;
+mod trader_manifests;
+use common::*;
+use creator_manifests::*;
+use marketplace_manifests::*;
+use misc_manifests::*;
+use scenario_manifests::*;
+use trader_manifests::*;
+
+fn decode_events<T: ScryptoDecode>(
+    test_runner: &DefaultLedgerSimulator,
+    receipt: &TransactionReceipt,
+    name: &str,
+) -> Vec<T> {
+    receipt
+        .expect_commit_success()
+        .application_events
+        .iter()
+        .filter(|(event_type, _)| test_runner.event_name(event_type) == name)
+        .map(|(_, data)| scrypto_decode::<T>(data).unwrap())
+        .collect()
+}
+
+/// Decodes the events of a type that were emitted by the central event component, ignoring any emitted elsewhere.
+fn decode_central_events<T: ScryptoDecode>(
+    test_runner: &DefaultLedgerSimulator,
+    receipt: &TransactionReceipt,
+    event_component: ComponentAddress,
+    name: &str,
+) -> Vec<T> {
+    receipt
+        .expect_commit_success()
+        .application_events
+        .iter()
+        .filter(|(event_type, _)| {
+            test_runner.event_name(event_type) == name
+                && event_type.0
+                    == Emitter::Method(event_component.into_node_id(), ModuleId::Main)
+        })
+        .map(|(_, data)| scrypto_decode::<T>(data).unwrap())
+        .collect()
+}
+
+#[test]
+fn royal_transfers_emit_central_events() {
+    let mut fixture = setup_trading();
+
+    let nft_address = mint_royalty_nfts(&mut fixture, 3).nft_address;
+
+    let TradingFixture {
+        mut test_runner,
+        user,
+        package,
+        trader_component,
+        ..
+    } = fixture;
+
+    // a transfer between accounts of the same owner names the recipient
+    let receipt = same_owner_royal_transfer(
+        &mut test_runner,
+        &user,
+        trader_component,
+        nft_address,
+        NonFungibleLocalId::integer(0),
+        user.account,
+    );
+
+    let events: Vec<SameOwnerTransfer> =
+        decode_events(&test_runner, &receipt, "SameOwnerTransfer");
+
+    assert_eq!(events.len(), 1);
+
+    match &events[0].payload {
+        SameOwnerTransferPayload::V1 {
+            outpost_component,
+            nft_ids,
+            recipient,
+        } => {
+            assert_eq!(*outpost_component, trader_component);
+            assert_eq!(*nft_ids, vec![create_global_id(nft_address, 0)]);
+            assert_eq!(*recipient, user.account);
+        }
+    }
+
+    // a transfer to a dApp names the dApp and the method it was deposited with
+    let dapp_component = create_generic_dapp(&mut test_runner, &user, package);
+
+    let receipt = transfer_royal_nft_to_component(
+        &mut test_runner,
+        &user,
+        trader_component,
+        nft_address,
+        NonFungibleLocalId::integer(1),
+        dapp_component,
+        "deposit_royalty_nft".to_string(),
+    );
+
+    let events: Vec<DappTransfer> = decode_events(&test_runner, &receipt, "DappTransfer");
+
+    assert_eq!(events.len(), 1);
+
+    match &events[0].payload {
+        DappTransferPayload::V1 {
+            outpost_component,
+            nft_ids,
+            dapp,
+            method,
+        } => {
+            assert_eq!(*outpost_component, trader_component);
+            assert_eq!(*nft_ids, vec![create_global_id(nft_address, 1)]);
+            assert_eq!(*dapp, dapp_component);
+            assert_eq!(method, "deposit_royalty_nft");
+        }
+    }
+
+    assert_eq!(
+        test_runner.get_component_balance(dapp_component, nft_address),
+        dec!(1)
+    );
+}
+
+#[test]
+fn sales_rentals_and_relinks_emit_central_events() {
+    let mut fixture = setup_trading();
+
+    let nft_address = mint_nfts(&mut fixture, 2).nft_address;
+
+    let TradingFixture {
+        mut test_runner,
+        user,
+        open_hub_component,
+        trader_key_resource,
+        trader_key_local,
+        trader_component,
+        marketplace_component,
+        marketplace_key,
+        ..
+    } = fixture;
+
+    let buyer = make_user(&mut test_runner, Some("buyer"));
+
+    let new_user = make_user(&mut test_runner, Some("user2"));
+
+    let event_component = get_event_component(&mut test_runner, &user, open_hub_component);
+
+    list(
+        &mut test_runner,
+        &user,
+        trader_component,
+        trader_key_resource,
+        trader_key_local.clone(),
+        nft_address,
+        NonFungibleLocalId::integer(0),
+        dec!(100),
+        None,
+        vec![marketplace_key],
+    );
+
+    // the seller's proceeds are reported as revenue paid out to the linked account
+    let receipt = try_purchase_multi_listing(
+        &mut test_runner,
+        &buyer,
+        marketplace_component,
+        vec![(trader_component, create_global_id(nft_address, 0), dec!(100))],
+        dec!(100),
+    );
+
+    let events: Vec<RevenueClaimed> =
+        decode_central_events(&test_runner, &receipt, event_component, "RevenueClaimed");
+
+    assert_eq!(events.len(), 1);
+
+    match &events[0].payload {
+        RevenueClaimedPayload::V1 {
+            outpost_component,
+            recipient,
+            currency,
+            amount,
+        } => {
+            assert_eq!(*outpost_component, trader_component);
+            assert_eq!(*recipient, user.account);
+            assert_eq!(*currency, XRD);
+            // less the 2% marketplace fee
+            assert_eq!(*amount, dec!(98));
+        }
+    }
+
+    // rental listings are announced by the event component rather than the Outpost
+    let receipt = try_list_rental(
+        &mut test_runner,
+        &user,
+        trader_component,
+        trader_key_resource,
+        trader_key_local,
+        nft_address,
+        NonFungibleLocalId::integer(1),
+        dec!(10),
+        24 * 60 * 60,
+        vec![marketplace_key],
+    );
+
+    let events: Vec<RentalListed> =
+        decode_central_events(&test_runner, &receipt, event_component, "RentalListed");
+
+    assert_eq!(events.len(), 1);
+
+    match &events[0].payload {
+        RentalEventPayload::V1 {
+            outpost_account,
+            nft_id,
+            ..
+        } => {
+            assert_eq!(*outpost_account, trader_component);
+            assert_eq!(*nft_id, create_global_id(nft_address, 1));
+        }
+    }
+
+    // as are relinks made through the hub
+    let receipt = relink_outpost(
+        &mut test_runner,
+        &user,
+        &new_user,
+        open_hub_component,
+        trader_component,
+    );
+
+    let events: Vec<OutpostRelinked> =
+        decode_central_events(&test_runner, &receipt, event_component, "OutpostRelinked");
+
+    assert_eq!(events.len(), 1);
+
+    match &events[0].payload {
+        OutpostRelinkedPayload::V1 {
+            outpost_component,
+            old_account,
+            new_account,
+        } => {
+            assert_eq!(*outpost_component, trader_component);
+            assert_eq!(*old_account, user.account);
+            assert_eq!(*new_account, new_user.account);
+        }
+    }
+}
@@ -0,0 +1,204 @@
+use scrypto::runtime::Clock;
+use scrypto_test::prelude::*;
+mod common;
+mod creator_manifests;
+mod marketplace_manifests;
+mod misc_manifests;
+mod scenario_manifests;
+mod trader_manifests;
+use common::*;
+use creator_manifests::*;
+use marketplace_manifests::*;
+use misc_manifests::*;
+use scenario_manifests::*;
+use trader_manifests::*;
+
+#[test]
+fn upgrade_outpost_moves_listings_under_the_same_key() {
+    let (
+        TradingFixture {
+            mut test_runner,
+            user,
+            package,
+            open_hub_component,
+            hub_admin,
+            trader_key_resource,
+            trader_key_local,
+            trader_component,
+            ..
+        },
+        TestCollection { nft_address, .. },
+    ) = setup_listed_royalty_nft();
+
+    // the same package stands in for a new release
+    set_outpost_package(&mut test_runner, &user, open_hub_component, hub_admin, package);
+
+    let new_outpost = upgrade_outpost(&mut test_runner, &user, open_hub_component, trader_component);
+
+    assert_ne!(new_outpost, trader_component);
+
+    assert_eq!(
+        get_outpost(&mut test_runner, &user, open_hub_component, user.account),
+        Some(new_outpost)
+    );
+
+    assert!(!is_registered_outpost(
+        &mut test_runner,
+        &user,
+        open_hub_component,
+        trader_component
+    ));
+
+    // the listing was recreated on the new Outpost and the existing key still controls it
+    cancel_royal_listing(
+        &mut test_runner,
+        &user,
+        new_outpost,
+        trader_key_resource,
+        trader_key_local,
+        NonFungibleGlobalId::new(nft_address, NonFungibleLocalId::integer(0)),
+    );
+
+    let balance = test_runner.get_component_balance(user.account, nft_address);
+
+    assert_eq!(balance, dec!(3));
+}
+
+#[test]
+fn upgrade_carries_the_blocklist_and_rentals_once_active_loans_are_repaid() {
+    let mut fixture = setup_trading();
+
+    let nft_address = mint_nfts(&mut fixture, 2).nft_address;
+
+    let TradingFixture {
+        mut test_runner,
+        user,
+        package,
+        open_hub_component,
+        hub_admin,
+        trader_key_resource,
+        trader_key_local,
+        trader_component,
+        marketplace_key,
+        ..
+    } = fixture;
+
+    let lender = make_user(&mut test_runner, Some("lender"));
+
+    list(
+        &mut test_runner,
+        &user,
+        trader_component,
+        trader_key_resource,
+        trader_key_local.clone(),
+        nft_address,
+        NonFungibleLocalId::integer(0),
+        dec!(100),
+        None,
+        vec![marketplace_key],
+    );
+
+    let rental_badge = test_runner.create_fungible_resource(dec!(1), 0, user.account);
+
+    list_rental(
+        &mut test_runner,
+        &user,
+        trader_component,
+        trader_key_resource,
+        trader_key_local.clone(),
+        nft_address,
+        NonFungibleLocalId::integer(1),
+        dec!(10),
+        60,
+        vec![rental_badge],
+    );
+
+    block_buyer(
+        &mut test_runner,
+        &user,
+        trader_component,
+        trader_key_resource,
+        trader_key_local.clone(),
+        BlockedBuyer::Recipient(lender.account),
+        true,
+    );
+
+    offer_loan(
+        &mut test_runner,
+        &lender,
+        trader_component,
+        dec!(50),
+        dec!(60),
+        60,
+        vec![(create_global_id(nft_address, 0), dec!(50))],
+    );
+
+    accept_loan(
+        &mut test_runner,
+        &user,
+        trader_component,
+        trader_key_resource,
+        trader_key_local.clone(),
+        1,
+    );
+
+    // an offer nobody has accepted doesn't hold anything back
+    offer_loan(
+        &mut test_runner,
+        &lender,
+        trader_component,
+        dec!(1),
+        dec!(2),
+        60,
+        vec![(create_global_id(nft_address, 1), dec!(1))],
+    );
+
+    set_outpost_package(&mut test_runner, &user, open_hub_component, hub_admin, package);
+
+    // the accepted loan locks its collateral in this Outpost, so it has to be repaid first
+    try_upgrade_outpost(&mut test_runner, &user, open_hub_component, trader_component)
+        .expect_commit_failure();
+
+    try_repay_loan(
+        &mut test_runner,
+        &user,
+        trader_component,
+        trader_key_resource,
+        trader_key_local,
+        1,
+        dec!(60),
+    )
+    .expect_commit_success();
+
+    let new_outpost = upgrade_outpost(&mut test_runner, &user, open_hub_component, trader_component);
+
+    assert!(is_buyer_blocked(
+        &mut test_runner,
+        &user,
+        new_outpost,
+        BlockedBuyer::Recipient(lender.account)
+    ));
+
+    assert!(!is_buyer_blocked(
+        &mut test_runner,
+        &user,
+        trader_component,
+        BlockedBuyer::Recipient(lender.account)
+    ));
+
+    // the rental listing was recreated on the new Outpost
+    try_rent(
+        &mut test_runner,
+        &user,
+        new_outpost,
+        create_global_id(nft_address, 1),
+        dec!(10),
+        rental_badge,
+    )
+    .expect_commit_success();
+
+    // the repayment and the open offer are still claimed with their loan notes on the old Outpost
+    try_claim_loan(&mut test_runner, &lender, trader_component, 1, None).expect_commit_success();
+
+    try_claim_loan(&mut test_runner, &lender, trader_component, 2, None).expect_commit_success();
+}
@@ -0,0 +1,202 @@
+use scrypto_test::prelude::*;
+mod common;
+mod creator_manifests;
+mod marketplace_manifests;
+mod misc_manifests;
+mod scenario_manifests;
+mod trader_manifests;
+use common::*;
+use creator_manifests::*;
+use marketplace_manifests::*;
+use misc_manifests::*;
+use scenario_manifests::*;
+use trader_manifests::*;
+
+const ONE_DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+fn count_events(
+    test_runner: &DefaultLedgerSimulator,
+    receipt: &TransactionReceipt,
+    name: &str,
+) -> usize {
+    receipt
+        .expect_commit_success()
+        .application_events
+        .iter()
+        .filter(|(event_type, _)| test_runner.event_name(event_type) == name)
+        .count()
+}
+
+#[test]
+fn oracle_prices_sales_and_excludes_wash_trades() {
+    let mut fixture = setup_trading();
+
+    let nft_address = mint_nfts(&mut fixture, 5).nft_address;
+
+    let TradingFixture {
+        mut test_runner,
+        user,
+        open_hub_component,
+        trader_key_resource,
+        trader_key_local,
+        trader_component,
+        marketplace_component,
+        marketplace_key,
+        ..
+    } = fixture;
+
+    let buyer = make_user(&mut test_runner, Some("buyer"));
+
+    let price_oracle = get_price_oracle(&mut test_runner, &user, open_hub_component);
+
+    let (buyer_key_resource, buyer_key_local, buyer_component) =
+        create_outpost(&mut test_runner, &buyer, open_hub_component);
+
+    let prices = [dec!(100), dec!(200), dec!(300), dec!(400), dec!(500)];
+
+    for (id, price) in prices.iter().enumerate() {
+        list(
+            &mut test_runner,
+            &user,
+            trader_component.clone(),
+            trader_key_resource.clone(),
+            trader_key_local.clone(),
+            nft_address.clone(),
+            NonFungibleLocalId::integer(id as u64),
+            *price,
+            None,
+            vec![marketplace_key.clone()],
+        );
+    }
+
+    let order = |id: u64, price: Decimal| {
+        vec![(
+            trader_component.clone(),
+            create_global_id(nft_address.clone(), id),
+            price,
+        )]
+    };
+
+    let start = ONE_DAY_MS;
+
+    // sales at 0s, 1000s and 2000s
+    for (round, id) in [(2u64, 0u64), (3, 1), (4, 2)] {
+        test_runner.advance_to_round_at_timestamp(
+            Round::of(round),
+            start + (id as i64) * 1_000_000,
+        );
+
+        let receipt = try_purchase_multi_listing(
+            &mut test_runner,
+            &buyer,
+            marketplace_component,
+            order(id, prices[id as usize]),
+            prices[id as usize],
+        );
+
+        assert_eq!(count_events(&test_runner, &receipt, "SaleObserved"), 1);
+    }
+
+    test_runner.advance_to_round_at_timestamp(Round::of(5), start + 4_000_000);
+
+    // 100 stood for 1000s, 200 for 1000s and 300 for the last 2000s
+    assert_eq!(
+        oracle_price(&mut test_runner, &user, price_oracle, "twap", nft_address, XRD, None),
+        Some(dec!(225))
+    );
+
+    assert_eq!(
+        oracle_price(&mut test_runner, &user, price_oracle, "median", nft_address, XRD, None),
+        Some(dec!(200))
+    );
+
+    // within the last 1000s only the latest sale stood, and no sale happened
+    assert_eq!(
+        oracle_price(&mut test_runner, &user, price_oracle, "twap", nft_address, XRD, Some(1000)),
+        Some(dec!(300))
+    );
+
+    assert_eq!(
+        oracle_price(&mut test_runner, &user, price_oracle, "median", nft_address, XRD, Some(1000)),
+        None
+    );
+
+    // buying your own listing is a wash trade
+    let receipt = try_purchase_multi_listing(
+        &mut test_runner,
+        &user,
+        marketplace_component,
+        order(3, dec!(400)),
+        dec!(400),
+    );
+
+    assert_eq!(count_events(&test_runner, &receipt, "SaleExcluded"), 1);
+    assert_eq!(count_events(&test_runner, &receipt, "SaleObserved"), 0);
+
+    // so is buying for another account with the same owner as the seller
+    let sellers_other_account =
+        test_runner.new_account_advanced(OwnerRole::Fixed(rule!(require(user.nfgid.clone()))));
+
+    let receipt = try_purchase_multi_listing_for(
+        &mut test_runner,
+        &buyer,
+        marketplace_component,
+        order(4, dec!(500)),
+        dec!(500),
+        sellers_other_account,
+    );
+
+    assert_eq!(count_events(&test_runner, &receipt, "SaleExcluded"), 1);
+    assert_eq!(count_events(&test_runner, &receipt, "SaleObserved"), 0);
+
+    // reselling an NFT within the cooldown is excluded
+    list(
+        &mut test_runner,
+        &buyer,
+        buyer_component,
+        buyer_key_resource,
+        buyer_key_local,
+        nft_address.clone(),
+        NonFungibleLocalId::integer(0),
+        dec!(1000),
+        None,
+        vec![marketplace_key.clone()],
+    );
+
+    let receipt = try_purchase_multi_listing(
+        &mut test_runner,
+        &user,
+        marketplace_component,
+        vec![(
+            buyer_component,
+            create_global_id(nft_address.clone(), 0),
+            dec!(1000),
+        )],
+        dec!(1000),
+    );
+
+    assert_eq!(count_events(&test_runner, &receipt, "SaleExcluded"), 1);
+    assert_eq!(count_events(&test_runner, &receipt, "SaleObserved"), 0);
+
+    // none of the excluded sales moved the prices
+    assert_eq!(
+        oracle_price(&mut test_runner, &user, price_oracle, "median", nft_address, XRD, None),
+        Some(dec!(200))
+    );
+}
+
+#[test]
+fn resale_cooldown_cannot_be_negative() {
+    let (mut test_runner, user, package) = setup_for_test();
+
+    let (open_hub_component, hub_admin) =
+        instantiate_open_hub_with_admin(&mut test_runner, &user, package);
+
+    let price_oracle = get_price_oracle(&mut test_runner, &user, open_hub_component);
+
+    try_set_resale_cooldown(&mut test_runner, &user, price_oracle, hub_admin, -1)
+        .expect_commit_failure();
+
+    try_set_resale_cooldown(&mut test_runner, &user, price_oracle, hub_admin, 0)
+        .expect_commit_success();
+}
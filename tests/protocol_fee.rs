@@ -0,0 +1,183 @@
+use scrypto_test::prelude::*;
+mod common;
+mod creator_manifests;
+mod marketplace_manifests;
+mod misc_manifests;
+mod scenario_manifests;
+mod trader_manifests;
+use common::*;
+use creator_manifests::*;
+use marketplace_manifests::*;
+use misc_manifests::*;
+use scenario_manifests::*;
+use trader_manifests::*;
+
+const ONE_DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+#[test]
+fn protocol_fee_increases_are_timelocked() {
+    let (mut test_runner, user, package) = setup_for_test();
+
+    let (open_hub_component, hub_admin) =
+        instantiate_open_hub_with_admin(&mut test_runner, &user, package);
+
+    assert_eq!(
+        get_protocol_fee(&mut test_runner, &user, open_hub_component),
+        dec!(0)
+    );
+
+    // above the cap
+    set_protocol_fee(&mut test_runner, &user, open_hub_component, hub_admin, dec!(0.1))
+        .expect_commit_failure();
+
+    set_protocol_fee(&mut test_runner, &user, open_hub_component, hub_admin, dec!(0.01))
+        .expect_commit_success();
+
+    // the increase is only scheduled
+    assert_eq!(
+        get_protocol_fee(&mut test_runner, &user, open_hub_component),
+        dec!(0)
+    );
+
+    test_runner.advance_to_round_at_timestamp(Round::of(2), 8 * ONE_DAY_MS);
+
+    assert_eq!(
+        get_protocol_fee(&mut test_runner, &user, open_hub_component),
+        dec!(0.01)
+    );
+
+    // decreases apply straight away
+    set_protocol_fee(&mut test_runner, &user, open_hub_component, hub_admin, dec!(0.005))
+        .expect_commit_success();
+
+    assert_eq!(
+        get_protocol_fee(&mut test_runner, &user, open_hub_component),
+        dec!(0.005)
+    );
+}
+
+#[test]
+fn protocol_fee_is_charged_through_any_marketplace() {
+    // the fixture's marketplace is not registered in the hub
+    let mut fixture = setup_trading();
+
+    let nft_address = mint_nfts(&mut fixture, 1).nft_address;
+
+    let TradingFixture {
+        mut test_runner,
+        user,
+        open_hub_component,
+        hub_admin,
+        trader_key_resource,
+        trader_key_local,
+        trader_component,
+        marketplace_component,
+        marketplace_key,
+        ..
+    } = fixture;
+
+    let buyer = make_user(&mut test_runner, Some("buyer"));
+
+    set_protocol_fee(&mut test_runner, &user, open_hub_component, hub_admin, dec!(0.05))
+        .expect_commit_success();
+
+    test_runner.advance_to_round_at_timestamp(Round::of(2), 8 * ONE_DAY_MS);
+
+    list(
+        &mut test_runner,
+        &user,
+        trader_component,
+        trader_key_resource,
+        trader_key_local,
+        nft_address,
+        NonFungibleLocalId::integer(0),
+        dec!(100),
+        None,
+        vec![marketplace_key],
+    );
+
+    let treasury_before = test_runner.get_component_balance(open_hub_component, XRD);
+
+    try_purchase_multi_listing(
+        &mut test_runner,
+        &buyer,
+        marketplace_component,
+        vec![(trader_component, create_global_id(nft_address, 0), dec!(100))],
+        dec!(100),
+    )
+    .expect_commit_success();
+
+    // 5% of the sale price
+    assert_eq!(
+        test_runner.get_component_balance(open_hub_component, XRD) - treasury_before,
+        dec!(5)
+    );
+}
+
+#[test]
+fn protocol_fee_is_limited_to_what_royalties_and_marketplace_fees_leave() {
+    let mut fixture = setup_trading();
+
+    let mut royalty_config = defaults_royalty_config(fixture.depositer_badge);
+
+    royalty_config.royaly_conig.royalty_percent = dec!(0.5);
+
+    let nft_address = mint_royalty_nfts_with_config(&mut fixture, royalty_config, 3).nft_address;
+
+    let TradingFixture {
+        mut test_runner,
+        user,
+        package,
+        open_hub_component,
+        hub_admin,
+        trader_key_resource,
+        trader_key_local,
+        trader_component,
+        ..
+    } = fixture;
+
+    // together with the 50% royalty this marketplace leaves 2% of the price, less than the protocol fee
+    let (expensive_marketplace_component, expensive_marketplace_key) =
+        create_marketplace(&mut test_runner, &user, package, dec!(0.48));
+
+    set_protocol_fee(&mut test_runner, &user, open_hub_component, hub_admin, dec!(0.05))
+        .expect_commit_success();
+
+    test_runner.advance_to_round_at_timestamp(Round::of(2), 8 * ONE_DAY_MS);
+
+    list_royalty_nft(
+        &mut test_runner,
+        &user,
+        trader_component,
+        trader_key_resource,
+        trader_key_local,
+        nft_address,
+        NonFungibleLocalId::integer(0),
+        dec!(100),
+        None,
+        vec![expensive_marketplace_key],
+    );
+
+    let transient_token_address =
+        get_transient_token_address(&mut test_runner, &user, trader_component);
+
+    let treasury_before = test_runner.get_component_balance(open_hub_component, XRD);
+
+    try_purchase_royalty_nft(
+        &mut test_runner,
+        &user,
+        expensive_marketplace_component,
+        trader_component,
+        create_global_id(nft_address, 0),
+        dec!(100),
+        None,
+        transient_token_address,
+    )
+    .expect_commit_success();
+
+    // the fee takes the 2 left over rather than failing the purchase for the full 5
+    assert_eq!(
+        test_runner.get_component_balance(open_hub_component, XRD) - treasury_before,
+        dec!(2)
+    );
+}
@@ -0,0 +1,136 @@
+use scrypto::runtime::Clock;
+use scrypto_test::prelude::*;
+mod common;
+mod creator_manifests;
+mod marketplace_manifests;
+mod misc_manifests;
+mod scenario_manifests;
+mod trader_manifests;
+use common::*;
+use creator_manifests::*;
+use marketplace_manifests::*;
+use misc_manifests::*;
+use scenario_manifests::*;
+use trader_manifests::*;
+
+#[test]
+fn pause_blocks_listing_but_not_cancelling() {
+    let mut fixture = setup_trading();
+
+    let nft_address = mint_royalty_nfts(&mut fixture, 3).nft_address;
+
+    let TradingFixture {
+        mut test_runner,
+        user,
+        open_hub_component,
+        hub_admin,
+        trader_key_resource,
+        trader_key_local,
+        trader_component,
+        marketplace_key,
+        ..
+    } = fixture;
+
+    set_protocol_pause(&mut test_runner, &user, open_hub_component, hub_admin, true);
+
+    assert!(is_trading_paused(
+        &mut test_runner,
+        &user,
+        open_hub_component,
+        nft_address
+    ));
+
+    // listing while the protocol is paused must fail
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(
+            user.account,
+            "create_proof_of_non_fungibles",
+            manifest_args!(trader_key_resource, indexset![trader_key_local.clone()]),
+        )
+        .call_method(
+            user.account,
+            "withdraw_non_fungibles",
+            manifest_args!(nft_address, indexset![NonFungibleLocalId::integer(0)]),
+        )
+        .take_all_from_worktop(nft_address, "listing")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(
+                trader_component,
+                "royal_list",
+                manifest_args!(
+                    lookup.bucket("listing"),
+                    dec!(100),
+                    XRD,
+                    vec![marketplace_key.clone()],
+                    None::<Instant>,
+                    false,
+                ),
+            )
+        })
+        .call_method(
+            user.account,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+
+    let receipt = test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    );
+
+    receipt.expect_commit_failure();
+
+    set_protocol_pause(&mut test_runner, &user, open_hub_component, hub_admin, false);
+
+    list_royalty_nft(
+        &mut test_runner,
+        &user,
+        trader_component.clone(),
+        trader_key_resource.clone(),
+        trader_key_local.clone(),
+        nft_address.clone(),
+        NonFungibleLocalId::integer(0),
+        dec!(100),
+        None,
+        vec![marketplace_key.clone()],
+    );
+
+    set_collection_pause(
+        &mut test_runner,
+        &user,
+        open_hub_component,
+        hub_admin,
+        nft_address,
+        true,
+    );
+
+    assert!(is_trading_paused(
+        &mut test_runner,
+        &user,
+        open_hub_component,
+        nft_address
+    ));
+
+    assert!(!is_trading_paused(
+        &mut test_runner,
+        &user,
+        open_hub_component,
+        XRD
+    ));
+
+    // cancelling stays open while the collection is paused
+    cancel_royal_listing(
+        &mut test_runner,
+        &user,
+        trader_component,
+        trader_key_resource,
+        trader_key_local,
+        NonFungibleGlobalId::new(nft_address, NonFungibleLocalId::integer(0)),
+    );
+
+    let balance = test_runner.get_component_balance(user.account, nft_address);
+
+    assert_eq!(balance, dec!(3));
+}
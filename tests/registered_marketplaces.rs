@@ -0,0 +1,157 @@
+use scrypto_test::prelude::*;
+mod common;
+mod creator_manifests;
+mod marketplace_manifests;
+mod misc_manifests;
+mod scenario_manifests;
+mod trader_manifests;
+use common::*;
+use creator_manifests::*;
+use marketplace_manifests::*;
+use misc_manifests::*;
+use scenario_manifests::*;
+use trader_manifests::*;
+
+#[test]
+fn registered_marketplaces_can_buy_listings_open_to_them() {
+    let mut fixture = setup_trading();
+
+    let nft_address = mint_nfts(&mut fixture, 2).nft_address;
+
+    let TradingFixture {
+        mut test_runner,
+        user,
+        open_hub_component,
+        hub_admin,
+        trader_key_resource,
+        trader_key_local,
+        trader_component,
+        marketplace_component,
+        marketplace_key,
+        ..
+    } = fixture;
+
+    let buyer = make_user(&mut test_runner, Some("buyer"));
+
+    register_marketplace(
+        &mut test_runner,
+        &user,
+        open_hub_component,
+        hub_admin,
+        marketplace_key,
+        marketplace_component,
+        dec!(0.05),
+    );
+
+    let private_badge = test_runner.create_fungible_resource(dec!(1), 0, user.account);
+
+    // neither listing names the marketplace, but only the second is open to every registered marketplace
+    for (local_id, registered_marketplaces) in [(0, false), (1, true)] {
+        list_from(
+            &mut test_runner,
+            &user,
+            trader_component,
+            trader_key_resource,
+            trader_key_local.clone(),
+            nft_address,
+            NonFungibleLocalId::integer(local_id),
+            dec!(100),
+            None,
+            vec![private_badge],
+            registered_marketplaces,
+        );
+    }
+
+    try_purchase_multi_listing(
+        &mut test_runner,
+        &buyer,
+        marketplace_component,
+        vec![(trader_component, create_global_id(nft_address, 0), dec!(100))],
+        dec!(100),
+    )
+    .expect_commit_failure();
+
+    try_purchase_multi_listing(
+        &mut test_runner,
+        &buyer,
+        marketplace_component,
+        vec![(trader_component, create_global_id(nft_address, 1), dec!(100))],
+        dec!(100),
+    )
+    .expect_commit_success();
+
+    assert_eq!(
+        test_runner.get_component_balance(buyer.account, nft_address),
+        dec!(1)
+    );
+}
+
+#[test]
+fn registered_marketplaces_can_rent_out_rentals_open_to_them() {
+    let mut fixture = setup_trading();
+
+    let nft_address = mint_nfts(&mut fixture, 2).nft_address;
+
+    let TradingFixture {
+        mut test_runner,
+        user,
+        open_hub_component,
+        hub_admin,
+        trader_key_resource,
+        trader_key_local,
+        trader_component,
+        marketplace_component,
+        marketplace_key,
+        ..
+    } = fixture;
+
+    register_marketplace(
+        &mut test_runner,
+        &user,
+        open_hub_component,
+        hub_admin,
+        marketplace_key,
+        marketplace_component,
+        dec!(0.05),
+    );
+
+    let private_badge = test_runner.create_fungible_resource(dec!(1), 0, user.account);
+
+    // as with sale listings, only the second rental is open to every registered marketplace
+    for (local_id, registered_marketplaces) in [(0, false), (1, true)] {
+        try_list_rental_from(
+            &mut test_runner,
+            &user,
+            trader_component,
+            trader_key_resource,
+            trader_key_local.clone(),
+            nft_address,
+            NonFungibleLocalId::integer(local_id),
+            dec!(10),
+            60,
+            vec![private_badge],
+            registered_marketplaces,
+        )
+        .expect_commit_success();
+    }
+
+    try_rent(
+        &mut test_runner,
+        &user,
+        trader_component,
+        create_global_id(nft_address, 0),
+        dec!(10),
+        marketplace_key,
+    )
+    .expect_commit_failure();
+
+    try_rent(
+        &mut test_runner,
+        &user,
+        trader_component,
+        create_global_id(nft_address, 1),
+        dec!(10),
+        marketplace_key,
+    )
+    .expect_commit_success();
+}
@@ -0,0 +1,116 @@
+use scrypto_test::prelude::*;
+mod common;
+mod creator_manifests;
+mod marketplace_manifests;
+mod misc_manifests;
+mod scenario_manifests;
+mod trader_manifests;
+use common::*;
+use creator_manifests::*;
+use marketplace_manifests::*;
+use misc_manifests::*;
+use scenario_manifests::*;
+use trader_manifests::*;
+
+const ONE_DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+#[test]
+fn rental_royalty_uses_the_royalty_bands() {
+    let mut fixture = setup_trading();
+
+    let TestCollection {
+        component: royalty_nft_component,
+        creator_key,
+        nft_address,
+    } = mint_royalty_nfts(&mut fixture, 3);
+
+    let TradingFixture {
+        mut test_runner,
+        user,
+        trader_key_resource,
+        trader_key_local,
+        trader_component,
+        ..
+    } = fixture;
+
+    let renter = make_user(&mut test_runner, Some("renter"));
+
+    // 10% by default, 20% from a price of 5 XRD
+    set_royalty_bands(
+        &mut test_runner,
+        &user,
+        royalty_nft_component,
+        creator_key,
+        XRD,
+        vec![
+            RoyaltyBand {
+                from_price: dec!(0),
+                royalty_percent: dec!(0.1),
+            },
+            RoyaltyBand {
+                from_price: dec!(5),
+                royalty_percent: dec!(0.2),
+            },
+        ],
+    )
+    .expect_commit_success();
+
+    // a private rental badge held by the renter
+    let rental_badge = test_runner.create_fungible_resource(dec!(1), 0, renter.account);
+
+    list_rental(
+        &mut test_runner,
+        &user,
+        trader_component,
+        trader_key_resource,
+        trader_key_local,
+        nft_address,
+        NonFungibleLocalId::integer(0),
+        dec!(10),
+        ONE_DAY_MS / 1000,
+        vec![rental_badge],
+    );
+
+    let nfgid = create_global_id(nft_address, 0);
+
+    test_runner.advance_to_round_at_timestamp(Round::of(2), ONE_DAY_MS);
+
+    let royalties_before = test_runner.get_component_balance(royalty_nft_component, XRD);
+
+    try_rent(
+        &mut test_runner,
+        &renter,
+        trader_component,
+        nfgid.clone(),
+        dec!(10),
+        rental_badge,
+    )
+    .expect_commit_success();
+
+    let royalties_after = test_runner.get_component_balance(royalty_nft_component, XRD);
+
+    assert_eq!(royalties_after - royalties_before, dec!(2));
+
+    // the NFT can't be rented again until the rental has expired
+    try_rent(
+        &mut test_runner,
+        &renter,
+        trader_component,
+        nfgid.clone(),
+        dec!(10),
+        rental_badge,
+    )
+    .expect_commit_failure();
+
+    test_runner.advance_to_round_at_timestamp(Round::of(3), 3 * ONE_DAY_MS);
+
+    try_rent(
+        &mut test_runner,
+        &renter,
+        trader_component,
+        nfgid,
+        dec!(10),
+        rental_badge,
+    )
+    .expect_commit_success();
+}
@@ -143,3 +143,194 @@ fn list_and_purchase_bulk_royalty_nft() {
         transient_token_address,
     );
 }
+
+#[test]
+fn bulk_purchase_pays_royalty_on_each_price() {
+    let mut fixture = setup_trading();
+
+    let TestCollection {
+        component: royalty_nft_component,
+        creator_key,
+        nft_address,
+    } = mint_royalty_nfts(&mut fixture, 3);
+
+    let TradingFixture {
+        mut test_runner,
+        user,
+        trader_key_resource,
+        trader_key_local,
+        trader_component,
+        marketplace_component,
+        marketplace_key,
+        ..
+    } = fixture;
+
+    let buyer = make_user(&mut test_runner, Some("buyer"));
+
+    // 10% below a price of 100 and 20% from 100 upwards
+    set_royalty_bands(
+        &mut test_runner,
+        &user,
+        royalty_nft_component,
+        creator_key,
+        XRD,
+        vec![
+            RoyaltyBand {
+                from_price: dec!(0),
+                royalty_percent: dec!(0.1),
+            },
+            RoyaltyBand {
+                from_price: dec!(100),
+                royalty_percent: dec!(0.2),
+            },
+        ],
+    )
+    .expect_commit_success();
+
+    royal_multi_list(
+        &mut test_runner,
+        &user,
+        vec![
+            (create_global_id(nft_address, 0), dec!(50)),
+            (create_global_id(nft_address, 1), dec!(150)),
+        ],
+        trader_component,
+        trader_key_resource,
+        trader_key_local,
+        nft_address,
+        NonFungibleLocalId::integer(0),
+        None,
+        vec![marketplace_key],
+    );
+
+    let transient_token_address =
+        get_transient_token_address(&mut test_runner, &user, trader_component);
+
+    let royalties_before = test_runner.get_component_balance(royalty_nft_component, XRD);
+
+    purchase_multi_royalty_nft(
+        &mut test_runner,
+        &buyer,
+        vec![
+            (trader_component, create_global_id(nft_address, 0), dec!(50)),
+            (trader_component, create_global_id(nft_address, 1), dec!(150)),
+        ],
+        dec!(200),
+        marketplace_component,
+        trader_component,
+        None,
+        transient_token_address,
+    );
+
+    // 5 on the first NFT and 30 on the second - splitting the payment evenly would have charged 20% on both
+    assert_eq!(
+        test_runner.get_component_balance(royalty_nft_component, XRD) - royalties_before,
+        dec!(35)
+    );
+}
+
+#[test]
+fn bulk_purchase_clears_the_minimum_royalty_for_each_nft() {
+    let mut fixture = setup_trading();
+
+    let TestCollection {
+        component: royalty_nft_component,
+        creator_key,
+        nft_address,
+    } = mint_royalty_nfts(&mut fixture, 3);
+
+    let TradingFixture {
+        mut test_runner,
+        user,
+        trader_key_resource,
+        trader_key_local,
+        trader_component,
+        marketplace_component,
+        marketplace_key,
+        ..
+    } = fixture;
+
+    let buyer = make_user(&mut test_runner, Some("buyer"));
+
+    // a flat 10%, so the two NFTs below pay 5 and 15
+    set_royalty_bands(
+        &mut test_runner,
+        &user,
+        royalty_nft_component,
+        creator_key,
+        XRD,
+        vec![RoyaltyBand {
+            from_price: dec!(0),
+            royalty_percent: dec!(0.1),
+        }],
+    )
+    .expect_commit_success();
+
+    enable_minimum_royalties(&mut test_runner, &user, royalty_nft_component, creator_key);
+
+    set_minimum_royalty_amount(
+        &mut test_runner,
+        &user,
+        royalty_nft_component,
+        creator_key,
+        XRD,
+        dec!(12),
+    );
+
+    royal_multi_list(
+        &mut test_runner,
+        &user,
+        vec![
+            (create_global_id(nft_address, 0), dec!(50)),
+            (create_global_id(nft_address, 1), dec!(150)),
+        ],
+        trader_component,
+        trader_key_resource,
+        trader_key_local,
+        nft_address,
+        NonFungibleLocalId::integer(0),
+        None,
+        vec![marketplace_key],
+    );
+
+    let transient_token_address =
+        get_transient_token_address(&mut test_runner, &user, trader_component);
+
+    let orders = vec![
+        (trader_component, create_global_id(nft_address, 0), dec!(50)),
+        (trader_component, create_global_id(nft_address, 1), dec!(150)),
+    ];
+
+    // 20 in royalties clears one minimum of 12, but not one for each of the two NFTs
+    try_purchase_multi_royalty_nft(
+        &mut test_runner,
+        &buyer,
+        orders.clone(),
+        dec!(200),
+        marketplace_component,
+        trader_component,
+        None,
+        transient_token_address,
+    )
+    .expect_commit_failure();
+
+    set_minimum_royalty_amount(
+        &mut test_runner,
+        &user,
+        royalty_nft_component,
+        creator_key,
+        XRD,
+        dec!(10),
+    );
+
+    purchase_multi_royalty_nft(
+        &mut test_runner,
+        &buyer,
+        orders,
+        dec!(200),
+        marketplace_component,
+        trader_component,
+        None,
+        transient_token_address,
+    );
+}
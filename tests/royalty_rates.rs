@@ -0,0 +1,625 @@
+use scrypto_test::prelude::*;
+mod common;
+mod creator_manifests;
+mod marketplace_manifests;
+mod misc_manifests;
+mod scenario_manifests;
+mod trader_manifests;
+use common::*;
+use creator_manifests::*;
+use marketplace_manifests::*;
+use misc_manifests::*;
+use scenario_manifests::*;
+use trader_manifests::*;
+
+const ONE_DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+fn band(from_price: Decimal, royalty_percent: Decimal) -> RoyaltyBand {
+    RoyaltyBand {
+        from_price,
+        royalty_percent,
+    }
+}
+
+#[test]
+fn royalty_bands_stay_within_the_maximum_and_respect_the_lock() {
+    let (
+        mut test_runner,
+        user,
+        TestCollection {
+            component: royalty_nft_component,
+            creator_key,
+            ..
+        },
+    ) = setup_royalty_nft();
+
+    let bands = vec![
+        band(dec!(0), dec!(0.1)),
+        band(dec!(100), dec!(0.07)),
+        band(dec!(1000), dec!(0.05)),
+    ];
+
+    // above the maximum royalty percentage of the default config
+    set_royalty_bands(
+        &mut test_runner,
+        &user,
+        royalty_nft_component,
+        creator_key,
+        XRD,
+        vec![band(dec!(0), dec!(0.6))],
+    )
+    .expect_commit_failure();
+
+    // must start from 0
+    set_royalty_bands(
+        &mut test_runner,
+        &user,
+        royalty_nft_component,
+        creator_key,
+        XRD,
+        vec![band(dec!(100), dec!(0.07))],
+    )
+    .expect_commit_failure();
+
+    // out of order
+    set_royalty_bands(
+        &mut test_runner,
+        &user,
+        royalty_nft_component,
+        creator_key,
+        XRD,
+        vec![band(dec!(0), dec!(0.1)), band(dec!(0), dec!(0.05))],
+    )
+    .expect_commit_failure();
+
+    set_royalty_bands(
+        &mut test_runner,
+        &user,
+        royalty_nft_component,
+        creator_key,
+        XRD,
+        bands,
+    )
+    .expect_commit_success();
+
+    let stored = get_royalty_bands(&mut test_runner, &user, royalty_nft_component, XRD).unwrap();
+
+    assert_eq!(stored.len(), 3);
+    assert_eq!(stored[1].from_price, dec!(100));
+    assert_eq!(stored[1].royalty_percent, dec!(0.07));
+
+    assert!(get_royalty_bands(&mut test_runner, &user, royalty_nft_component, FAUCET).is_none());
+
+    lock_royalty_configuration(&mut test_runner, &user, royalty_nft_component, creator_key);
+
+    set_royalty_bands(
+        &mut test_runner,
+        &user,
+        royalty_nft_component,
+        creator_key,
+        XRD,
+        vec![band(dec!(0), dec!(0.01))],
+    )
+    .expect_commit_failure();
+}
+
+#[test]
+fn flat_royalties_respect_the_lock() {
+    let (
+        mut test_runner,
+        user,
+        TestCollection {
+            component: royalty_nft_component,
+            creator_key,
+            ..
+        },
+    ) = setup_royalty_nft();
+
+    set_flat_royalty(
+        &mut test_runner,
+        &user,
+        royalty_nft_component,
+        creator_key,
+        XRD,
+        FlatRoyalty {
+            amount: dec!(0),
+            greater_of_percent: false,
+        },
+    )
+    .expect_commit_failure();
+
+    set_flat_royalty(
+        &mut test_runner,
+        &user,
+        royalty_nft_component,
+        creator_key,
+        XRD,
+        FlatRoyalty {
+            amount: dec!(5),
+            greater_of_percent: true,
+        },
+    )
+    .expect_commit_success();
+
+    let flat_royalty =
+        get_flat_royalty(&mut test_runner, &user, royalty_nft_component, XRD).unwrap();
+
+    assert_eq!(flat_royalty.amount, dec!(5));
+    assert!(flat_royalty.greater_of_percent);
+
+    lock_royalty_configuration(&mut test_runner, &user, royalty_nft_component, creator_key);
+
+    set_flat_royalty(
+        &mut test_runner,
+        &user,
+        royalty_nft_component,
+        creator_key,
+        XRD,
+        FlatRoyalty {
+            amount: dec!(1),
+            greater_of_percent: false,
+        },
+    )
+    .expect_commit_failure();
+}
+
+#[test]
+fn royalty_splits_are_capped_and_respect_the_lock() {
+    let (
+        mut test_runner,
+        user,
+        TestCollection {
+            component: royalty_nft_component,
+            creator_key,
+            ..
+        },
+    ) = setup_royalty_nft();
+
+    let artist = make_user(&mut test_runner, Some("artist"));
+
+    // more than 100%
+    set_royalty_splits(
+        &mut test_runner,
+        &user,
+        royalty_nft_component,
+        creator_key,
+        vec![
+            RoyaltySplit {
+                beneficiary: artist.account,
+                basis_points: 6000,
+            },
+            RoyaltySplit {
+                beneficiary: user.account,
+                basis_points: 5000,
+            },
+        ],
+        SplitPayout::Locker,
+    )
+    .expect_commit_failure();
+
+    set_royalty_splits(
+        &mut test_runner,
+        &user,
+        royalty_nft_component,
+        creator_key,
+        vec![RoyaltySplit {
+            beneficiary: artist.account,
+            basis_points: 2500,
+        }],
+        SplitPayout::Accrue,
+    )
+    .expect_commit_success();
+
+    let (splits, payout) = get_royalty_splits(&mut test_runner, &user, royalty_nft_component);
+
+    assert_eq!(splits.len(), 1);
+    assert_eq!(splits[0].beneficiary, artist.account);
+    assert_eq!(splits[0].basis_points, 2500);
+    assert_eq!(payout, SplitPayout::Accrue);
+
+    lock_royalty_configuration(&mut test_runner, &user, royalty_nft_component, creator_key);
+
+    set_royalty_splits(
+        &mut test_runner,
+        &user,
+        royalty_nft_component,
+        creator_key,
+        vec![],
+        SplitPayout::Locker,
+    )
+    .expect_commit_failure();
+}
+
+#[test]
+fn currency_royalty_percents_override_permitted_currencies() {
+    let (
+        mut test_runner,
+        user,
+        TestCollection {
+            component: royalty_nft_component,
+            creator_key,
+            ..
+        },
+    ) = setup_royalty_nft();
+
+    // currency limits are off
+    set_currency_royalty_percent(
+        &mut test_runner,
+        &user,
+        royalty_nft_component,
+        creator_key,
+        FAUCET,
+        dec!(0.02),
+    )
+    .expect_commit_failure();
+
+    restrict_currencies_true(&mut test_runner, &user, royalty_nft_component, creator_key);
+
+    add_permitted_currency(&mut test_runner, &user, royalty_nft_component, creator_key, XRD);
+
+    add_permitted_currency(&mut test_runner, &user, royalty_nft_component, creator_key, FAUCET);
+
+    set_currency_royalty_percent(
+        &mut test_runner,
+        &user,
+        royalty_nft_component,
+        creator_key,
+        FAUCET,
+        dec!(0.02),
+    )
+    .expect_commit_success();
+
+    assert_eq!(
+        get_currency_royalty_percent(&mut test_runner, &user, royalty_nft_component, FAUCET),
+        dec!(0.02)
+    );
+
+    assert_eq!(
+        get_currency_royalty_percent(&mut test_runner, &user, royalty_nft_component, XRD),
+        dec!(0.1)
+    );
+
+    // removing the currency drops its override
+    remove_permitted_currency(&mut test_runner, &user, royalty_nft_component, creator_key, FAUCET);
+
+    assert_eq!(
+        get_currency_royalty_percent(&mut test_runner, &user, royalty_nft_component, FAUCET),
+        dec!(0.1)
+    );
+}
+
+#[test]
+fn nft_royalty_percents_stay_within_the_maximum() {
+    let (
+        mut test_runner,
+        user,
+        TestCollection {
+            component: royalty_nft_component,
+            creator_key,
+            ..
+        },
+    ) = setup_royalty_nft();
+
+    let one_of_one = NonFungibleLocalId::integer(1);
+
+    set_nft_royalty_percent(
+        &mut test_runner,
+        &user,
+        royalty_nft_component,
+        creator_key,
+        one_of_one.clone(),
+        dec!(0.6),
+    )
+    .expect_commit_failure();
+
+    set_nft_royalty_percent(
+        &mut test_runner,
+        &user,
+        royalty_nft_component,
+        creator_key,
+        one_of_one.clone(),
+        dec!(0.25),
+    )
+    .expect_commit_success();
+
+    assert_eq!(
+        get_nft_royalty_percent(
+            &mut test_runner,
+            &user,
+            royalty_nft_component,
+            one_of_one.clone()
+        ),
+        Some(dec!(0.25))
+    );
+
+    assert_eq!(
+        get_nft_royalty_percent(
+            &mut test_runner,
+            &user,
+            royalty_nft_component,
+            NonFungibleLocalId::integer(2)
+        ),
+        None
+    );
+
+    lock_royalty_configuration(&mut test_runner, &user, royalty_nft_component, creator_key);
+
+    set_nft_royalty_percent(
+        &mut test_runner,
+        &user,
+        royalty_nft_component,
+        creator_key,
+        one_of_one,
+        dec!(0.3),
+    )
+    .expect_commit_failure();
+}
+
+#[test]
+fn minimum_royalties_do_not_need_currency_restrictions() {
+    let (
+        mut test_runner,
+        user,
+        TestCollection {
+            component: royalty_nft_component,
+            creator_key,
+            ..
+        },
+    ) = setup_royalty_nft();
+
+    enable_minimum_royalties(&mut test_runner, &user, royalty_nft_component, creator_key);
+
+    set_minimum_royalty_amount(
+        &mut test_runner,
+        &user,
+        royalty_nft_component,
+        creator_key,
+        XRD,
+        dec!(1),
+    );
+
+    remove_minimum_royalty_amount(
+        &mut test_runner,
+        &user,
+        royalty_nft_component,
+        creator_key,
+        XRD,
+    );
+}
+
+#[test]
+fn royalty_increases_wait_for_the_change_delay() {
+    let (
+        mut test_runner,
+        user,
+        TestCollection {
+            component: royalty_nft_component,
+            creator_key,
+            ..
+        },
+    ) = setup_royalty_nft();
+
+    let starting_percent =
+        get_currency_royalty_percent(&mut test_runner, &user, royalty_nft_component, XRD);
+
+    set_royalty_change_delay(
+        &mut test_runner,
+        &user,
+        royalty_nft_component,
+        creator_key,
+        24 * 60 * 60,
+    )
+    .expect_commit_success();
+
+    change_royalty_percentage_fee(
+        &mut test_runner,
+        &user,
+        royalty_nft_component,
+        creator_key,
+        starting_percent + dec!(0.01),
+    );
+
+    // the increase is only announced
+    let pending = get_pending_royalty_changes(&mut test_runner, &user, royalty_nft_component);
+    assert_eq!(pending.len(), 1);
+    assert_eq!(
+        pending[0].change,
+        RoyaltyChange::RoyaltyPercent(starting_percent + dec!(0.01))
+    );
+    assert_eq!(
+        get_currency_royalty_percent(&mut test_runner, &user, royalty_nft_component, XRD),
+        starting_percent
+    );
+
+    test_runner.advance_to_round_at_timestamp(Round::of(2), 2 * ONE_DAY_MS);
+
+    apply_pending_royalty_changes(&mut test_runner, &user, royalty_nft_component);
+
+    assert!(get_pending_royalty_changes(&mut test_runner, &user, royalty_nft_component).is_empty());
+    assert_eq!(
+        get_currency_royalty_percent(&mut test_runner, &user, royalty_nft_component, XRD),
+        starting_percent + dec!(0.01)
+    );
+
+    // decreases apply straight away
+    change_royalty_percentage_fee(
+        &mut test_runner,
+        &user,
+        royalty_nft_component,
+        creator_key,
+        starting_percent,
+    );
+
+    assert_eq!(
+        get_currency_royalty_percent(&mut test_runner, &user, royalty_nft_component, XRD),
+        starting_percent
+    );
+    // bands that charge more at some price and restricting currencies wait as well
+    let raised_bands = vec![
+        band(dec!(0), starting_percent),
+        band(dec!(100), starting_percent + dec!(0.01)),
+    ];
+
+    set_royalty_bands(
+        &mut test_runner,
+        &user,
+        royalty_nft_component,
+        creator_key,
+        XRD,
+        raised_bands.clone(),
+    )
+    .expect_commit_success();
+
+    restrict_currencies_true(&mut test_runner, &user, royalty_nft_component, creator_key);
+
+    let pending = get_pending_royalty_changes(&mut test_runner, &user, royalty_nft_component);
+    assert_eq!(pending.len(), 2);
+    assert_eq!(
+        pending[0].change,
+        RoyaltyChange::RoyaltyBands(XRD, raised_bands.clone())
+    );
+    assert_eq!(pending[1].change, RoyaltyChange::LimitCurrencies);
+    assert!(get_royalty_bands(&mut test_runner, &user, royalty_nft_component, XRD).is_none());
+
+    test_runner.advance_to_round_at_timestamp(Round::of(3), 4 * ONE_DAY_MS);
+
+    apply_pending_royalty_changes(&mut test_runner, &user, royalty_nft_component);
+
+    assert_eq!(
+        get_royalty_bands(&mut test_runner, &user, royalty_nft_component, XRD),
+        Some(raised_bands)
+    );
+
+    // bands that charge no more at any price apply straight away
+    let lowered_bands = vec![band(dec!(0), starting_percent)];
+
+    set_royalty_bands(
+        &mut test_runner,
+        &user,
+        royalty_nft_component,
+        creator_key,
+        XRD,
+        lowered_bands.clone(),
+    )
+    .expect_commit_success();
+
+    assert!(get_pending_royalty_changes(&mut test_runner, &user, royalty_nft_component).is_empty());
+    assert_eq!(
+        get_royalty_bands(&mut test_runner, &user, royalty_nft_component, XRD),
+        Some(lowered_bands)
+    );
+}
+
+#[test]
+fn pending_royalty_changes_are_cancelled_by_id() {
+    let (
+        mut test_runner,
+        user,
+        TestCollection {
+            component: royalty_nft_component,
+            creator_key,
+            ..
+        },
+    ) = setup_royalty_nft();
+
+    let starting_percent =
+        get_currency_royalty_percent(&mut test_runner, &user, royalty_nft_component, XRD);
+
+    set_royalty_change_delay(
+        &mut test_runner,
+        &user,
+        royalty_nft_component,
+        creator_key,
+        24 * 60 * 60,
+    )
+    .expect_commit_success();
+
+    change_royalty_percentage_fee(
+        &mut test_runner,
+        &user,
+        royalty_nft_component,
+        creator_key,
+        starting_percent + dec!(0.01),
+    );
+
+    test_runner.advance_to_round_at_timestamp(Round::of(2), ONE_DAY_MS / 2);
+
+    restrict_currencies_true(&mut test_runner, &user, royalty_nft_component, creator_key);
+
+    let pending = get_pending_royalty_changes(&mut test_runner, &user, royalty_nft_component);
+    assert_eq!(pending.len(), 2);
+    assert_eq!(pending[1].id, 1);
+    assert_eq!(pending[1].change, RoyaltyChange::LimitCurrencies);
+
+    // applying the raise moves the currency limit to the front of the list, but its id stays the same
+    test_runner.advance_to_round_at_timestamp(Round::of(3), ONE_DAY_MS * 5 / 4);
+
+    apply_pending_royalty_changes(&mut test_runner, &user, royalty_nft_component);
+
+    try_cancel_pending_royalty_change(&mut test_runner, &user, royalty_nft_component, creator_key, 1)
+        .expect_commit_success();
+
+    assert!(get_pending_royalty_changes(&mut test_runner, &user, royalty_nft_component).is_empty());
+
+    // the raise has already applied, so there's nothing left to cancel under its id
+    try_cancel_pending_royalty_change(&mut test_runner, &user, royalty_nft_component, creator_key, 0)
+        .expect_commit_failure();
+
+    assert_eq!(
+        get_currency_royalty_percent(&mut test_runner, &user, royalty_nft_component, XRD),
+        starting_percent + dec!(0.01)
+    );
+}
+
+#[test]
+fn removing_or_locking_the_config_leaves_nothing_pending() {
+    let (
+        mut test_runner,
+        user,
+        TestCollection {
+            component: royalty_nft_component,
+            creator_key,
+            ..
+        },
+    ) = setup_royalty_nft();
+
+    let starting_percent =
+        get_currency_royalty_percent(&mut test_runner, &user, royalty_nft_component, XRD);
+
+    set_royalty_change_delay(
+        &mut test_runner,
+        &user,
+        royalty_nft_component,
+        creator_key,
+        24 * 60 * 60,
+    )
+    .expect_commit_success();
+
+    change_royalty_percentage_fee(
+        &mut test_runner,
+        &user,
+        royalty_nft_component,
+        creator_key,
+        starting_percent + dec!(0.01),
+    );
+
+    // a scheduled raise would keep changing a locked configuration
+    try_lock_royalty_configuration(&mut test_runner, &user, royalty_nft_component, creator_key)
+        .expect_commit_failure();
+
+    remove_royalty_config(&mut test_runner, &user, royalty_nft_component, creator_key);
+
+    assert!(get_pending_royalty_changes(&mut test_runner, &user, royalty_nft_component).is_empty());
+
+    // the raise scheduled before the removal never comes back
+    test_runner.advance_to_round_at_timestamp(Round::of(2), 2 * ONE_DAY_MS);
+
+    apply_pending_royalty_changes(&mut test_runner, &user, royalty_nft_component);
+
+    assert_eq!(
+        get_currency_royalty_percent(&mut test_runner, &user, royalty_nft_component, XRD),
+        dec!(0)
+    );
+
+    lock_royalty_configuration(&mut test_runner, &user, royalty_nft_component, creator_key);
+}
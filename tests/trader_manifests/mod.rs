@@ -3,6 +3,13 @@ use scrypto_test::{prelude::*, utils::dump_manifest_to_file_system};
 
 use crate::common::*;
 
+#[derive(ScryptoSbor, ManifestEncode, ManifestDecode, Clone, PartialEq, Eq, Debug)]
+pub enum BlockedBuyer {
+    Permission(ResourceAddress),
+    Badge(NonFungibleGlobalId),
+    Recipient(ComponentAddress),
+}
+
 pub fn trader_auth_key(
     test_runner: &mut DefaultLedgerSimulator,
     user: &User,
@@ -45,6 +52,36 @@ pub fn list_royalty_nft(
     price: Decimal,
     currency: Option<ResourceAddress>,
     auth_buyers: Vec<ResourceAddress>,
+) {
+    list_royalty_nft_from(
+        test_runner,
+        user,
+        trader_component,
+        trader_key_resource,
+        trader_key_local,
+        nft_address,
+        nft_local_id,
+        price,
+        currency,
+        auth_buyers,
+        None,
+        false,
+    );
+}
+
+pub fn list_royalty_nft_from(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    trader_component: ComponentAddress,
+    trader_key_resource: ResourceAddress,
+    trader_key_local: NonFungibleLocalId,
+    nft_address: ResourceAddress,
+    nft_local_id: NonFungibleLocalId,
+    price: Decimal,
+    currency: Option<ResourceAddress>,
+    auth_buyers: Vec<ResourceAddress>,
+    start_time: Option<Instant>,
+    registered_marketplaces: bool,
 ) {
     let sell_currency: ResourceAddress;
 
@@ -71,7 +108,14 @@ pub fn list_royalty_nft(
             builder.call_method(
                 trader_component,
                 "royal_list",
-                manifest_args!(lookup.bucket("listing"), price, sell_currency, auth_buyers,),
+                manifest_args!(
+                    lookup.bucket("listing"),
+                    price,
+                    sell_currency,
+                    auth_buyers,
+                    start_time,
+                    registered_marketplaces,
+                ),
             )
         })
         .call_method(
@@ -129,17 +173,54 @@ pub fn purchase_multi_listing(
     amount: Decimal,
     cost_receipt: bool,
 ) {
-    // let nft_ids: IndexSet<NonFungibleLocalId> = orders
-    //     .iter()
-    //     .map(|(_, nfgid, _)| nfgid.clone().into_parts().1.into())
-    //     .collect();
+    let receipt =
+        try_purchase_multi_listing(test_runner, user, marketplace_component, orders, amount);
+
+    if !receipt.is_commit_success() {
+        println!("{:?}", receipt);
+        panic!("TRANSACTION FAIL");
+    };
 
+    if cost_receipt {
+        println!(
+            "{:?}",
+            format_cost_breakdown(&receipt.fee_summary, receipt.fee_details.as_ref().unwrap())
+        );
+    }
+}
+
+pub fn try_purchase_multi_listing(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    marketplace_component: ComponentAddress,
+    orders: Vec<(ComponentAddress, NonFungibleGlobalId, Decimal)>,
+    amount: Decimal,
+) -> TransactionReceipt {
+    try_purchase_multi_listing_for(
+        test_runner,
+        user,
+        marketplace_component,
+        orders,
+        amount,
+        user.account,
+    )
+}
+
+/// Purchases on behalf of another account - the NFTs still come back to the paying user.
+pub fn try_purchase_multi_listing_for(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    marketplace_component: ComponentAddress,
+    orders: Vec<(ComponentAddress, NonFungibleGlobalId, Decimal)>,
+    amount: Decimal,
+    recipient: ComponentAddress,
+) -> TransactionReceipt {
     let manifest = ManifestBuilder::new()
         .lock_fee_from_faucet()
         .call_method(user.account, "withdraw", manifest_args!(XRD, amount))
         .take_all_from_worktop(XRD, "payment")
         .call_method_with_name_lookup(marketplace_component, "multi_listing_purchase", |lookup| {
-            manifest_args!(orders, lookup.bucket("payment"))
+            manifest_args!(orders, lookup.bucket("payment"), recipient)
         })
         .call_method(
             user.account,
@@ -148,39 +229,50 @@ pub fn purchase_multi_listing(
         )
         .build();
 
-    let receipt = test_runner.execute_manifest(
+    test_runner.execute_manifest(
         manifest,
         vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    )
+}
+
+pub fn purchase_multi_royalty_nft(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    orders: Vec<(ComponentAddress, NonFungibleGlobalId, Decimal)>,
+    amount: Decimal,
+    marketplace_component: ComponentAddress,
+    trader_component: ComponentAddress,
+    // nfgids: Vec<NonFungibleGlobalId>,
+    currency: Option<ResourceAddress>,
+    transient_token_address: ResourceAddress,
+) {
+    let receipt = try_purchase_multi_royalty_nft(
+        test_runner,
+        user,
+        orders,
+        amount,
+        marketplace_component,
+        trader_component,
+        currency,
+        transient_token_address,
     );
 
     if !receipt.is_commit_success() {
         println!("{:?}", receipt);
         panic!("TRANSACTION FAIL");
-    };
-
-    if cost_receipt {
-        println!(
-            "{:?}",
-            format_cost_breakdown(&receipt.fee_summary, receipt.fee_details.as_ref().unwrap())
-        );
     }
-
-    // println!("hit")
-    // println!("{:?}", receipt);
-    // println!("{:?}", receipt.costing_parameters)
 }
 
-pub fn purchase_multi_royalty_nft(
+pub fn try_purchase_multi_royalty_nft(
     test_runner: &mut DefaultLedgerSimulator,
     user: &User,
     orders: Vec<(ComponentAddress, NonFungibleGlobalId, Decimal)>,
     amount: Decimal,
     marketplace_component: ComponentAddress,
     trader_component: ComponentAddress,
-    // nfgids: Vec<NonFungibleGlobalId>,
     currency: Option<ResourceAddress>,
     transient_token_address: ResourceAddress,
-) {
+) -> TransactionReceipt {
     let buy_currency: ResourceAddress;
 
     if currency.is_some() {
@@ -226,9 +318,31 @@ pub fn purchase_multi_royalty_nft(
         )
         .build();
 
-    let receipt = test_runner.execute_manifest(
+    test_runner.execute_manifest(
         manifest,
         vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    )
+}
+
+pub fn purchase_royalty_nft(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    marketplace_component: ComponentAddress,
+    trader_component: ComponentAddress,
+    nfgid: NonFungibleGlobalId,
+    payment: Decimal,
+    currency: Option<ResourceAddress>,
+    transient_token_address: ResourceAddress,
+) {
+    let receipt = try_purchase_royalty_nft(
+        test_runner,
+        user,
+        marketplace_component,
+        trader_component,
+        nfgid,
+        payment,
+        currency,
+        transient_token_address,
     );
 
     if !receipt.is_commit_success() {
@@ -237,7 +351,7 @@ pub fn purchase_multi_royalty_nft(
     }
 }
 
-pub fn purchase_royalty_nft(
+pub fn try_purchase_royalty_nft(
     test_runner: &mut DefaultLedgerSimulator,
     user: &User,
     marketplace_component: ComponentAddress,
@@ -246,7 +360,7 @@ pub fn purchase_royalty_nft(
     payment: Decimal,
     currency: Option<ResourceAddress>,
     transient_token_address: ResourceAddress,
-) {
+) -> TransactionReceipt {
     let buy_currency: ResourceAddress;
 
     if currency.is_some() {
@@ -287,15 +401,10 @@ pub fn purchase_royalty_nft(
         })
         .build();
 
-    let receipt = test_runner.execute_manifest(
+    test_runner.execute_manifest(
         manifest,
         vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
-    );
-
-    if !receipt.is_commit_success() {
-        println!("{:?}", receipt);
-        panic!("TRANSACTION FAIL");
-    }
+    )
 }
 
 pub fn cancel_royal_listing(
@@ -338,7 +447,7 @@ pub fn same_owner_royal_transfer(
     nft_address: ResourceAddress,
     nft_local_id: NonFungibleLocalId,
     other_account: ComponentAddress,
-) {
+) -> TransactionReceipt {
     let manifest = ManifestBuilder::new()
         .lock_fee_from_faucet()
         .call_method(
@@ -350,7 +459,7 @@ pub fn same_owner_royal_transfer(
         .with_name_lookup(|builder, lookup| {
             builder.call_method(
                 trader_component,
-                "royal_transfer",
+                "same_owner_royal_transfer",
                 manifest_args!(lookup.bucket("transfer"), other_account),
             )
         })
@@ -365,6 +474,8 @@ pub fn same_owner_royal_transfer(
         println!("{:?}", receipt);
         panic!("TRANSACTION FAIL");
     }
+
+    receipt
 }
 
 pub fn transfer_royal_nft_to_component(
@@ -375,7 +486,7 @@ pub fn transfer_royal_nft_to_component(
     nft_local_id: NonFungibleLocalId,
     other_component: ComponentAddress,
     custom_method: String,
-) {
+) -> TransactionReceipt {
     let manifest = ManifestBuilder::new()
         .lock_fee_from_faucet()
         .call_method(
@@ -407,6 +518,8 @@ pub fn transfer_royal_nft_to_component(
         println!("{:?}", receipt);
         panic!("TRANSACTION FAIL");
     }
+
+    receipt
 }
 
 pub fn list(
@@ -420,6 +533,34 @@ pub fn list(
     price: Decimal,
     currency: Option<ResourceAddress>,
     auth_buyers: Vec<ResourceAddress>,
+) {
+    list_from(
+        test_runner,
+        user,
+        trader_component,
+        trader_key_resource,
+        trader_key_local,
+        nft_address,
+        nft_local_id,
+        price,
+        currency,
+        auth_buyers,
+        false,
+    );
+}
+
+pub fn list_from(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    trader_component: ComponentAddress,
+    trader_key_resource: ResourceAddress,
+    trader_key_local: NonFungibleLocalId,
+    nft_address: ResourceAddress,
+    nft_local_id: NonFungibleLocalId,
+    price: Decimal,
+    currency: Option<ResourceAddress>,
+    auth_buyers: Vec<ResourceAddress>,
+    registered_marketplaces: bool,
 ) {
     let sell_currency: ResourceAddress;
 
@@ -446,7 +587,14 @@ pub fn list(
             builder.call_method(
                 trader_component,
                 "list",
-                manifest_args!(lookup.bucket("listing"), sell_currency, price, auth_buyers,),
+                manifest_args!(
+                    lookup.bucket("listing"),
+                    sell_currency,
+                    price,
+                    auth_buyers,
+                    None::<Instant>,
+                    registered_marketplaces,
+                ),
             )
         })
         .build();
@@ -519,7 +667,9 @@ pub fn multi_list(
                     listings,
                     sell_currency,
                     auth_buyers,
-                    lookup.bucket("listing")
+                    lookup.bucket("listing"),
+                    None::<Instant>,
+                    false
                 ),
             )
         })
@@ -592,7 +742,9 @@ pub fn royal_multi_list(
                     listings,
                     sell_currency,
                     auth_buyers,
-                    lookup.bucket("listing")
+                    lookup.bucket("listing"),
+                    None::<Instant>,
+                    false
                 ),
             )
         })
@@ -663,3 +815,421 @@ pub fn purchase_listing(
         panic!("TRANSACTION FAIL");
     }
 }
+
+pub fn close_outpost(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    trader_component: ComponentAddress,
+    trader_key_resource: ResourceAddress,
+    trader_key_local: NonFungibleLocalId,
+    migrate_to: Option<ComponentAddress>,
+) {
+    let receipt = try_close_outpost(
+        test_runner,
+        user,
+        trader_component,
+        trader_key_resource,
+        trader_key_local,
+        migrate_to,
+    );
+
+    if !receipt.is_commit_success() {
+        println!("{:?}", receipt);
+        panic!("TRANSACTION FAIL");
+    }
+}
+
+pub fn try_close_outpost(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    trader_component: ComponentAddress,
+    trader_key_resource: ResourceAddress,
+    trader_key_local: NonFungibleLocalId,
+    migrate_to: Option<ComponentAddress>,
+) -> TransactionReceipt {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(
+            user.account,
+            "create_proof_of_non_fungibles",
+            manifest_args!(trader_key_resource, indexset![trader_key_local.clone()]),
+        )
+        .call_method(
+            trader_component,
+            "close_outpost",
+            manifest_args!(migrate_to),
+        )
+        .build();
+
+    test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    )
+}
+
+pub fn block_buyer(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    trader_component: ComponentAddress,
+    trader_key_resource: ResourceAddress,
+    trader_key_local: NonFungibleLocalId,
+    buyer: BlockedBuyer,
+    block: bool,
+) {
+    let method = if block { "block_buyer" } else { "unblock_buyer" };
+
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(
+            user.account,
+            "create_proof_of_non_fungibles",
+            manifest_args!(trader_key_resource, indexset![trader_key_local.clone()]),
+        )
+        .call_method(trader_component, method, manifest_args!(buyer))
+        .build();
+
+    let receipt = test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    );
+
+    if !receipt.is_commit_success() {
+        println!("{:?}", receipt);
+        panic!("TRANSACTION FAIL");
+    }
+}
+
+pub fn is_buyer_blocked(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    trader_component: ComponentAddress,
+    buyer: BlockedBuyer,
+) -> bool {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(trader_component, "is_buyer_blocked", manifest_args!(buyer))
+        .build();
+
+    let receipt = test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    );
+
+    receipt.expect_commit(true).output(1)
+}
+
+pub fn try_list_rental(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    trader_component: ComponentAddress,
+    trader_key_resource: ResourceAddress,
+    trader_key_local: NonFungibleLocalId,
+    nft_address: ResourceAddress,
+    nft_local_id: NonFungibleLocalId,
+    fee: Decimal,
+    duration_seconds: i64,
+    permissions: Vec<ResourceAddress>,
+) -> TransactionReceipt {
+    try_list_rental_from(
+        test_runner,
+        user,
+        trader_component,
+        trader_key_resource,
+        trader_key_local,
+        nft_address,
+        nft_local_id,
+        fee,
+        duration_seconds,
+        permissions,
+        false,
+    )
+}
+
+pub fn try_list_rental_from(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    trader_component: ComponentAddress,
+    trader_key_resource: ResourceAddress,
+    trader_key_local: NonFungibleLocalId,
+    nft_address: ResourceAddress,
+    nft_local_id: NonFungibleLocalId,
+    fee: Decimal,
+    duration_seconds: i64,
+    permissions: Vec<ResourceAddress>,
+    registered_marketplaces: bool,
+) -> TransactionReceipt {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(
+            user.account,
+            "create_proof_of_non_fungibles",
+            manifest_args!(trader_key_resource, indexset![trader_key_local.clone()]),
+        )
+        .call_method(
+            user.account,
+            "withdraw_non_fungibles",
+            manifest_args!(nft_address, indexset![nft_local_id.clone()]),
+        )
+        .take_all_from_worktop(nft_address, "rental")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(
+                trader_component,
+                "list_rental",
+                manifest_args!(
+                    lookup.bucket("rental"),
+                    fee,
+                    XRD,
+                    duration_seconds,
+                    permissions,
+                    registered_marketplaces,
+                ),
+            )
+        })
+        .build();
+
+    test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    )
+}
+
+pub fn list_rental(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    trader_component: ComponentAddress,
+    trader_key_resource: ResourceAddress,
+    trader_key_local: NonFungibleLocalId,
+    nft_address: ResourceAddress,
+    nft_local_id: NonFungibleLocalId,
+    fee: Decimal,
+    duration_seconds: i64,
+    permissions: Vec<ResourceAddress>,
+) {
+    let receipt = try_list_rental(
+        test_runner,
+        user,
+        trader_component,
+        trader_key_resource,
+        trader_key_local,
+        nft_address,
+        nft_local_id,
+        fee,
+        duration_seconds,
+        permissions,
+    );
+
+    if !receipt.is_commit_success() {
+        println!("{:?}", receipt);
+        panic!("TRANSACTION FAIL");
+    }
+}
+
+pub fn try_rent(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    trader_component: ComponentAddress,
+    nfgid: NonFungibleGlobalId,
+    fee: Decimal,
+    permission: ResourceAddress,
+) -> TransactionReceipt {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .create_proof_from_account_of_amount(user.account, permission, dec!(1))
+        .pop_from_auth_zone("permission")
+        .call_method(user.account, "withdraw", manifest_args!(XRD, fee))
+        .take_all_from_worktop(XRD, "payment")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(
+                trader_component,
+                "rent",
+                manifest_args!(
+                    nfgid,
+                    lookup.bucket("payment"),
+                    lookup.proof("permission"),
+                    user.account,
+                ),
+            )
+        })
+        .call_method(
+            user.account,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+
+    test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    )
+}
+
+pub fn offer_loan(
+    test_runner: &mut DefaultLedgerSimulator,
+    lender: &User,
+    trader_component: ComponentAddress,
+    principal: Decimal,
+    repayment_amount: Decimal,
+    duration_seconds: i64,
+    collateral: Vec<(NonFungibleGlobalId, Decimal)>,
+) {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(lender.account, "withdraw", manifest_args!(XRD, principal))
+        .take_all_from_worktop(XRD, "principal")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(
+                trader_component,
+                "offer_loan",
+                manifest_args!(
+                    lookup.bucket("principal"),
+                    repayment_amount,
+                    duration_seconds,
+                    collateral,
+                    lender.account,
+                ),
+            )
+        })
+        .call_method(
+            lender.account,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+
+    let receipt = test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&lender.pubkey)],
+    );
+
+    if !receipt.is_commit_success() {
+        println!("{:?}", receipt);
+        panic!("TRANSACTION FAIL");
+    }
+}
+
+pub fn accept_loan(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    trader_component: ComponentAddress,
+    trader_key_resource: ResourceAddress,
+    trader_key_local: NonFungibleLocalId,
+    loan_id: u64,
+) {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(
+            user.account,
+            "create_proof_of_non_fungibles",
+            manifest_args!(trader_key_resource, indexset![trader_key_local.clone()]),
+        )
+        .call_method(trader_component, "accept_loan", manifest_args!(loan_id))
+        .call_method(
+            user.account,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+
+    let receipt = test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    );
+
+    if !receipt.is_commit_success() {
+        println!("{:?}", receipt);
+        panic!("TRANSACTION FAIL");
+    }
+}
+
+pub fn try_repay_loan(
+    test_runner: &mut DefaultLedgerSimulator,
+    user: &User,
+    trader_component: ComponentAddress,
+    trader_key_resource: ResourceAddress,
+    trader_key_local: NonFungibleLocalId,
+    loan_id: u64,
+    repayment: Decimal,
+) -> TransactionReceipt {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(
+            user.account,
+            "create_proof_of_non_fungibles",
+            manifest_args!(trader_key_resource, indexset![trader_key_local.clone()]),
+        )
+        .call_method(user.account, "withdraw", manifest_args!(XRD, repayment))
+        .take_all_from_worktop(XRD, "repayment")
+        .with_name_lookup(|builder, lookup| {
+            builder.call_method(
+                trader_component,
+                "repay_loan",
+                manifest_args!(loan_id, lookup.bucket("repayment")),
+            )
+        })
+        .build();
+
+    test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&user.pubkey)],
+    )
+}
+
+pub fn try_claim_loan(
+    test_runner: &mut DefaultLedgerSimulator,
+    lender: &User,
+    trader_component: ComponentAddress,
+    loan_id: u64,
+    royalty_payment: Option<Decimal>,
+) -> TransactionReceipt {
+    let manifest = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(trader_component, "loan_note_address", manifest_args!())
+        .build();
+
+    let receipt = test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&lender.pubkey)],
+    );
+
+    let loan_note_address: ResourceAddress = receipt.expect_commit(true).output(1);
+
+    let mut builder = ManifestBuilder::new()
+        .lock_fee_from_faucet()
+        .call_method(
+            lender.account,
+            "withdraw_non_fungibles",
+            manifest_args!(
+                loan_note_address,
+                indexset![NonFungibleLocalId::integer(loan_id)]
+            ),
+        )
+        .take_all_from_worktop(loan_note_address, "loan_note");
+
+    if let Some(amount) = royalty_payment {
+        builder = builder
+            .call_method(lender.account, "withdraw", manifest_args!(XRD, amount))
+            .take_all_from_worktop(XRD, "royalty_payment");
+    }
+
+    let manifest = builder
+        .with_name_lookup(|builder, lookup| {
+            let royalty_payment = royalty_payment.map(|_| lookup.bucket("royalty_payment"));
+
+            builder.call_method(
+                trader_component,
+                "claim_loan",
+                manifest_args!(lookup.bucket("loan_note"), royalty_payment),
+            )
+        })
+        .call_method(
+            lender.account,
+            "deposit_batch",
+            manifest_args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+
+    test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&lender.pubkey)],
+    )
+}